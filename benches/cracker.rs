@@ -0,0 +1,58 @@
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use fix_oxide::cracker::{crack_message, Crack};
+use fix_oxide::model::view::MessageView;
+use fix_oxide::model::BeginString;
+
+/// A market data snapshot with a few price levels, as a consumer would
+/// receive it from a venue
+fn snapshot() -> Bytes {
+    let mut msg =
+        String::from("8=FIX.4.4\x0135=W\x0134=12\x0149=VENUE\x0156=CLIENT\x0155=ABC\x01268=10\x01");
+    for level in 0..10 {
+        msg.push_str(&format!(
+            "269={}\x01270=10.{}\x01271={}\x01",
+            level % 2,
+            level,
+            100 * level
+        ));
+    }
+    Bytes::from(msg)
+}
+
+fn crack(c: &mut Criterion) {
+    let msg = snapshot();
+    let mut group = c.benchmark_group("snapshot");
+    group.throughput(Throughput::Bytes(msg.len() as u64));
+
+    group.bench_function("begin_string", |b| {
+        let msg = std::str::from_utf8(&msg).unwrap();
+        b.iter(|| BeginString::crack(black_box(msg)).unwrap());
+    });
+
+    group.bench_function("crack_message", |b| {
+        b.iter(|| {
+            let message = crack_message(black_box(&msg)).unwrap();
+            (
+                message.get_field(55).as_str().len(),
+                *message.get_field(270).as_decimal(),
+            )
+        });
+    });
+
+    group.bench_function("message_view", |b| {
+        b.iter(|| {
+            let view = MessageView::new(black_box(&msg)).unwrap();
+            (
+                view.get(55).unwrap().as_str().unwrap().len(),
+                view.get(270).unwrap().as_decimal().unwrap(),
+            )
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, crack);
+criterion_main!(benches);
@@ -0,0 +1,50 @@
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_decimal::Decimal;
+
+use fix_oxide::encoder;
+use fix_oxide::model::generated::fields::Field;
+use fix_oxide::model::message::Message;
+
+fn order() -> Message {
+    let mut msg = Message::of_type("D");
+    msg.set_field(Field::String(8, "FIX.4.4".to_string()));
+    msg.set_field(Field::String(49, "CLIENT".to_string()));
+    msg.set_field(Field::String(56, "VENUE".to_string()));
+    msg.set_field(Field::Int(34, 1234));
+    msg.set_field(Field::String(11, "order-000001".to_string()));
+    msg.set_field(Field::String(55, "ABC".to_string()));
+    msg.set_field(Field::Char(54, '1'));
+    msg.set_field(Field::Int(38, 100_000));
+    msg.set_field(Field::Decimal(44, Decimal::new(1_012_345, 4)));
+    msg.set_field(Field::Char(40, '2'));
+    msg
+}
+
+fn encode(c: &mut Criterion) {
+    let msg = order();
+    let mut group = c.benchmark_group("order");
+
+    group.bench_function("display_string", |b| {
+        b.iter(|| {
+            black_box(&msg)
+                .to_string()
+                .replace('|', "\x01")
+                .into_bytes()
+        });
+    });
+
+    group.bench_function("encode_reused_buffer", |b| {
+        let mut buf = BytesMut::with_capacity(1024);
+        b.iter(|| {
+            buf.clear();
+            encoder::encode(black_box(&msg), &mut buf);
+            buf.len()
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
@@ -4,6 +4,8 @@ use std::str::FromStr;
 
 fn main() {
     println!("cargo:rerun-if-changed=spec/generator.py");
+    println!("cargo:rerun-if-changed=spec/extensions");
+    println!("cargo:rerun-if-changed=spec/sbe");
 
     let dir = env!("CARGO_MANIFEST_DIR");
     const SPECS_DIR: &str = "spec";
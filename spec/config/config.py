@@ -72,9 +72,9 @@ def _init_enum_variant():
 def _init_parse_functions():
     return {
         'String': 'Field::String(tag, s_value.to_string())',
-        'Char': 'Field::Char(tag, str::parse::<char>(s_value).unwrap())',
-        'Decimal': 'Field::Decimal(tag, Decimal::from_str(s_value).unwrap())',
-        'Int': 'Field::Int(tag, str::parse::<i32>(s_value).unwrap())',
+        'Char': 'Field::Char(tag, str::parse::<char>(s_value).map_err(|_| ParseError::new(Some(tag), s_value, ValueType::Char, offset))?)',
+        'Decimal': 'Field::Decimal(tag, Decimal::from_str(s_value).map_err(|_| ParseError::new(Some(tag), s_value, ValueType::Decimal, offset))?)',
+        'Int': 'Field::Int(tag, str::parse::<i32>(s_value).map_err(|_| ParseError::new(Some(tag), s_value, ValueType::Int, offset))?)',
         'Currency': 'Field::String(tag, s_value.to_string())',
     }
 
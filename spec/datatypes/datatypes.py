@@ -1,3 +1,4 @@
+import typing
 from dataclasses import dataclass
 
 @dataclass
@@ -6,4 +7,55 @@ class Field:
     version: str
     tag: int
     name: str
-    type: str
\ No newline at end of file
+    type: str
+
+@dataclass
+class Message:
+    """FIX Message"""
+    version: str
+    name: str
+    msg_type: str
+    msg_cat: str
+
+@dataclass
+class SbeField:
+    """Fixed size field of an SBE message"""
+    tag: int
+    name: str
+    offset: int
+    # char, chars, int or decimal
+    kind: str
+    primitive: str
+    length: int
+    # decimals either have a constant exponent or carry it after the mantissa
+    exponent: typing.Optional[int]
+    # required, optional or constant
+    presence: str
+    constant: typing.Optional[str]
+
+@dataclass
+class SbeData:
+    """Variable length data field of an SBE message"""
+    tag: int
+    name: str
+    length_primitive: str
+
+@dataclass
+class SbeMessage:
+    """SBE message template"""
+    name: str
+    template_id: int
+    msg_type: str
+    block_length: int
+    fields: typing.List[SbeField]
+    data: typing.List[SbeData]
+
+@dataclass
+class SbeSchema:
+    """SBE message schema"""
+    module: str
+    package: str
+    id: int
+    version: int
+    byte_order: str
+    messages: typing.List[SbeMessage]
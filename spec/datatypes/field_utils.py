@@ -1,3 +1,5 @@
+import re
+
 from ..config.config import Config
 from ..datatypes.datatypes import Field
 
@@ -10,4 +12,11 @@ def unique_field_enum_variants(config: Config, fields: [Field]) -> [str]:
         if field_type not in _seen_field_types:
             _found_field_types.append(field.type)
         _seen_field_types.append(field_type)
-    return _found_field_types
\ No newline at end of file
+    return _found_field_types
+
+
+def to_snake_case(name: str) -> str:
+    """ NewOrderSingle -> new_order_single, RFQRequest -> rfq_request """
+    name = re.sub('([A-Z]+)([A-Z][a-z])', r'\1_\2', name)
+    name = re.sub('([a-z0-9])([A-Z])', r'\1_\2', name)
+    return name.lower()
@@ -12,7 +12,10 @@ from os import path, listdir
 # from spec.parser.parser import Parser
 from config.config import Config
 from spec.parser.parser import Parser
+from spec.parser.extension import extension_files_for, merge_extension
 from spec.writer.writer import Writer
+from spec.parser.sbe import SbeParser
+from spec.writer.sbe_writer import SbeWriter
 
 print(f"cargo:warning=Number of arguments: {len(sys.argv)}")
 print(f"cargo:warning=Argument List: {str(sys.argv)}")
@@ -20,6 +23,8 @@ print(f"cargo:warning=Argument List: {str(sys.argv)}")
 SPECS_DIR_NOT_FOUND=-1
 OUTPUT_DIR_NOT_FOUND=-2
 
+SBE_DIR = 'sbe'
+
 specs_path = sys.argv[1]
 if not path.exists(specs_path):
     exit(SPECS_DIR_NOT_FOUND)
@@ -37,12 +42,19 @@ def parse_and_write_fix_specs(config: Config, spec_file: str, spec_version: str)
     print(f"Spec version {spec_version} in {spec_file}")
 
     root = xml.parse(spec_file).getroot()
+    for extension_file in extension_files_for(spec_version):
+        print(f"cargo:warning=Applying {extension_file} to {spec_version}")
+        merge_extension(root, xml.parse(extension_file).getroot())
+
     parser = Parser(root, config, spec_version)
     parser.parse_fields()
+    parser.parse_messages()
 
     writer = Writer(output_path + '/' + spec_version + '.rs', config, import_fields=True)
     # writer.write_field_enum(parser.fields)
     writer.write_field_impls(spec_version, parser.fields)
+    writer.write_field_types(parser.all_fields)
+    writer.write_messages(spec_version, parser.messages)
     # writer.write_fieldset(parser.fields)
     writer.close()
 
@@ -50,6 +62,22 @@ def parse_and_write_fix_specs(config: Config, spec_file: str, spec_version: str)
     return parser
 
 
+def parse_and_write_sbe_schema(schema_file: str, module: str):
+    print(f"SBE schema {module} in {schema_file}")
+
+    schema = SbeParser(xml.parse(schema_file).getroot(), module).parse()
+    writer = SbeWriter(output_path + '/' + module + '.rs')
+    writer.write_schema(schema)
+    writer.close()
+
+
+def all_sbe_schema_files() -> [str]:
+    """ SBE schemas live in sbe/*.xml, e.g. sbe/orders.xml becomes model::generated::sbe_orders """
+    if not path.isdir(SBE_DIR):
+        return []
+    return [file for file in sorted(listdir(SBE_DIR)) if file.endswith('.xml')]
+
+
 def all_fix_spec_files() -> [str]:
     return [file for file in listdir('.') if looks_like_fix_specs(file)]
 
@@ -82,6 +110,11 @@ if __name__ == "__main__":
     # generate Field type + impls
     parse_and_write_fields(config, parsers)
 
+    # SBE message layouts
+    for schema_file in all_sbe_schema_files():
+        module = 'sbe_' + schema_file.split('.')[0].lower()
+        parse_and_write_sbe_schema(path.join(SBE_DIR, schema_file), module)
+
 
 
 
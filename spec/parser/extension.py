@@ -0,0 +1,63 @@
+from os import path, listdir
+from xml.etree import ElementTree as xml
+
+EXTENSIONS_DIR = 'extensions'
+
+
+def extension_files_for(spec_version: str, extensions_dir: str = EXTENSIONS_DIR) -> [str]:
+    """ Venue extensions for a spec live in extensions/<spec_version>/*.xml, e.g. extensions/fix44/venue.xml """
+    ext_dir = path.join(extensions_dir, spec_version)
+    if not path.isdir(ext_dir):
+        return []
+    return [path.join(ext_dir, file) for file in sorted(listdir(ext_dir)) if file.endswith('.xml')]
+
+
+def merge_extension(base: xml.Element, extension: xml.Element):
+    """ Layer an extension over a base spec, the same way DataDictionary::extend does at runtime """
+    _merge_definitions(base, extension, 'fields', 'field', 'number')
+    _merge_definitions(base, extension, 'components', 'component', 'name')
+    _merge_definitions(base, extension, 'messages', 'message', 'msgtype')
+    for section in ['header', 'trailer']:
+        ext_section = extension.find(section)
+        if ext_section is not None:
+            _merge_members(_find_or_create(base, section), ext_section)
+
+
+def _merge_definitions(base: xml.Element, extension: xml.Element, section: str, tag: str, key: str):
+    ext_section = extension.find(section)
+    if ext_section is None:
+        return
+
+    base_section = _find_or_create(base, section)
+    for ext_def in ext_section.findall(tag):
+        base_def = next((d for d in base_section.findall(tag) if d.get(key) == ext_def.get(key)), None)
+        if base_def is None:
+            base_section.append(ext_def)
+        elif tag == 'field' and ext_def.get('type') is None:
+            # only adds a FIXML abbreviation
+            if ext_def.get('abbr') is not None:
+                base_def.set('abbr', ext_def.get('abbr'))
+        elif tag == 'field':
+            # a field is redefined outright
+            base_section.remove(base_def)
+            base_section.append(ext_def)
+        else:
+            if ext_def.get('abbr') is not None:
+                base_def.set('abbr', ext_def.get('abbr'))
+            _merge_members(base_def, ext_def)
+
+
+def _merge_members(base: xml.Element, extension: xml.Element):
+    for member in extension:
+        existing = next((m for m in base if m.tag == member.tag and m.get('name') == member.get('name')), None)
+        if existing is None:
+            base.append(member)
+        else:
+            base[list(base).index(existing)] = member
+
+
+def _find_or_create(parent: xml.Element, tag: str) -> xml.Element:
+    child = parent.find(tag)
+    if child is None:
+        child = xml.SubElement(parent, tag)
+    return child
@@ -1,6 +1,6 @@
 from xml.etree import ElementTree as xml
 
-from spec.datatypes.datatypes import Field
+from spec.datatypes.datatypes import Field, Message
 from spec.config.config import Config
 
 
@@ -10,11 +10,22 @@ class Parser:
         self.config = config
         self.spec_version = spec_version
         self.fields = []
+        # every field, including those of types without a Field variant
+        self.all_fields = []
+        self.messages = []
 
     def parse_fields(self):
         for field in self.root.findall('fields/field'):
             field_num  = field.get('number')
             field_name = field.get('name')
             field_type = field.get('type')
+            self.all_fields.append(Field(self.spec_version, int(field_num), field_name, field_type))
             if self.config.is_supported(field_type) and not self.config.is_excluded(field_type):
-                self.fields.append(Field(self.spec_version, int(field_num), field_name, field_type))
\ No newline at end of file
+                self.fields.append(Field(self.spec_version, int(field_num), field_name, field_type))
+
+    def parse_messages(self):
+        for message in self.root.findall('messages/message'):
+            message_name = message.get('name')
+            msg_type = message.get('msgtype')
+            msg_cat = message.get('msgcat')
+            self.messages.append(Message(self.spec_version, message_name, msg_type, msg_cat))
@@ -0,0 +1,132 @@
+from xml.etree import ElementTree as xml
+
+from spec.datatypes.datatypes import SbeData, SbeField, SbeMessage, SbeSchema
+
+SBE_NAMESPACE = '{http://fixprotocol.io/2016/sbe}'
+
+PRIMITIVE_SIZES = {
+    'char': 1,
+    'int8': 1, 'uint8': 1,
+    'int16': 2, 'uint16': 2,
+    'int32': 4, 'uint32': 4,
+    'int64': 8, 'uint64': 8,
+}
+
+# blockLength, templateId, schemaId and version, all uint16
+MESSAGE_HEADER = ['blockLength', 'templateId', 'schemaId', 'version']
+
+
+class SbeParseError(Exception):
+    pass
+
+
+class SbeParser:
+    """ Lays out the messages of an SBE schema, e.g. sbe/orders.xml """
+
+    def __init__(self, doc_root: xml.Element, module: str):
+        self.root = doc_root
+        self.module = module
+        self.types = {}
+
+    def parse(self) -> SbeSchema:
+        for types in self.root.findall('types'):
+            for t in types:
+                self.types[t.get('name')] = t
+        self._check_message_header()
+
+        messages = [self._parse_message(m) for m in self.root if _local_name(m) == 'message']
+        return SbeSchema(
+            self.module,
+            self.root.get('package', self.module),
+            int(self.root.get('id', '0')),
+            int(self.root.get('version', '0')),
+            self.root.get('byteOrder', 'littleEndian'),
+            messages,
+        )
+
+    def _check_message_header(self):
+        header = self.types.get('messageHeader')
+        names = [t.get('name') for t in header] if header is not None else []
+        primitives = [t.get('primitiveType') for t in header] if header is not None else []
+        if names != MESSAGE_HEADER or any(p != 'uint16' for p in primitives):
+            raise SbeParseError(f"{self.module}: messageHeader must be the standard 4 x uint16 header")
+
+    def _parse_message(self, message: xml.Element) -> SbeMessage:
+        name = message.get('name')
+        fields = []
+        data = []
+        offset = 0
+        for member in message:
+            member_kind = _local_name(member)
+            if member_kind == 'field':
+                field = self._parse_field(member, offset)
+                fields.append(field)
+                offset = field.offset + self._size_of(field)
+            elif member_kind == 'data':
+                data.append(self._parse_data(member))
+            elif member_kind == 'group':
+                raise SbeParseError(f"{self.module}: repeating group {member.get('name')} in {name} is not supported")
+
+        return SbeMessage(
+            name,
+            int(message.get('id')),
+            message.get('semanticType', ''),
+            int(message.get('blockLength', offset)),
+            fields,
+            data,
+        )
+
+    def _parse_field(self, field: xml.Element, offset: int) -> SbeField:
+        name = field.get('name')
+        encoding = self.types.get(field.get('type'))
+        if encoding is None:
+            raise SbeParseError(f"{self.module}: field {name} has unknown type {field.get('type')}")
+
+        tag = int(field.get('id'))
+        offset = int(field.get('offset', offset))
+        kind = _local_name(encoding)
+        if kind == 'type':
+            primitive = encoding.get('primitiveType')
+            length = int(encoding.get('length', '1'))
+            value_kind = 'chars' if primitive == 'char' and length > 1 else 'char' if primitive == 'char' else 'int'
+            presence = field.get('presence', encoding.get('presence', 'required'))
+            constant = (encoding.text or '').strip() if presence == 'constant' else None
+            return SbeField(tag, name, offset, value_kind, primitive, length, None, presence, constant)
+        if kind == 'enum':
+            primitive = encoding.get('encodingType')
+            value_kind = 'char' if primitive == 'char' else 'int'
+            presence = field.get('presence', 'required')
+            return SbeField(tag, name, offset, value_kind, primitive, 1, None, presence, None)
+        if kind == 'composite':
+            mantissa = encoding.find("type[@name='mantissa']")
+            exponent = encoding.find("type[@name='exponent']")
+            if mantissa is None or exponent is None:
+                raise SbeParseError(f"{self.module}: composite {encoding.get('name')} of {name} is not a decimal")
+            presence = field.get('presence', mantissa.get('presence', 'required'))
+            if exponent.get('presence') == 'constant':
+                return SbeField(tag, name, offset, 'decimal', mantissa.get('primitiveType'), 1,
+                                int(exponent.text.strip()), presence, None)
+            if exponent.get('primitiveType') != 'int8':
+                raise SbeParseError(f"{self.module}: exponent of {encoding.get('name')} must be an int8")
+            return SbeField(tag, name, offset, 'decimal', mantissa.get('primitiveType'), 1, None, presence, None)
+        raise SbeParseError(f"{self.module}: field {name} has unsupported type {kind}")
+
+    def _parse_data(self, data: xml.Element) -> SbeData:
+        encoding = self.types.get(data.get('type'))
+        length = encoding.find("type[@name='length']") if encoding is not None else None
+        if length is None:
+            raise SbeParseError(f"{self.module}: data {data.get('name')} has no length")
+        return SbeData(int(data.get('id')), data.get('name'), length.get('primitiveType'))
+
+    @staticmethod
+    def _size_of(field: SbeField) -> int:
+        if field.presence == 'constant':
+            return 0
+        size = PRIMITIVE_SIZES[field.primitive] * field.length
+        if field.kind == 'decimal' and field.exponent is None:
+            size += 1
+        return size
+
+
+def _local_name(element: xml.Element) -> str:
+    return element.tag.replace(SBE_NAMESPACE, '')
@@ -0,0 +1,45 @@
+import tempfile
+import unittest
+import xml.etree.ElementTree as xml
+from os import path
+
+from spec.config.config import Config
+from spec.parser.extension import extension_files_for, merge_extension
+from spec.parser.parser import Parser
+from spec.writer.writer import Writer
+
+SPEC_DIR = path.dirname(path.dirname(path.abspath(__file__)))
+FIXTURES_DIR = path.join(path.dirname(path.abspath(__file__)), 'extensions')
+
+
+class ExtensionTest(unittest.TestCase):
+    """ Run: python -m unittest discover -s spec/tests -t . """
+
+    def test_generated_code_has_extension_fields(self):
+        extension_files = extension_files_for('fix44', FIXTURES_DIR)
+        self.assertEqual([path.join(FIXTURES_DIR, 'fix44', 'venue.xml')], extension_files)
+        self.assertEqual([], extension_files_for('fix42', FIXTURES_DIR))
+
+        root = xml.parse(path.join(SPEC_DIR, 'FIX44.xml')).getroot()
+        for extension_file in extension_files:
+            merge_extension(root, xml.parse(extension_file).getroot())
+
+        config = Config()
+        parser = Parser(root, config, 'fix44')
+        parser.parse_fields()
+        parser.parse_messages()
+        with tempfile.TemporaryDirectory() as output_dir:
+            output_file = path.join(output_dir, 'fix44.rs')
+            writer = Writer(output_file, config, import_fields=True)
+            writer.write_field_impls('fix44', parser.fields)
+            writer.write_messages('fix44', parser.messages)
+            writer.close()
+            with open(output_file) as generated:
+                code = generated.read()
+
+        self.assertIn('5001', code)
+        self.assertIn('VenueBook', code)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,94 @@
+import typing
+
+from ..datatypes.datatypes import SbeField, SbeSchema
+
+PRIMITIVES = {
+    'char': 'UInt8',
+    'int8': 'Int8', 'uint8': 'UInt8',
+    'int16': 'Int16', 'uint16': 'UInt16',
+    'int32': 'Int32', 'uint32': 'UInt32',
+    'int64': 'Int64', 'uint64': 'UInt64',
+}
+
+
+class SbeWriter:
+    """ Writes the layout of an SBE schema as a `crate::sbe::Schema` constant """
+
+    def __init__(self, output_file: str):
+        self.fd = self.init(output_file)
+        self._write_imports()
+
+    def init(self, output_file: str) -> typing.TextIO:
+        fd = open(output_file.lower(), "w", newline='\n')
+        return fd
+
+    def _write_imports(self):
+        self.write("use crate::sbe::{")
+        self.write("    ByteOrder, DataLayout, Encoding, FieldLayout, MessageLayout, Presence, Primitive, Schema,")
+        self.write("};")
+        self.write_newline()
+
+    def close(self):
+        self.fd.close()
+
+    def write(self, output: str):
+        self.fd.write(output)
+        self.fd.write('\n')
+
+    def write_newline(self):
+        self.fd.write('\n')
+
+    def write_schema(self, schema: SbeSchema):
+        byte_order = 'BigEndian' if schema.byte_order == 'bigEndian' else 'LittleEndian'
+        self.write(f"pub const SCHEMA: Schema = Schema {{")
+        self.write(f"    package: \"{schema.package}\",")
+        self.write(f"    id: {schema.id},")
+        self.write(f"    version: {schema.version},")
+        self.write(f"    byte_order: ByteOrder::{byte_order},")
+        self.write(f"    messages: &[")
+        for message in schema.messages:
+            self.write(f"        MessageLayout {{")
+            self.write(f"            name: \"{message.name}\",")
+            self.write(f"            template_id: {message.template_id},")
+            self.write(f"            msg_type: \"{message.msg_type}\",")
+            self.write(f"            block_length: {message.block_length},")
+            self.write(f"            fields: &[")
+            for field in message.fields:
+                self.write(f"                FieldLayout {{")
+                self.write(f"                    tag: {field.tag},")
+                self.write(f"                    name: \"{field.name}\",")
+                self.write(f"                    offset: {field.offset},")
+                self.write(f"                    encoding: {self._encoding(field)},")
+                self.write(f"                    presence: {self._presence(field)},")
+                self.write(f"                }},")
+            self.write(f"            ],")
+            self.write(f"            data: &[")
+            for data in message.data:
+                self.write(f"                DataLayout {{")
+                self.write(f"                    tag: {data.tag},")
+                self.write(f"                    name: \"{data.name}\",")
+                self.write(f"                    length: Primitive::{PRIMITIVES[data.length_primitive]},")
+                self.write(f"                }},")
+            self.write(f"            ],")
+            self.write(f"        }},")
+        self.write(f"    ],")
+        self.write(f"}};")
+
+    @staticmethod
+    def _encoding(field: SbeField) -> str:
+        if field.kind == 'char':
+            return "Encoding::Char"
+        if field.kind == 'chars':
+            return f"Encoding::CharArray({field.length})"
+        if field.kind == 'decimal':
+            exponent = 'None' if field.exponent is None else f"Some({field.exponent})"
+            return f"Encoding::Decimal(Primitive::{PRIMITIVES[field.primitive]}, {exponent})"
+        return f"Encoding::Int(Primitive::{PRIMITIVES[field.primitive]})"
+
+    @staticmethod
+    def _presence(field: SbeField) -> str:
+        if field.presence == 'constant':
+            return f"Presence::Constant(\"{field.constant}\")"
+        if field.presence == 'optional':
+            return "Presence::Optional"
+        return "Presence::Required"
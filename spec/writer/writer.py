@@ -1,8 +1,8 @@
 import typing
 
 from ..config.config import Config
-from ..datatypes.field_utils import unique_field_enum_variants
-from ..datatypes.datatypes import Field
+from ..datatypes.field_utils import unique_field_enum_variants, to_snake_case
+from ..datatypes.datatypes import Field, Message
 
 
 class Writer:
@@ -25,12 +25,14 @@ class Writer:
         self.write("use rust_decimal::Decimal;")
         self.write_newline()
 
-        self.write("use crate::model::field::{FieldSet, FieldTypeMismatchError};")
+        self.write("use crate::model::field::{FieldKind, FieldSet, FieldType, FieldTypeMismatchError, ParseError, ValueType};")
         self.write("use crate::model::message_type::UnknownMsgTypeError;")
         self.write("use crate::model::BeginString;")
 
         if import_fields:
             self.write("use crate::model::generated::fields::Field;")
+            self.write("use crate::model::message::Message;")
+            self.write("use crate::model::message_type::UnsupportedMessageType;")
         self.write_newline()
 
     def close(self):
@@ -45,31 +47,65 @@ class Writer:
 
     def write_field_enum(self, spec_versions: [str], fields: [Field]):
         self.write(f"#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]")
+        self.write(f"#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]")
         self.write(f"pub enum Field {{")
         seen_enum_variants = []
         for field in fields:
             enum_variant = self.config.enum_variant_for(field)
             if enum_variant not in seen_enum_variants:
                 struct_type  = self.config.struct_type_for(field)
-                self.write(f"    {enum_variant}(u16, {struct_type}),")
+                self.write(f"    {enum_variant}(u32, {struct_type}),")
                 seen_enum_variants.append(enum_variant)
         self.write(f"}}")
         self.write_newline()
 
         self.write(f"impl Field {{")
-        self.write(f"   pub fn crack(bs: BeginString, to_parse: &str) -> Result<Field, ()> {{")
+        self.write(f"   /// Parse a single `tag=value` field using the field types of `bs`")
+        self.write(f"   ///")
+        self.write(f"   /// # Errors")
+        self.write(f"   ///")
+        self.write(f"   /// Will return `Err` if the tag or the value can't be parsed; the error's")
+        self.write(f"   /// offset is relative to the start of `to_parse`")
+        self.write(f"   pub fn crack(bs: BeginString, to_parse: &str) -> Result<Field, ParseError> {{")
         self.write(f"       match bs {{")
 
         for spec_file in spec_versions:
             spec_version = spec_file.split('.')[0]
-            self.write(f"           BeginString::{spec_version.title()} => Ok(crate::model::generated::{spec_version.lower()}::{spec_version.upper()}CrackerUtils::crack(")
-            self.write(f"               to_parse,")
-            self.write(f"           ).unwrap()),")
+            self.write(f"           BeginString::{spec_version.title()} => crate::model::generated::{spec_version.lower()}::{spec_version.upper()}CrackerUtils::crack(to_parse),")
 
         self.write(f"       }}")
         self.write(f"   }}")
+        self.write_newline()
+
+        self.write(f"   /// Type `bs` declares for `tag`, or `None` if `bs` doesn't define it")
+        self.write(f"   #[must_use]")
+        self.write(f"   pub const fn field_type(bs: BeginString, tag: u32) -> Option<FieldType> {{")
+        self.write(f"       match bs {{")
+        for spec_file in spec_versions:
+            spec_version = spec_file.split('.')[0]
+            self.write(f"           BeginString::{spec_version.title()} => crate::model::generated::{spec_version.lower()}::field_type(tag),")
+        self.write(f"       }}")
+        self.write(f"   }}")
         self.write(f"}}")
 
+    def write_field_types(self, fields: [Field]):
+        # (FIX type, enum variant) -> [tag nums...]
+        _tags_by_type = {}
+        for field in fields:
+            kind = self.config.enum_variant_for(field) if self.config.should_process_type(field.type) else 'String'
+            _tags_by_type.setdefault((field.type, kind), []).append(str(field.tag))
+
+        self.write(f"/// Type of each tag, as declared in this version's spec")
+        self.write(f"#[must_use]")
+        self.write(f"pub const fn field_type(tag: u32) -> Option<FieldType> {{")
+        self.write(f"    match tag {{")
+        for (fix_type, kind), tags in _tags_by_type.items():
+            self.write(f"        {' | '.join(tags)} => Some(FieldType {{ fix_type: \"{fix_type}\", kind: FieldKind::{kind} }}),")
+        self.write(f"        _ => None,")
+        self.write(f"    }}")
+        self.write(f"}}")
+        self.write_newline()
+
     def write_field_struct_impl(self, fields: [Field]):
         _seen_types = []
         _seen_enum_variants = []
@@ -109,7 +145,7 @@ class Writer:
 
         # Field::tag(&self)
         self.write(f"    #[must_use]")
-        self.write(f"    pub const fn tag(&self) -> u16 {{")
+        self.write(f"    pub const fn tag(&self) -> u32 {{")
         self.write(f"        match self {{")
         count = 1
         _seen_enum_variants2 = []
@@ -156,12 +192,12 @@ class Writer:
             enum_variant = self.config.enum_variant_for(field)
             if enum_variant not in seen_types:
                 self.write(f"pub struct {enum_variant}Field {{")
-                self.write(f"    tag: u16,")
+                self.write(f"    tag: u32,")
                 self.write(f"    fd: Field,")
                 self.write(f"}}")
                 self.write_newline()
                 self.write(f"impl {enum_variant}Field {{")
-                self.write(f"    const fn tag(&self) -> u16 {{")
+                self.write(f"    const fn tag(&self) -> u32 {{")
                 self.write(f"        self.tag")
                 self.write(f"    }}")
                 self.write_newline()
@@ -199,26 +235,25 @@ class Writer:
         self.write(f"pub struct {spec_version.upper()}CrackerUtils;")
         self.write(f"// parse string (35=D) into Field{{35, \"D\"}}")
         self.write(f"impl {spec_version.upper()}CrackerUtils {{")
-        self.write(f"    pub fn crack(s: &str) -> Result<Field, ()> {{")
-        self.write(f"        println!(\"crack for Field: {{}}\", &s);")
-        self.write(f"        let two_parts = s.split_once('=');")
-        self.write(f"        match two_parts {{")
-        self.write(f"            Some((s_tag, s_value)) => {{")
-        self.write(f"                println!(\"parsing tag: {{}}, field: {{}} into Field\", s_tag, s_value);")
+        self.write(f"    pub fn crack(s: &str) -> Result<Field, ParseError> {{")
+        self.write(f"        let Some((s_tag, s_value)) = s.split_once('=') else {{")
+        self.write(f"            return Err(ParseError::new(None, s, ValueType::Field, 0));")
+        self.write(f"        }};")
         self.write(f"                  ")
-        self.write(f"                // figure out the tag")
-        self.write(f"                let tag: u16 = s_tag.parse::<u16>().unwrap();")
+        self.write(f"        // figure out the tag")
+        self.write(f"        let tag: u32 = s_tag")
+        self.write(f"            .parse::<u32>()")
+        self.write(f"            .map_err(|_| ParseError::new(None, s_tag, ValueType::Tag, 0))?;")
+        self.write(f"        let offset = s_tag.len() + 1;")
         self.write(f"                   ")
-        self.write(f"                // build field using the tag & value")
-        self.write(f"                match tag {{")
+        self.write(f"        // build field using the tag & value")
+        self.write(f"        match tag {{")
         for enum_key in _tag_num_enum_variant.keys():
             parse_fn = self.config.parse_fn_for(enum_key)
-            self.write(f"                    {' | '.join(_tag_num_enum_variant.get(enum_key))} => Ok({parse_fn}),")
+            self.write(f"            {' | '.join(_tag_num_enum_variant.get(enum_key))} => Ok({parse_fn}),")
             self.write_newline()
-        self.write(f"                    _ => Err(()),")
-        self.write(f"                }}")
-        self.write(f"            }}")
-        self.write(f"            None => Err(()),")
+        self.write(f"            // user-defined and other unknown tags are kept as strings")
+        self.write(f"            _ => Ok(Field::String(tag, s_value.to_string())),")
         self.write(f"        }}")
         self.write(f"    }}")
         self.write(f"}}")
@@ -247,7 +282,7 @@ class Writer:
                 self.write(f"   }}")
 
                 self.write(f"    #[must_use]")
-                self.write(f"    pub const fn tag() -> u16 {{")
+                self.write(f"    pub const fn tag() -> u32 {{")
                 self.write(f"        {field.tag}")
                 self.write(f"    }}")
                 self.write_newline()
@@ -279,7 +314,7 @@ class Writer:
         self.write(f"impl FieldSet {{")
         # // todo generate get_clordid(&self) etc
         #
-        # // todo generate get_int(&self, tag: u16) etc.
+        # // todo generate get_int(&self, tag: u32) etc.
         #
 
         # _seen_methods = []
@@ -293,7 +328,7 @@ class Writer:
                 dereference_type = 'v.to_string()' if 'str' in return_type else '*v'  # if return_type.startswith('&') else 'v'
                 if method_name not in _seen_fix_types:
                     self.write(f"    /// for use by custom fields")
-                    self.write(f"    pub fn get_{method_name}_field(&self, tag: u16) -> Result<{enum_variant}Field, FieldTypeMismatchError> {{")
+                    self.write(f"    pub fn get_{method_name}_field(&self, tag: u32) -> Result<{enum_variant}Field, FieldTypeMismatchError> {{")
                     self.write(f"        let f = self.iter().find_or_first(|p| p.tag() == tag).unwrap();")
                     self.write(f"        match f {{")
                     self.write(f"            Field::{enum_variant}(t, v) => Ok({enum_variant}Field {{")
@@ -306,3 +341,66 @@ class Writer:
                 _seen_fix_types.append(method_name)
 
         self.write(f"}}")
+
+
+    def write_messages(self, spec_version: str, messages: [Message]):
+        ##
+        ## Generate Message Types
+        ##
+        for message in messages:
+            self.write_newline()
+            self.write(f"/// {message.name} (35={message.msg_type}, {message.msg_cat})")
+            self.write(f"#[derive(Debug)]")
+            self.write(f"pub struct {message.name} {{")
+            self.write(f"    pub message: Message,")
+            self.write(f"}}")
+            self.write_newline()
+            self.write(f"impl {message.name} {{")
+            self.write(f"    pub const MSG_TYPE: &'static str = \"{message.msg_type}\";")
+            self.write(f"}}")
+            self.write_newline()
+            self.write(f"impl Default for {message.name} {{")
+            self.write(f"    fn default() -> Self {{")
+            self.write(f"        Self {{")
+            self.write(f"            message: Message::of_type(Self::MSG_TYPE),")
+            self.write(f"        }}")
+            self.write(f"    }}")
+            self.write(f"}}")
+            self.write_newline()
+            self.write(f"impl From<{message.name}> for Message {{")
+            self.write(f"    fn from(typed: {message.name}) -> Self {{")
+            self.write(f"        typed.message")
+            self.write(f"    }}")
+            self.write(f"}}")
+
+        ##
+        ## Generate MessageCracker: MsgType -> on_<message>(typed message)
+        ##
+        self.write_newline()
+        self.write(f"/// Dispatches a `Message` to the typed handler for its MsgType. Handlers")
+        self.write(f"/// which aren't overridden report the message as unsupported.")
+        self.write(f"pub trait {spec_version.upper()}MessageCracker {{")
+        self.write(f"    ///")
+        self.write(f"    /// # Errors")
+        self.write(f"    ///")
+        self.write(f"    /// Will return `Err` if there's no handler for the message's MsgType")
+        self.write(f"    fn crack(&mut self, message: Message) -> Result<(), UnsupportedMessageType> {{")
+        self.write(f"        let msg_type = match message.get_field_safe(35).map(Field::as_str_safe) {{")
+        self.write(f"            Ok(Ok(msg_type)) => msg_type.to_string(),")
+        self.write(f"            _ => return Err(UnsupportedMessageType::of(&message)),")
+        self.write(f"        }};")
+        self.write(f"        match msg_type.as_str() {{")
+        for message in messages:
+            self.write(f"            {message.name}::MSG_TYPE => self.on_{to_snake_case(message.name)}({message.name} {{ message }}),")
+        self.write(f"            _ => Err(UnsupportedMessageType::of(&message)),")
+        self.write(f"        }}")
+        self.write(f"    }}")
+        for message in messages:
+            self.write_newline()
+            self.write(f"    ///")
+            self.write(f"    /// # Errors")
+            self.write(f"    ///")
+            self.write(f"    fn on_{to_snake_case(message.name)}(&mut self, message: {message.name}) -> Result<(), UnsupportedMessageType> {{")
+            self.write(f"        Err(UnsupportedMessageType::of(&message.message))")
+            self.write(f"    }}")
+        self.write(f"}}")
@@ -1,6 +1,9 @@
 use std::any::Any;
 
+use tracing::debug;
+
 use crate::model::message::Message;
+use crate::model::message_type::UnsupportedMessageType;
 
 pub trait FixApp {
     fn as_any(&self) -> &dyn Any; // todo no no no
@@ -8,13 +11,20 @@ pub trait FixApp {
     // todo engine should handle these messages; FixApp should just be there for
     //  application to implement to ge tthe msgs.
 
-    fn on_message(&mut self, message: Message) {
-        println!("Received {}", message);
+    // implement with e.g. `FIX44MessageCracker::crack(self, message)` to have
+    //  messages dispatched to typed handlers.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the application doesn't handle the message's type
+    fn on_message(&mut self, message: Message) -> Result<(), UnsupportedMessageType> {
+        debug!(message = %message.redacted(), "received");
         match message.get_field(35).as_str() {
             "A" => {
                 self.on_logon(message);
+                Ok(())
             }
-            _ => todo!("Handle unexpected message types"),
+            _ => Err(UnsupportedMessageType::of(&message)),
         }
     }
 
@@ -1,42 +1,445 @@
-use bytes::Bytes;
+use std::fmt;
+use std::fmt::Formatter;
+
+use bytes::{Buf, Bytes, BytesMut};
+use tracing::{debug, trace};
 
 use crate::application::FixApp;
-use crate::model::field::{FieldSet, NoSuchField};
+use crate::model::field::{FieldSet, NoSuchField, ParseError, ValueType};
 use crate::model::generated::fields::Field;
 use crate::model::message::Message;
 use crate::model::BeginString;
 
+const SOH: u8 = b'\x01';
+
 struct Cracker<'a> {
     app: Box<dyn FixApp + 'a>,
 }
 
 // todo link to FixApplication...
 impl Cracker<'_> {
-    pub fn crack(&mut self, msg: &Bytes) {
-        let msg_string = String::from_utf8_lossy(msg.as_ref()).to_string();
+    /// Hand the message to the application, returning the reject to send
+    /// back if the application doesn't support its MsgType.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the message can't be parsed; the application
+    /// doesn't see it
+    pub fn crack(&mut self, msg: &Bytes) -> Result<Option<Message>, ParseError> {
+        let message = crack_message(msg)?;
+
+        Ok(self.app.on_message(message).err().map(|unsupported| {
+            debug!(%unsupported, "rejecting unsupported message");
+            unsupported.business_reject()
+        }))
+    }
+}
+
+/// Parse a single received message into a `Message`.
+///
+/// # Errors
+///
+/// Will return `Err` if the message does not start with a known `BeginString`
+/// or any of its fields can't be parsed as the type its tag requires
+pub fn crack_message(msg: &Bytes) -> Result<Message, ParseError> {
+    crack_message_with(msg, None)
+}
 
-        let begin_string = BeginString::crack(msg_string.as_str());
-        let begin_string = begin_string.unwrap_or_else(|s| panic!("{:?}", s));
+/// Parse a single received message, using `default_appl_ver_id` as the
+/// application version of FIXT.1.1 messages which don't carry their own
+/// `ApplVerID` (1128).
+///
+/// Header and trailer fields are parsed with the transport version's types
+/// and body fields with the application version's.
+///
+/// # Errors
+///
+/// Will return `Err` if the message does not start with a known `BeginString`,
+/// the application version of a FIXT.1.1 message can't be determined, or any
+/// of its fields can't be parsed as the type its tag requires
+pub fn crack_message_with(
+    msg: &Bytes,
+    default_appl_ver_id: Option<BeginString>,
+) -> Result<Message, ParseError> {
+    let header = scan_header(msg)?;
+    let transport = header.begin_string;
+    let application = application_version(msg, &header, default_appl_ver_id)?;
+    let mut fields = vec![];
 
-        // let fields: Vec<Field> = self.get_cracker(begin_string).crack(msg_string);
+    let mut offset = 0;
+    for raw in msg.split(|b| *b == SOH) {
+        let start = offset;
+        offset += raw.len() + 1;
+        if raw.is_empty() {
+            continue;
+        }
 
-        let fields: Vec<Field> = msg_string
-            .split('\x01')
-            // .filter(|p| p.len() == 2)
-            .into_iter()
-            // .map(|s| Field::cra ::try_from(s.to_string()))
-            .filter(|p| !p.is_empty())
-            .map(|s| Field::crack(begin_string, s))
-            .filter_map(|s| match s {
-                Ok(o) => Some(o),
-                Err(_) => None,
-            })
-            .collect();
+        let text = String::from_utf8_lossy(raw);
+        let tag = text
+            .split_once('=')
+            .and_then(|(tag, _)| tag.parse::<u32>().ok());
+        let version = field_version(tag, transport, application);
+        fields.push(Field::crack(version, &text).map_err(|e| e.offset_by(start))?);
+    }
 
-        let field_set = FieldSet::with(fields);
-        let message: Message = field_set.into();
+    trace!(fields = fields.len(), "cracked message");
+    Ok(FieldSet::with(fields).into())
+}
 
-        self.app.on_message(message);
+/// Version whose types the body of `msg` is in.
+///
+/// For FIXT.1.1 that's `ApplVerID` (1128), falling back to the session's
+/// `DefaultApplVerID`; session level messages are FIXT.1.1 throughout.
+/// Every other `BeginString` is its own application version.
+///
+/// # Errors
+///
+/// Will return `Err` if `ApplVerID` isn't a known version, or an application
+/// message has neither it nor a default
+pub fn application_version(
+    msg: &[u8],
+    header: &StandardHeader,
+    default_appl_ver_id: Option<BeginString>,
+) -> Result<BeginString, ParseError> {
+    if !header.begin_string.is_transport_only() || header.msg_type.is_some_and(is_session_level) {
+        return Ok(header.begin_string);
+    }
+
+    let mut at = 0;
+    while let Some((tag, value, next)) = split_field(msg, at) {
+        if tag == b"1128" {
+            return std::str::from_utf8(value)
+                .ok()
+                .and_then(BeginString::from_appl_ver_id)
+                .ok_or_else(|| {
+                    value_error(1128, value, ValueType::ApplVerID, next - value.len() - 1)
+                });
+        }
+        at = next;
+    }
+    default_appl_ver_id.ok_or_else(|| ParseError::new(Some(1128), "", ValueType::ApplVerID, 0))
+}
+
+/// Version whose types field `tag` is parsed with: body fields are in the
+/// `application` version, header and trailer fields in the `transport` one
+pub(crate) fn field_version(
+    tag: Option<u32>,
+    transport: BeginString,
+    application: BeginString,
+) -> BeginString {
+    match tag {
+        Some(tag) if !Field::is_header_tag(tag) && !Field::is_trailer_tag(tag) => application,
+        _ => transport,
+    }
+}
+
+/// MsgTypes defined by the FIXT.1.1 session layer itself
+pub(crate) fn is_session_level(msg_type: &str) -> bool {
+    matches!(msg_type, "0" | "1" | "2" | "3" | "4" | "5" | "A" | "n")
+}
+
+/// `BeginString` (8), `BodyLength` (9) and `MsgType` (35), which lead every
+/// message
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct StandardHeader<'a> {
+    pub begin_string: BeginString,
+    pub body_length: Option<usize>,
+    pub msg_type: Option<&'a str>,
+}
+
+/// Read the leading 8, 9 and 35 fields of `msg` without looking at the rest
+/// of it, e.g. to route a message before cracking it.
+///
+/// `BodyLength` and `MsgType` are only picked up in their standard positions,
+/// straight after `BeginString`.
+///
+/// # Errors
+///
+/// Will return `Err` if the message does not start with a known `BeginString`,
+/// or `BodyLength` or `MsgType` can't be parsed
+pub fn scan_header(msg: &[u8]) -> Result<StandardHeader<'_>, ParseError> {
+    let (begin_string, mut next) = match split_field(msg, 0) {
+        Some((b"8", value, next)) => (value, next),
+        _ => {
+            let first = msg.split(|b| *b == SOH).next().unwrap_or_default();
+            return Err(ParseError::new(
+                None,
+                &String::from_utf8_lossy(first),
+                ValueType::BeginString,
+                0,
+            ));
+        }
+    };
+    let begin_string = std::str::from_utf8(begin_string)
+        .ok()
+        .and_then(|s| BeginString::try_from(s).ok())
+        .ok_or_else(|| value_error(8, begin_string, ValueType::BeginString, 2))?;
+
+    let mut body_length = None;
+    if let Some((b"9", value, after)) = split_field(msg, next) {
+        let parsed = std::str::from_utf8(value)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .ok_or_else(|| value_error(9, value, ValueType::Int, after - value.len() - 1))?;
+        body_length = Some(parsed);
+        next = after;
+    }
+
+    let mut msg_type = None;
+    if let Some((b"35", value, after)) = split_field(msg, next) {
+        let parsed = std::str::from_utf8(value)
+            .map_err(|_| value_error(35, value, ValueType::String, after - value.len() - 1))?;
+        msg_type = Some(parsed);
+    }
+
+    Ok(StandardHeader {
+        begin_string,
+        body_length,
+        msg_type,
+    })
+}
+
+/// Tag, value and the start of the following field, for the field at `at`
+fn split_field(msg: &[u8], at: usize) -> Option<(&[u8], &[u8], usize)> {
+    let rest = msg.get(at..)?;
+    let end = rest.iter().position(|b| *b == SOH).unwrap_or(rest.len());
+    let eq = rest[..end].iter().position(|b| *b == b'=')?;
+    Some((&rest[..eq], &rest[eq + 1..end], at + end + 1))
+}
+
+fn value_error(tag: u32, value: &[u8], expected: ValueType, offset: usize) -> ParseError {
+    ParseError::new(Some(tag), &String::from_utf8_lossy(value), expected, offset)
+}
+
+pub trait Crack<T> {
+    ///
+    /// # Errors
+    ///
+    fn crack(s: &str) -> Result<T, NoSuchField>;
+}
+
+impl Crack<BeginString> for BeginString {
+    fn crack(s: &str) -> Result<BeginString, NoSuchField> {
+        scan_header(s.as_bytes())
+            .map(|header| header.begin_string)
+            .map_err(|_| NoSuchField { tag: 8 })
+    }
+}
+
+/// Split the next complete message (`8=...` through `10=nnn<SOH>`) off the
+/// front of `buf`.
+///
+/// Returns `Ok(None)` when `buf` doesn't yet hold a complete message. Bytes
+/// which can't be the start of a message are discarded.
+///
+/// # Errors
+///
+/// Will return `Err` if the message at the front of `buf` is malformed; the
+/// offending bytes are consumed so the next call resumes at the following
+/// message.
+pub fn next_frame(buf: &mut BytesMut) -> Result<Option<Bytes>, FrameError> {
+    // resync onto the start of a message
+    match find(buf, b"8=") {
+        Some(0) => {}
+        Some(start) => buf.advance(start),
+        None => {
+            let keep = usize::from(buf.last() == Some(&b'8'));
+            buf.advance(buf.len() - keep);
+            return Ok(None);
+        }
+    }
+
+    let Some(begin_string_end) = find(buf, &[SOH]) else {
+        return Ok(None);
+    };
+    let length_start = begin_string_end + 1;
+    if buf.len() < length_start + 2 {
+        return Ok(None);
+    }
+    if &buf[length_start..length_start + 2] != b"9=" {
+        buf.advance(length_start);
+        return Err(FrameError::MissingBodyLength);
+    }
+    let Some(length_end) = find(&buf[length_start..], &[SOH]).map(|i| length_start + i) else {
+        return Ok(None);
+    };
+    let body_length = std::str::from_utf8(&buf[length_start + 2..length_end])
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok());
+    // a length that overflows can never be satisfied by a real message
+    let body_length = body_length.filter(|len| len.checked_add(length_end + 8).is_some());
+    let Some(body_length) = body_length else {
+        buf.advance(length_end + 1);
+        return Err(FrameError::InvalidBodyLength);
+    };
+
+    // "10=nnn<SOH>"
+    let checksum_start = length_end + 1 + body_length;
+    let frame_end = checksum_start + 7;
+    if buf.len() < frame_end {
+        return Ok(None);
+    }
+    let checksum_field = &buf[checksum_start..frame_end];
+    if &checksum_field[..3] != b"10=" || checksum_field[6] != SOH {
+        buf.advance(length_end + 1);
+        return Err(FrameError::MissingCheckSum);
+    }
+
+    let expected = checksum(&buf[..checksum_start]);
+    let received = std::str::from_utf8(&checksum_field[3..6])
+        .ok()
+        .and_then(|s| s.parse::<u8>().ok());
+    if received != Some(expected) {
+        buf.advance(frame_end);
+        return Err(FrameError::CheckSumMismatch { expected, received });
+    }
+
+    Ok(Some(buf.split_to(frame_end).freeze()))
+}
+
+/// Length of the FIX Simple Open Framing Header: a big-endian `u32` message
+/// length, which includes the header, and a big-endian `u16` encoding type
+pub const SOFH_LENGTH: usize = 6;
+
+/// Longest SOFH message, header included, that is buffered while waiting for
+/// the rest of it
+pub const MAX_SOFH_MESSAGE_LENGTH: usize = 1024 * 1024;
+
+/// Encoding of a message carried in a Simple Open Framing Header
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EncodingType {
+    TagValue,
+    Fixml,
+    Json,
+    SbeBigEndian,
+    SbeLittleEndian,
+    Other(u16),
+}
+
+impl From<u16> for EncodingType {
+    fn from(value: u16) -> Self {
+        match value {
+            0xF000 => EncodingType::TagValue,
+            0xF100 => EncodingType::Fixml,
+            0xF500 => EncodingType::Json,
+            0x5BE0 => EncodingType::SbeBigEndian,
+            0xEB50 => EncodingType::SbeLittleEndian,
+            other => EncodingType::Other(other),
+        }
+    }
+}
+
+impl From<EncodingType> for u16 {
+    fn from(value: EncodingType) -> Self {
+        match value {
+            EncodingType::TagValue => 0xF000,
+            EncodingType::Fixml => 0xF100,
+            EncodingType::Json => 0xF500,
+            EncodingType::SbeBigEndian => 0x5BE0,
+            EncodingType::SbeLittleEndian => 0xEB50,
+            EncodingType::Other(other) => other,
+        }
+    }
+}
+
+/// A message split off a stream framed with Simple Open Framing Headers
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SofhFrame {
+    pub encoding_type: EncodingType,
+    /// The message, without the header
+    pub payload: Bytes,
+}
+
+/// Split the next message framed by a Simple Open Framing Header off the
+/// front of `buf`.
+///
+/// Returns `Ok(None)` when `buf` doesn't yet hold the whole header and
+/// message. The payload is returned as it is, whatever its encoding, so one
+/// stream can carry tag=value and binary messages alike.
+///
+/// # Errors
+///
+/// Will return `Err` if the header's message length is too short to even
+/// hold the header, or longer than `MAX_SOFH_MESSAGE_LENGTH`. SOFH has
+/// nothing to resync on, so the header is consumed and the stream should be
+/// considered broken.
+pub fn next_sofh_frame(buf: &mut BytesMut) -> Result<Option<SofhFrame>, FrameError> {
+    if buf.len() < SOFH_LENGTH {
+        return Ok(None);
+    }
+    let message_length = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    let encoding_type = EncodingType::from(u16::from_be_bytes([buf[4], buf[5]]));
+    let Some(frame_end) = usize::try_from(message_length)
+        .ok()
+        .filter(|len| (SOFH_LENGTH..=MAX_SOFH_MESSAGE_LENGTH).contains(len))
+    else {
+        buf.advance(SOFH_LENGTH);
+        return Err(FrameError::InvalidMessageLength(message_length));
+    };
+    if buf.len() < frame_end {
+        return Ok(None);
+    }
+
+    let mut frame = buf.split_to(frame_end);
+    frame.advance(SOFH_LENGTH);
+    Ok(Some(SofhFrame {
+        encoding_type,
+        payload: frame.freeze(),
+    }))
+}
+
+/// Sum of all bytes modulo 256, as carried in `CheckSum` (10)
+#[must_use]
+pub fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0_u8, |acc, b| acc.wrapping_add(*b))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// A received message could not be delimited
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FrameError {
+    MissingBodyLength,
+    InvalidBodyLength,
+    MissingCheckSum,
+    CheckSumMismatch {
+        expected: u8,
+        received: Option<u8>,
+    },
+    /// SOFH message length, which is shorter than the header itself or
+    /// longer than `MAX_SOFH_MESSAGE_LENGTH`
+    InvalidMessageLength(u32),
+    /// SOFH encoding type the session can't decode
+    UnsupportedEncoding(EncodingType),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::MissingBodyLength => write!(f, "BodyLength (9) must follow BeginString"),
+            FrameError::InvalidBodyLength => write!(f, "BodyLength (9) is not a number"),
+            FrameError::MissingCheckSum => {
+                write!(f, "CheckSum (10) not found at the end of the body")
+            }
+            FrameError::CheckSumMismatch { expected, received } => match received {
+                Some(received) => {
+                    write!(f, "CheckSum (10) was {}, expected {}", received, expected)
+                }
+                None => write!(f, "CheckSum (10) is not a number, expected {}", expected),
+            },
+            FrameError::InvalidMessageLength(length) => {
+                write!(
+                    f,
+                    "SOFH message length {} is not from {} to {}",
+                    length, SOFH_LENGTH, MAX_SOFH_MESSAGE_LENGTH
+                )
+            }
+            FrameError::UnsupportedEncoding(encoding_type) => {
+                write!(f, "SOFH encoding type {:?} is not supported", encoding_type)
+            }
+        }
     }
 }
 
@@ -45,10 +448,21 @@ mod tests {
     use std::any::Any;
 
     use bytes::{BufMut, Bytes, BytesMut};
+    use rust_decimal::Decimal;
 
     use crate::application::FixApp;
-    use crate::cracker::Cracker;
+    use crate::cracker::{
+        crack_message, crack_message_with, next_frame, next_sofh_frame, scan_header, Crack,
+        Cracker, EncodingType, FrameError, MAX_SOFH_MESSAGE_LENGTH, SOFH_LENGTH,
+    };
+    use crate::encoder;
+    use crate::model::field::{FieldKind, ParseError, ValueType};
+    use crate::model::generated::fields::Field;
+    use crate::model::generated::fix42;
+    use crate::model::generated::fix42::FIX42MessageCracker;
     use crate::model::message::Message;
+    use crate::model::message_type::UnsupportedMessageType;
+    use crate::model::BeginString;
 
     struct TestApp {
         messages: Vec<Message>,
@@ -59,9 +473,10 @@ mod tests {
             self
         }
 
-        fn on_message(&mut self, message: Message) {
+        fn on_message(&mut self, message: Message) -> Result<(), UnsupportedMessageType> {
             println!("TestApp adding: {:#?}", message.to_string());
             self.messages.push(message);
+            Ok(())
         }
 
         // fn on_logon(&self, message: Message) {
@@ -83,7 +498,7 @@ mod tests {
         let mut cracker = Cracker { app: Box::new(app) };
 
         let b = Bytes::from(buf);
-        cracker.crack(&b);
+        cracker.crack(&b).unwrap();
 
         let a: Box<dyn FixApp> = cracker.app;
         let b: &TestApp = match a.as_any().downcast_ref::<TestApp>() {
@@ -119,7 +534,7 @@ mod tests {
         let mut cracker = Cracker { app: Box::new(app) };
 
         let b = Bytes::from(buf);
-        cracker.crack(&b);
+        cracker.crack(&b).unwrap();
 
         let a: Box<dyn FixApp> = cracker.app;
         let b: &TestApp = match a.as_any().downcast_ref::<TestApp>() {
@@ -160,7 +575,7 @@ mod tests {
         }
 
         let b = Bytes::from(buf);
-        cracker.crack(&b);
+        cracker.crack(&b).unwrap();
 
         ////////
         // FIX.4.4 message
@@ -172,7 +587,7 @@ mod tests {
         }
 
         let b = Bytes::from(buf);
-        cracker.crack(&b);
+        cracker.crack(&b).unwrap();
 
         let a: Box<dyn FixApp> = cracker.app;
         let b: &TestApp = match a.as_any().downcast_ref::<TestApp>() {
@@ -195,9 +610,22 @@ mod tests {
             assert_eq!("Fix 4.4 Test", text.as_str_safe().unwrap());
         }
 
-        // FIX.4.0 defines MsgType as CHAR (which is, in effect, a string)
-        // FIX.4.4 (actually 4.2 onwards) differentiate char (single char) and
-        // string, and define MsgType as String explicitly
+        // each version keeps its own type for MsgType
+        let msg_type = |bs| Field::field_type(bs, 35).unwrap();
+        assert_eq!(
+            ("CHAR", FieldKind::String),
+            (
+                msg_type(BeginString::Fix40).fix_type,
+                msg_type(BeginString::Fix40).kind
+            )
+        );
+        assert_eq!(
+            ("STRING", FieldKind::String),
+            (
+                msg_type(BeginString::Fix44).fix_type,
+                msg_type(BeginString::Fix44).kind
+            )
+        );
         assert_eq!("A", fix40message.get_field(35).as_str());
         assert_eq!("A", fix40message.get_field(35).as_str());
 
@@ -210,22 +638,399 @@ mod tests {
             fix44message.to_string()
         );
     }
-}
 
-pub trait Crack<T> {
-    fn crack(s: &str) -> Result<T, NoSuchField>;
-}
+    #[test]
+    fn high_custom_tags() {
+        let app = TestApp { messages: vec![] };
+        let mut cracker = Cracker { app: Box::new(app) };
 
-impl Crack<BeginString> for BeginString {
-    fn crack(s: &str) -> Result<BeginString, NoSuchField> {
-        let re = regex::Regex::new("^8=(?P<begin_string>.+?)\x01").unwrap();
-        match re.captures(s) {
-            None => Err(NoSuchField { tag: 8 }),
-            Some(caps) => {
-                println!("Found {} captures: {:?}", caps.len(), caps);
-                let bs = caps.name("begin_string").unwrap().as_str().to_string();
-                BeginString::try_from(bs)
+        let msg =
+            Bytes::from("8=FIX.4.4\x0135=D\x0111=order-1\x0170000=BOOK SHELF\x01100000=7\x01");
+        cracker.crack(&msg).unwrap();
+
+        let app = cracker.app.as_any().downcast_ref::<TestApp>().unwrap();
+        let message = app.messages.first().unwrap();
+        assert_eq!("BOOK SHELF", message.get_field(70_000).as_str());
+        assert_eq!("7", message.get_field(100_000).as_str());
+        assert_eq!(
+            "8=FIX.4.4|35=D|11=order-1|70000=BOOK SHELF|100000=7|",
+            message.to_string()
+        );
+    }
+
+    #[derive(Default)]
+    struct OrderApp {
+        orders: Vec<String>,
+    }
+
+    impl FixApp for OrderApp {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn on_message(&mut self, message: Message) -> Result<(), UnsupportedMessageType> {
+            FIX42MessageCracker::crack(self, message)
+        }
+    }
+
+    impl FIX42MessageCracker for OrderApp {
+        fn on_new_order_single(
+            &mut self,
+            order: fix42::NewOrderSingle,
+        ) -> Result<(), UnsupportedMessageType> {
+            self.orders
+                .push(order.message.get_field(11).as_str().to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn typed_dispatch_by_msg_type() {
+        let mut cracker = Cracker {
+            app: Box::new(OrderApp::default()),
+        };
+
+        let order = Bytes::from("8=FIX.4.2\x0135=D\x0134=7\x0111=order-1\x01");
+        assert!(cracker.crack(&order).unwrap().is_none());
+
+        // News isn't handled by the app
+        let news = Bytes::from("8=FIX.4.2\x0135=B\x0134=8\x01148=Headline\x01");
+        let reject = cracker.crack(&news).unwrap().unwrap();
+        assert_eq!("j", reject.get_field(35).as_str());
+        assert_eq!("B", reject.get_field(372).as_str());
+        assert_eq!(3, reject.get_field(380).as_i32());
+        assert_eq!(8, reject.get_field(45).as_i32());
+
+        let app = cracker.app.as_any().downcast_ref::<OrderApp>().unwrap();
+        assert_eq!(vec!["order-1".to_string()], app.orders);
+    }
+
+    #[test]
+    fn unsupported_message_rejected_per_version() {
+        let mut cracker = Cracker {
+            app: Box::new(OrderApp::default()),
+        };
+
+        // FIX.4.0 has no BusinessMessageReject, so a session Reject is used
+        let news = Bytes::from("8=FIX.4.0\x0135=B\x0134=2\x01");
+        let reject = cracker.crack(&news).unwrap().unwrap();
+        assert_eq!("3", reject.get_field(35).as_str());
+        assert_eq!(2, reject.get_field(45).as_i32());
+        assert!(reject.get_field_safe(372).is_err());
+    }
+
+    #[test]
+    fn malformed_fields_are_errors() {
+        let mut cracker = Cracker {
+            app: Box::new(TestApp { messages: vec![] }),
+        };
+
+        // Price (44) is a decimal
+        let msg = Bytes::from("8=FIX.4.4\x0135=D\x0144=12.x5\x01");
+        assert_eq!(
+            ParseError::new(Some(44), "12.x5", ValueType::Decimal, 18),
+            cracker.crack(&msg).unwrap_err()
+        );
+
+        let app = cracker.app.as_any().downcast_ref::<TestApp>().unwrap();
+        assert!(app.messages.is_empty());
+
+        let cases = [
+            ("", ParseError::new(None, "", ValueType::BeginString, 0)),
+            (
+                "35=D\x01",
+                ParseError::new(None, "35=D", ValueType::BeginString, 0),
+            ),
+            (
+                "8=FIX.9.9\x01",
+                ParseError::new(Some(8), "FIX.9.9", ValueType::BeginString, 2),
+            ),
+            (
+                "8=FIX.4.2\x01abc=1\x01",
+                ParseError::new(None, "abc", ValueType::Tag, 10),
+            ),
+            (
+                "8=FIX.4.2\x01=1\x01",
+                ParseError::new(None, "", ValueType::Tag, 10),
+            ),
+            (
+                "8=FIX.4.2\x0135\x01",
+                ParseError::new(None, "35", ValueType::Field, 10),
+            ),
+            (
+                "8=FIX.4.2\x0138=1e9\x01",
+                ParseError::new(Some(38), "1e9", ValueType::Int, 13),
+            ),
+            (
+                "8=FIX.4.2\x0138=99999999999\x01",
+                ParseError::new(Some(38), "99999999999", ValueType::Int, 13),
+            ),
+            (
+                "8=FIX.4.2\x0154=12\x01",
+                ParseError::new(Some(54), "12", ValueType::Char, 13),
+            ),
+            (
+                "8=FIX.4.2\x0154=\x01",
+                ParseError::new(Some(54), "", ValueType::Char, 13),
+            ),
+        ];
+        for (msg, expected) in cases {
+            assert_eq!(
+                Err(expected),
+                crack_message(&Bytes::from(msg)).map(|m| m.to_string())
+            );
+        }
+    }
+
+    /// Deterministic xorshift, so a failing case can be replayed
+    fn rng(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    #[test]
+    fn fuzz_cracking_never_panics() {
+        let valid = frame("35=D\x0134=2\x0111=order-1\x0138=100\x0144=10.25\x0154=1\x0155=ABC\x01");
+        let alphabet = b"0123456789=.-+\x01ABCx8\xff";
+
+        let mut seed = 0x5eed_u64;
+        for _ in 0..20_000 {
+            let mut mutated = valid.to_vec();
+            for _ in 0..=rng(&mut seed) % 4 {
+                let at = (rng(&mut seed) % mutated.len() as u64) as usize;
+                let byte = alphabet[(rng(&mut seed) % alphabet.len() as u64) as usize];
+                match rng(&mut seed) % 3 {
+                    0 => mutated[at] = byte,
+                    1 => mutated.insert(at, byte),
+                    _ => {
+                        mutated.remove(at);
+                    }
+                }
+                if mutated.is_empty() {
+                    break;
+                }
+            }
+
+            let _ = crack_message(&Bytes::from(mutated.clone()));
+
+            // every error consumes input, so this always terminates
+            let mut buf = BytesMut::from(&mutated[..]);
+            for _ in 0..mutated.len() {
+                match next_frame(&mut buf) {
+                    Ok(Some(frame)) => {
+                        let _ = crack_message(&frame);
+                    }
+                    Ok(None) => break,
+                    Err(_) => {}
+                }
             }
         }
     }
+
+    #[test]
+    fn huge_body_length_is_invalid() {
+        let mut buf = BytesMut::from(&b"8=FIX.4.2\x019=18446744073709551615\x0135=0\x01"[..]);
+        assert_eq!(Err(FrameError::InvalidBodyLength), next_frame(&mut buf));
+    }
+
+    #[test]
+    fn scans_the_standard_header() {
+        let frame = frame("35=D\x0134=2\x01");
+        let header = scan_header(&frame).unwrap();
+        assert_eq!(BeginString::Fix42, header.begin_string);
+        assert_eq!(Some(10), header.body_length);
+        assert_eq!(Some("D"), header.msg_type);
+
+        // BodyLength is optional, MsgType is only looked for in its place
+        let header = scan_header(b"8=FIX.4.4\x0135=W\x01").unwrap();
+        assert_eq!((None, Some("W")), (header.body_length, header.msg_type));
+        let header = scan_header(b"8=FIX.4.0\x0134=1\x0135=W\x01").unwrap();
+        assert_eq!(BeginString::Fix40, header.begin_string);
+        assert_eq!(None, header.msg_type);
+
+        assert_eq!(
+            ParseError::new(Some(9), "1O", ValueType::Int, 12),
+            scan_header(b"8=FIX.4.2\x019=1O\x0135=0\x01").unwrap_err()
+        );
+        assert_eq!(
+            ParseError::new(None, "9=10", ValueType::BeginString, 0),
+            scan_header(b"9=10\x018=FIX.4.2\x01").unwrap_err()
+        );
+
+        assert_eq!(
+            BeginString::Fix43,
+            BeginString::crack("8=FIX.4.3\x0135=0\x01").unwrap()
+        );
+        assert!(BeginString::crack("8=FIX.4\x01").is_err());
+    }
+
+    #[test]
+    fn fixt_messages_use_their_application_version() {
+        // session level messages are FIXT.1.1 throughout
+        let logon = Bytes::from("8=FIXT.1.1\x0135=A\x0134=1\x011137=9\x01108=30\x01");
+        let logon = crack_message(&logon).unwrap();
+        assert_eq!("9", logon.get_field(1137).as_str());
+
+        // Side (54) is a string in FIX.4.0 and a char from FIX.4.2
+        let order = "8=FIXT.1.1\x0135=D\x0134=2\x0152=20240102-09:30:00\x0144=10.5\x0154=1\x01";
+        let as_fix40 = Bytes::from(order.replace("52=", "1128=2\x0152="));
+        let message = crack_message(&as_fix40).unwrap();
+        assert_eq!("1", message.get_field(54).as_str());
+        assert_eq!("2", message.get_field(1128).as_str());
+
+        let by_default = crack_message_with(&Bytes::from(order), Some(BeginString::Fix50Sp2));
+        let message = by_default.unwrap();
+        assert_eq!('1', message.get_field(54).as_char());
+        assert_eq!(&Decimal::new(105, 1), message.get_field(44).as_decimal());
+
+        assert_eq!(
+            ParseError::new(Some(1128), "", ValueType::ApplVerID, 0),
+            crack_message(&Bytes::from(order)).unwrap_err()
+        );
+        let unknown = Bytes::from(order.replace("52=", "1128=X\x0152="));
+        assert_eq!(
+            ParseError::new(Some(1128), "X", ValueType::ApplVerID, 26),
+            crack_message_with(&unknown, Some(BeginString::Fix50Sp2)).unwrap_err()
+        );
+    }
+
+    fn frame(body: &str) -> BytesMut {
+        let head = format!("8=FIX.4.2\x019={}\x01{}", body.len(), body);
+        let sum = crate::cracker::checksum(head.as_bytes());
+        BytesMut::from(format!("{}10={:03}\x01", head, sum).as_bytes())
+    }
+
+    #[test]
+    fn frames_complete_messages() {
+        let mut buf = frame("35=0\x0134=2\x01");
+        buf.extend_from_slice(&frame("35=1\x0134=3\x01112=test\x01"));
+
+        let first = next_frame(&mut buf).unwrap().unwrap();
+        let second = next_frame(&mut buf).unwrap().unwrap();
+        assert!(next_frame(&mut buf).unwrap().is_none());
+        assert!(buf.is_empty());
+
+        let first = crack_message(&first).unwrap();
+        let second = crack_message(&second).unwrap();
+        assert_eq!("0", first.get_field(35).as_str());
+        assert_eq!("1", second.get_field(35).as_str());
+        assert_eq!(3, second.get_field(34).as_i32());
+    }
+
+    #[test]
+    fn waits_for_partial_messages() {
+        let whole = frame("35=0\x0134=2\x01");
+        let (head, tail) = whole.split_at(whole.len() - 4);
+
+        let mut buf = BytesMut::from(head);
+        assert!(next_frame(&mut buf).unwrap().is_none());
+        assert_eq!(head.len(), buf.len());
+
+        buf.extend_from_slice(tail);
+        assert!(next_frame(&mut buf).unwrap().is_some());
+    }
+
+    #[test]
+    fn skips_garbage_and_bad_checksums() {
+        let mut buf = BytesMut::from(&b"garbage"[..]);
+        let mut corrupt = frame("35=0\x0134=2\x01");
+        let len = corrupt.len();
+        corrupt[len - 2] = b'9';
+        corrupt[len - 3] = b'9';
+        buf.extend_from_slice(&corrupt);
+        buf.extend_from_slice(&frame("35=0\x0134=3\x01"));
+
+        assert!(matches!(
+            next_frame(&mut buf),
+            Err(FrameError::CheckSumMismatch { .. })
+        ));
+        let next = crack_message(&next_frame(&mut buf).unwrap().unwrap()).unwrap();
+        assert_eq!(3, next.get_field(34).as_i32());
+    }
+
+    #[test]
+    fn sofh_frames_mixed_encodings() {
+        let tag_value = frame("35=0\x0134=2\x01");
+        let mut buf = BytesMut::new();
+        encoder::put_sofh(&mut buf, EncodingType::TagValue, &tag_value);
+        encoder::put_sofh(&mut buf, EncodingType::SbeLittleEndian, &[46, 0, 1, 0]);
+        encoder::put_sofh(&mut buf, EncodingType::Json, b"{}");
+        encoder::put_sofh(&mut buf, EncodingType::Other(0x0001), &[]);
+        assert_eq!(
+            [0, 0, 0, 6 + tag_value.len() as u8, 0xF0, 0x00],
+            buf[..SOFH_LENGTH]
+        );
+
+        let first = next_sofh_frame(&mut buf).unwrap().unwrap();
+        assert_eq!(EncodingType::TagValue, first.encoding_type);
+        assert_eq!(
+            "0",
+            crack_message(&first.payload)
+                .unwrap()
+                .get_field(35)
+                .as_str()
+        );
+
+        let second = next_sofh_frame(&mut buf).unwrap().unwrap();
+        assert_eq!(EncodingType::SbeLittleEndian, second.encoding_type);
+        assert_eq!(&[46, 0, 1, 0], &second.payload[..]);
+
+        let third = next_sofh_frame(&mut buf).unwrap().unwrap();
+        assert_eq!(EncodingType::Json, third.encoding_type);
+        assert_eq!(b"{}", &third.payload[..]);
+
+        let empty = next_sofh_frame(&mut buf).unwrap().unwrap();
+        assert_eq!(EncodingType::Other(1), empty.encoding_type);
+        assert!(empty.payload.is_empty());
+
+        assert!(next_sofh_frame(&mut buf).unwrap().is_none());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn sofh_waits_for_truncated_headers_and_messages() {
+        let mut order = Message::of_type("D");
+        order.set_field(Field::String(8, "FIX.4.4".to_string()));
+        order.set_field(Field::String(11, "order-1".to_string()));
+        let mut whole = BytesMut::new();
+        encoder::encode_sofh(&order, &mut whole);
+        encoder::encode_sofh(&order, &mut whole);
+
+        // fed a byte at a time, nothing comes out until a message is complete
+        let mut buf = BytesMut::new();
+        let mut frames = vec![];
+        for b in whole.iter() {
+            buf.put_u8(*b);
+            if let Some(frame) = next_sofh_frame(&mut buf).unwrap() {
+                frames.push(frame);
+            }
+        }
+        assert_eq!(2, frames.len());
+        assert!(buf.is_empty());
+        let cracked = crack_message(&frames[1].payload).unwrap();
+        assert_eq!("order-1", cracked.get_field(11).as_str());
+
+        // a length which can't even cover the header
+        let mut buf = BytesMut::from(&[0, 0, 0, 5, 0xF0, 0x00, b'8'][..]);
+        assert_eq!(
+            Err(FrameError::InvalidMessageLength(5)),
+            next_sofh_frame(&mut buf)
+        );
+        assert_eq!(b"8", &buf[..]);
+
+        // a large message is waited for, a huge one refused
+        let large = u32::try_from(MAX_SOFH_MESSAGE_LENGTH).unwrap();
+        let mut buf = BytesMut::new();
+        buf.put_u32(large);
+        buf.put_u16(0x5BE0);
+        assert_eq!(Ok(None), next_sofh_frame(&mut buf));
+        assert_eq!(SOFH_LENGTH, buf.len());
+        let mut buf = BytesMut::from(&[0xFF, 0xFF, 0xFF, 0xFF, 0x5B, 0xE0][..]);
+        assert_eq!(
+            Err(FrameError::InvalidMessageLength(u32::MAX)),
+            next_sofh_frame(&mut buf)
+        );
+    }
 }
@@ -0,0 +1,855 @@
+pub mod validator;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::Formatter;
+use std::path::Path;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use minidom::{Element, NSChoice};
+
+use crate::model::field::MsgCat;
+use crate::model::message::Message;
+
+// minidom insists on a namespace, which the FIX specs don't declare
+const DEFAULT_NAMESPACE: &str = "urn:fix-oxide:dictionary";
+
+/// FIX data dictionary, loaded at runtime from a spec in the `spec/*.xml`
+/// (QuickFIX) format.
+#[derive(Debug, Clone)]
+pub struct DataDictionary {
+    begin_string: String,
+    fields: HashMap<u32, FieldDef>,
+    field_tags: HashMap<String, u32>,
+    header: Vec<Member>,
+    trailer: Vec<Member>,
+    messages: HashMap<String, MessageDef>,
+    components: HashMap<String, ComponentDef>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldDef {
+    pub tag: u32,
+    pub name: String,
+    /// FIX type as named in the spec, e.g. `PRICE`, `UTCTIMESTAMP`
+    pub field_type: String,
+    pub values: Vec<FieldValue>,
+    /// FIXML attribute name, e.g. `Px` for Price (44)
+    pub abbr: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldValue {
+    pub value: String,
+    pub description: String,
+}
+
+/// Entry in the layout of a header, trailer, message, component or group
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Member {
+    Field {
+        tag: u32,
+        required: bool,
+    },
+    Component {
+        name: String,
+        required: bool,
+    },
+    /// Repeating group, counted by the `NumInGroup` field `tag`
+    Group {
+        tag: u32,
+        required: bool,
+        members: Vec<Member>,
+    },
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MessageDef {
+    pub name: String,
+    pub msg_type: String,
+    pub msg_cat: MsgCat,
+    pub members: Vec<Member>,
+    /// FIXML element name, e.g. `Order` for NewOrderSingle
+    pub abbr: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ComponentDef {
+    pub name: String,
+    pub members: Vec<Member>,
+    /// FIXML element name, e.g. `Instrmt` for Instrument
+    pub abbr: Option<String>,
+}
+
+impl DataDictionary {
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can't be read or isn't a valid dictionary
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, DictionaryError> {
+        std::fs::read_to_string(path)
+            .map_err(DictionaryError::Io)?
+            .parse()
+    }
+
+    /// `BeginString` (8) described by the dictionary, e.g. `FIX.4.4`, `FIXT.1.1`
+    #[must_use]
+    pub fn begin_string(&self) -> &str {
+        &self.begin_string
+    }
+
+    #[must_use]
+    pub fn field(&self, tag: u32) -> Option<&FieldDef> {
+        self.fields.get(&tag)
+    }
+
+    #[must_use]
+    pub fn field_by_name(&self, name: &str) -> Option<&FieldDef> {
+        self.field_tags.get(name).and_then(|tag| self.field(*tag))
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &FieldDef> {
+        self.fields.values().sorted_by_key(|f| f.tag)
+    }
+
+    #[must_use]
+    pub fn message(&self, msg_type: &str) -> Option<&MessageDef> {
+        self.messages.get(msg_type)
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = &MessageDef> {
+        self.messages.values().sorted_by(|a, b| a.name.cmp(&b.name))
+    }
+
+    #[must_use]
+    pub fn component(&self, name: &str) -> Option<&ComponentDef> {
+        self.components.get(name)
+    }
+
+    #[must_use]
+    pub fn header(&self) -> &[Member] {
+        &self.header
+    }
+
+    #[must_use]
+    pub fn trailer(&self) -> &[Member] {
+        &self.trailer
+    }
+
+    #[must_use]
+    pub fn is_header_field(&self, tag: u32) -> bool {
+        self.tags_of(&self.header).contains(&tag)
+    }
+
+    #[must_use]
+    pub fn is_trailer_field(&self, tag: u32) -> bool {
+        self.tags_of(&self.trailer).contains(&tag)
+    }
+
+    /// Every tag which may appear in the header, including those of its
+    /// repeating groups
+    #[must_use]
+    pub fn header_fields(&self) -> HashSet<u32> {
+        self.tags_of(&self.header)
+    }
+
+    /// Every tag which may appear in the trailer
+    #[must_use]
+    pub fn trailer_fields(&self) -> HashSet<u32> {
+        self.tags_of(&self.trailer)
+    }
+
+    /// Every tag which may appear in the body of `msg_type`, including those
+    /// pulled in through components and repeating groups.
+    #[must_use]
+    pub fn message_fields(&self, msg_type: &str) -> HashSet<u32> {
+        self.message(msg_type)
+            .map(|m| self.tags_of(&m.members))
+            .unwrap_or_default()
+    }
+
+    /// Top-level tags which must be present in the body of `msg_type`.
+    ///
+    /// Fields of a required component are required; fields inside optional
+    /// components and repeating groups are not.
+    #[must_use]
+    pub fn required_fields(&self, msg_type: &str) -> Vec<u32> {
+        let mut required = vec![];
+        if let Some(message) = self.message(msg_type) {
+            self.collect_required(&message.members, &mut required);
+        }
+        required
+    }
+
+    #[must_use]
+    pub fn required_header_fields(&self) -> Vec<u32> {
+        let mut required = vec![];
+        self.collect_required(&self.header, &mut required);
+        required
+    }
+
+    #[must_use]
+    pub fn required_trailer_fields(&self) -> Vec<u32> {
+        let mut required = vec![];
+        self.collect_required(&self.trailer, &mut required);
+        required
+    }
+
+    /// Whether `value` is legal for `tag`; fields without an enumeration
+    /// accept any value.
+    #[must_use]
+    pub fn is_valid_value(&self, tag: u32, value: &str) -> bool {
+        self.field(tag).map_or(true, |f| {
+            f.values.is_empty() || f.values.iter().any(|v| v.value == value)
+        })
+    }
+
+    #[must_use]
+    pub fn value_description(&self, tag: u32, value: &str) -> Option<&str> {
+        self.field(tag)?
+            .values
+            .iter()
+            .find(|v| v.value == value)
+            .map(|v| v.description.as_str())
+    }
+
+    /// Render `message` with field names and enum descriptions, e.g.
+    /// `MsgType(35)=D[NEW_ORDER_SINGLE]|`
+    #[must_use]
+    pub fn pretty_print(&self, message: &Message) -> String {
+        message
+            .header()
+            .iter()
+            .chain(message.body().iter())
+            .chain(message.trailer().iter())
+            .map(|field| {
+                let tag = field.tag();
+                let value = field.value_string();
+                let name = self.field(tag).map_or("Unknown", |f| f.name.as_str());
+                match self.value_description(tag, &value) {
+                    Some(description) => format!("{}({})={}[{}]|", name, tag, value, description),
+                    None => format!("{}({})={}|", name, tag, value),
+                }
+            })
+            .collect()
+    }
+
+    fn tags_of(&self, members: &[Member]) -> HashSet<u32> {
+        let mut tags = HashSet::new();
+        self.collect_tags(members, &mut tags);
+        tags
+    }
+
+    fn collect_tags(&self, members: &[Member], tags: &mut HashSet<u32>) {
+        for member in members {
+            match member {
+                Member::Field { tag, .. } => {
+                    tags.insert(*tag);
+                }
+                Member::Component { name, .. } => {
+                    if let Some(component) = self.component(name) {
+                        self.collect_tags(&component.members, tags);
+                    }
+                }
+                Member::Group { tag, members, .. } => {
+                    tags.insert(*tag);
+                    self.collect_tags(members, tags);
+                }
+            }
+        }
+    }
+
+    fn collect_required(&self, members: &[Member], required: &mut Vec<u32>) {
+        for member in members {
+            match member {
+                Member::Field {
+                    tag,
+                    required: true,
+                }
+                | Member::Group {
+                    tag,
+                    required: true,
+                    ..
+                } => required.push(*tag),
+                Member::Component {
+                    name,
+                    required: true,
+                } => {
+                    if let Some(component) = self.component(name) {
+                        self.collect_required(&component.members, required);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl FromStr for DataDictionary {
+    type Err = DictionaryError;
+
+    fn from_str(xml: &str) -> Result<Self, Self::Err> {
+        let root = parse_root(xml)?;
+        let mut dictionary = Self {
+            begin_string: begin_string_of(&root)?,
+            fields: HashMap::new(),
+            field_tags: HashMap::new(),
+            header: vec![],
+            trailer: vec![],
+            messages: HashMap::new(),
+            components: HashMap::new(),
+        };
+        dictionary.merge(&root)?;
+        Ok(dictionary)
+    }
+}
+
+impl DataDictionary {
+    /// Layer a venue-specific extension over the dictionary.
+    ///
+    /// Extensions use the same format as the specs and only need to hold what
+    /// they change: new or redefined `<fields>`, extra members for existing
+    /// messages, components, the header or trailer (matched by `msgtype` /
+    /// `name`), and entirely new messages or components. A member which is
+    /// already present is replaced, so an extension can make a field required.
+    ///
+    /// A `<field>` without a `type`, `<message>` or `<component>` which is
+    /// already defined only needs its `abbr` attribute to set its FIXML
+    /// abbreviation; see `spec/extensions/examples/fixml.xml`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the extension isn't valid; the dictionary may have
+    /// been partially extended.
+    pub fn extend(&mut self, xml: &str) -> Result<(), DictionaryError> {
+        let root = parse_root(xml)?;
+        self.merge(&root)
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can't be read or isn't a valid extension
+    pub fn load_extension<P: AsRef<Path>>(&mut self, path: P) -> Result<(), DictionaryError> {
+        let xml = std::fs::read_to_string(path).map_err(DictionaryError::Io)?;
+        self.extend(&xml)
+    }
+
+    fn merge(&mut self, root: &Element) -> Result<(), DictionaryError> {
+        for def in parse_field_defs(root, &self.fields)?.into_values() {
+            if let Some(replaced) = self.fields.get(&def.tag) {
+                self.field_tags.remove(&replaced.name);
+            }
+            self.field_tags.insert(def.name.clone(), def.tag);
+            self.fields.insert(def.tag, def);
+        }
+
+        if let Some(header) = child(root, "header") {
+            let members = parse_members(header, &self.field_tags)?;
+            merge_members(&mut self.header, members);
+        }
+        if let Some(trailer) = child(root, "trailer") {
+            let members = parse_members(trailer, &self.field_tags)?;
+            merge_members(&mut self.trailer, members);
+        }
+
+        if let Some(defs) = child(root, "components") {
+            for def in defs.children().filter(|e| e.name() == "component") {
+                let name = required_attr(def, "name")?.to_string();
+                let members = parse_members(def, &self.field_tags)?;
+                let abbr = def.attr("abbr").map(ToString::to_string);
+                match self.components.get_mut(&name) {
+                    Some(component) => {
+                        merge_members(&mut component.members, members);
+                        component.abbr = abbr.or(component.abbr.take());
+                    }
+                    None => {
+                        self.components.insert(
+                            name.clone(),
+                            ComponentDef {
+                                name,
+                                members,
+                                abbr,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(defs) = child(root, "messages") {
+            for def in defs.children().filter(|e| e.name() == "message") {
+                let msg_type = required_attr(def, "msgtype")?.to_string();
+                let members = parse_members(def, &self.field_tags)?;
+                let abbr = def.attr("abbr").map(ToString::to_string);
+                if let Some(message) = self.messages.get_mut(&msg_type) {
+                    merge_members(&mut message.members, members);
+                    message.abbr = abbr.or(message.abbr.take());
+                    continue;
+                }
+
+                let msg_cat = required_attr(def, "msgcat")?;
+                let message = MessageDef {
+                    name: required_attr(def, "name")?.to_string(),
+                    msg_type: msg_type.clone(),
+                    msg_cat: MsgCat::from_str(msg_cat).map_err(|_| {
+                        DictionaryError::Invalid(format!("unknown msgcat '{}'", msg_cat))
+                    })?,
+                    members,
+                    abbr,
+                };
+                // new messages become legal MsgType (35) values
+                if let Some(msg_types) = self.fields.get_mut(&35) {
+                    if !msg_types.values.iter().any(|v| v.value == msg_type) {
+                        msg_types.values.push(FieldValue {
+                            value: msg_type.clone(),
+                            description: message.name.to_uppercase(),
+                        });
+                    }
+                }
+                self.messages.insert(msg_type, message);
+            }
+        }
+
+        self.check_components()
+    }
+}
+
+impl DataDictionary {
+    fn check_components(&self) -> Result<(), DictionaryError> {
+        fn check(dd: &DataDictionary, members: &[Member]) -> Result<(), DictionaryError> {
+            for member in members {
+                match member {
+                    Member::Component { name, .. } if dd.component(name).is_none() => {
+                        return Err(DictionaryError::Invalid(format!(
+                            "unknown component '{}'",
+                            name
+                        )));
+                    }
+                    Member::Group { members, .. } => check(dd, members)?,
+                    _ => {}
+                }
+            }
+            Ok(())
+        }
+
+        check(self, &self.header)?;
+        check(self, &self.trailer)?;
+        for component in self.components.values() {
+            check(self, &component.members)?;
+        }
+        for message in self.messages.values() {
+            check(self, &message.members)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_root(xml: &str) -> Result<Element, DictionaryError> {
+    let root = parse_xml(xml)?;
+    if root.name() == "fix" {
+        Ok(root)
+    } else {
+        Err(DictionaryError::Invalid(format!(
+            "expected <fix> root element, found <{}>",
+            root.name()
+        )))
+    }
+}
+
+fn parse_xml(xml: &str) -> Result<Element, DictionaryError> {
+    // minidom rejects comments and un-namespaced documents; the FIX specs (and
+    // hand-edited copies of them) are both
+    let without_comments = regex::Regex::new("(?s)<!--.*?-->")
+        .map_err(|e| DictionaryError::Invalid(e.to_string()))?
+        .replace_all(xml, "");
+
+    let root_start = without_comments
+        .find("<fix")
+        .ok_or_else(|| DictionaryError::Invalid("no <fix> root element".to_string()))?;
+    let root_end = without_comments[root_start..]
+        .find('>')
+        .map_or(without_comments.len(), |i| root_start + i);
+
+    let xml = if without_comments[root_start..root_end].contains("xmlns") {
+        without_comments.to_string()
+    } else {
+        format!(
+            "{} xmlns='{}'{}",
+            &without_comments[..root_start + 4],
+            DEFAULT_NAMESPACE,
+            &without_comments[root_start + 4..]
+        )
+    };
+    xml.parse::<Element>().map_err(DictionaryError::Xml)
+}
+
+fn begin_string_of(root: &Element) -> Result<String, DictionaryError> {
+    let fix_type = root.attr("type").unwrap_or("FIX");
+    let major = required_attr(root, "major")?;
+    let minor = required_attr(root, "minor")?;
+    match root.attr("servicepack") {
+        Some(sp) if sp != "0" => Ok(format!("{}.{}.{}SP{}", fix_type, major, minor, sp)),
+        _ => Ok(format!("{}.{}.{}", fix_type, major, minor)),
+    }
+}
+
+fn parse_field_defs(
+    root: &Element,
+    existing: &HashMap<u32, FieldDef>,
+) -> Result<HashMap<u32, FieldDef>, DictionaryError> {
+    let mut fields = HashMap::new();
+    if let Some(defs) = child(root, "fields") {
+        for def in defs.children().filter(|e| e.name() == "field") {
+            let number = required_attr(def, "number")?;
+            let tag = number.parse::<u32>().map_err(|_| {
+                DictionaryError::Invalid(format!("invalid field number '{}'", number))
+            })?;
+            let abbr = def.attr("abbr").map(ToString::to_string);
+            if let (None, Some(defined)) = (def.attr("type"), existing.get(&tag)) {
+                fields.insert(
+                    tag,
+                    FieldDef {
+                        abbr: abbr.or_else(|| defined.abbr.clone()),
+                        ..defined.clone()
+                    },
+                );
+                continue;
+            }
+            let values = def
+                .children()
+                .filter(|e| e.name() == "value")
+                .map(|v| {
+                    Ok(FieldValue {
+                        value: required_attr(v, "enum")?.to_string(),
+                        description: v.attr("description").unwrap_or_default().to_string(),
+                    })
+                })
+                .collect::<Result<Vec<FieldValue>, DictionaryError>>()?;
+            fields.insert(
+                tag,
+                FieldDef {
+                    tag,
+                    name: required_attr(def, "name")?.to_string(),
+                    field_type: required_attr(def, "type")?.to_string(),
+                    values,
+                    abbr,
+                },
+            );
+        }
+    }
+    Ok(fields)
+}
+
+fn parse_members(
+    parent: &Element,
+    field_tags: &HashMap<String, u32>,
+) -> Result<Vec<Member>, DictionaryError> {
+    let tag_of = |name: &str| {
+        field_tags.get(name).copied().ok_or_else(|| {
+            DictionaryError::Invalid(format!(
+                "<{} name='{}'> refers to unknown field '{}'",
+                parent.name(),
+                parent.attr("name").unwrap_or_default(),
+                name
+            ))
+        })
+    };
+
+    parent
+        .children()
+        .filter_map(|e| {
+            let required = e.attr("required") == Some("Y");
+            let member = match e.name() {
+                "field" => required_attr(e, "name")
+                    .and_then(tag_of)
+                    .map(|tag| Member::Field { tag, required }),
+                "component" => required_attr(e, "name").map(|name| Member::Component {
+                    name: name.to_string(),
+                    required,
+                }),
+                "group" => required_attr(e, "name").and_then(tag_of).and_then(|tag| {
+                    Ok(Member::Group {
+                        tag,
+                        required,
+                        members: parse_members(e, field_tags)?,
+                    })
+                }),
+                _ => return None,
+            };
+            Some(member)
+        })
+        .collect()
+}
+
+fn merge_members(members: &mut Vec<Member>, additions: Vec<Member>) {
+    fn same(a: &Member, b: &Member) -> bool {
+        match (a, b) {
+            (Member::Field { tag: a, .. }, Member::Field { tag: b, .. })
+            | (Member::Group { tag: a, .. }, Member::Group { tag: b, .. }) => a == b,
+            (Member::Component { name: a, .. }, Member::Component { name: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+
+    for addition in additions {
+        match members.iter_mut().find(|m| same(m, &addition)) {
+            Some(existing) => *existing = addition,
+            None => members.push(addition),
+        }
+    }
+}
+
+fn child<'a>(parent: &'a Element, name: &str) -> Option<&'a Element> {
+    parent.get_child(name, NSChoice::Any)
+}
+
+fn required_attr<'a>(element: &'a Element, name: &str) -> Result<&'a str, DictionaryError> {
+    element.attr(name).ok_or_else(|| {
+        DictionaryError::Invalid(format!(
+            "<{}> is missing the '{}' attribute",
+            element.name(),
+            name
+        ))
+    })
+}
+
+#[derive(Debug)]
+pub enum DictionaryError {
+    Io(std::io::Error),
+    Xml(minidom::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for DictionaryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DictionaryError::Io(e) => write!(f, "could not read dictionary: {}", e),
+            DictionaryError::Xml(e) => write!(f, "dictionary is not valid XML: {}", e),
+            DictionaryError::Invalid(reason) => write!(f, "invalid dictionary: {}", reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dictionary::{DataDictionary, Member};
+    use crate::model::field::MsgCat;
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+
+    fn spec(file: &str) -> DataDictionary {
+        DataDictionary::load(format!("{}/spec/{}", env!("CARGO_MANIFEST_DIR"), file)).unwrap()
+    }
+
+    #[test]
+    fn loads_every_spec() {
+        let expected = [
+            ("FIX40.xml", "FIX.4.0"),
+            ("FIX41.xml", "FIX.4.1"),
+            ("FIX42.xml", "FIX.4.2"),
+            ("FIX43.xml", "FIX.4.3"),
+            ("FIX44.xml", "FIX.4.4"),
+            ("FIX50.xml", "FIX.5.0"),
+            ("FIX50SP1.xml", "FIX.5.0SP1"),
+            ("FIX50SP2.xml", "FIX.5.0SP2"),
+            ("FIXT11.xml", "FIXT.1.1"),
+        ];
+        for (file, begin_string) in expected {
+            let dd = spec(file);
+            assert_eq!(begin_string, dd.begin_string());
+            assert!(dd.messages().count() > 0, "{} has no messages", file);
+        }
+    }
+
+    #[test]
+    fn fields_and_enums() {
+        let dd = spec("FIX44.xml");
+
+        let side = dd.field(54).unwrap();
+        assert_eq!("Side", side.name);
+        assert_eq!("CHAR", side.field_type);
+        assert_eq!(Some("BUY"), dd.value_description(54, "1"));
+        assert!(dd.is_valid_value(54, "2"));
+        assert!(!dd.is_valid_value(54, "Z"));
+        // no enumeration, so anything goes
+        assert!(dd.is_valid_value(58, "anything"));
+
+        assert_eq!(44, dd.field_by_name("Price").unwrap().tag);
+    }
+
+    #[test]
+    fn messages_components_and_groups() {
+        let dd = spec("FIX44.xml");
+
+        let order = dd.message("D").unwrap();
+        assert_eq!("NewOrderSingle", order.name);
+        assert_eq!(MsgCat::App, order.msg_cat);
+        assert!(order.members.contains(&Member::Component {
+            name: "Instrument".to_string(),
+            required: true
+        }));
+
+        let required = dd.required_fields("D");
+        for tag in [11, 54, 60, 40] {
+            assert!(required.contains(&tag), "{} should be required", tag);
+        }
+        // Account (1) is optional; NoPartyIDs (453) is in an optional component
+        assert!(!required.contains(&1));
+        assert!(!required.contains(&453));
+
+        let fields = dd.message_fields("D");
+        assert!(fields.contains(&453));
+        assert!(fields.contains(&448)); // PartyID, inside the NoPartyIDs group
+        assert!(!fields.contains(&150)); // ExecType
+
+        assert!(dd.is_header_field(49));
+        assert!(dd.is_trailer_field(10));
+        assert!(dd.required_header_fields().contains(&52));
+
+        let logon = dd.message("A").unwrap();
+        assert_eq!(MsgCat::Admin, logon.msg_cat);
+    }
+
+    #[test]
+    fn custom_dictionary() {
+        let xml = "<?xml version='1.0'?>
+            <!-- venue copy -->
+            <fix type='FIX' major='4' minor='2'>
+              <header><field name='MsgType' required='Y'/></header>
+              <trailer/>
+              <messages>
+                <message name='Heartbeat' msgtype='0' msgcat='admin'/>
+                <message name='News' msgtype='B' msgcat='app'>
+                  <component name='Headline' required='Y'/>
+                  <component name='Story' required='N'/>
+                </message>
+              </messages>
+              <components>
+                <component name='Headline'>
+                  <field name='Headline' required='Y'/>
+                </component>
+                <component name='Story'>
+                  <field name='Text' required='Y'/>
+                </component>
+              </components>
+              <fields>
+                <field number='35' name='MsgType' type='STRING'>
+                  <value enum='0' description='HEARTBEAT'/>
+                </field>
+                <field number='58' name='Text' type='STRING'/>
+                <field number='148' name='Headline' type='STRING'/>
+              </fields>
+            </fix>";
+        let dd: DataDictionary = xml.parse().unwrap();
+        assert_eq!("FIX.4.2", dd.begin_string());
+        assert_eq!(vec![35], dd.required_header_fields());
+
+        // required fields of required components only
+        assert_eq!(vec![148], dd.required_fields("B"));
+        assert!(dd.message_fields("B").contains(&58));
+
+        let mut heartbeat = Message::of_type("0");
+        heartbeat.set_field(Field::String(58, "hi".to_string()));
+        assert_eq!(
+            "MsgType(35)=0[HEARTBEAT]|Text(58)=hi|",
+            dd.pretty_print(&heartbeat)
+        );
+    }
+
+    #[test]
+    fn venue_extension() {
+        let mut dd = spec("FIX44.xml");
+        dd.load_extension(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/spec/extensions/examples/venue.xml"
+        ))
+        .unwrap();
+
+        let capacity = dd.field_by_name("VenueOrderCapacity").unwrap();
+        assert_eq!(5002, capacity.tag);
+        assert_eq!("CHAR", capacity.field_type);
+        assert_eq!(Some("PRINCIPAL"), dd.value_description(5002, "P"));
+        assert_eq!("INT", dd.field(20001).unwrap().field_type);
+
+        // existing message gains the venue's fields, keeping its own
+        assert!(dd.required_fields("D").contains(&5002));
+        assert!(dd.required_fields("D").contains(&11));
+        assert!(dd.message_fields("D").contains(&5001));
+
+        let status = dd.message("U1").unwrap();
+        assert_eq!("VenueBookStatus", status.name);
+        assert!(dd.is_valid_value(35, "U1"));
+        assert_eq!(vec![5001, 20001], dd.required_fields("U1"));
+    }
+
+    #[test]
+    fn extension_replaces_members() {
+        let mut dd = spec("FIX44.xml");
+        assert!(!dd.required_fields("D").contains(&1));
+
+        dd.extend(
+            "<fix>
+              <messages>
+                <message name='NewOrderSingle' msgtype='D'>
+                  <field name='Account' required='Y'/>
+                </message>
+              </messages>
+            </fix>",
+        )
+        .unwrap();
+        assert!(dd.required_fields("D").contains(&1));
+        let accounts = dd
+            .message("D")
+            .unwrap()
+            .members
+            .iter()
+            .filter(|m| matches!(m, Member::Field { tag: 1, .. }));
+        assert_eq!(1, accounts.count());
+
+        dd.extend(
+            "<fix>
+              <fields><field number='100000' name='VenueSequence' type='SEQNUM'/></fields>
+              <messages>
+                <message name='NewOrderSingle' msgtype='D'>
+                  <field name='VenueSequence' required='N'/>
+                </message>
+              </messages>
+            </fix>",
+        )
+        .unwrap();
+        assert_eq!("VenueSequence", dd.field(100_000).unwrap().name);
+        assert!(dd.message_fields("D").contains(&100_000));
+
+        // new messages must say what they are
+        assert!(dd
+            .extend("<fix><messages><message name='X' msgtype='U9'/></messages></fix>")
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_broken_dictionaries() {
+        let unknown_field = "<fix major='4' minor='2'>
+              <messages>
+                <message name='Heartbeat' msgtype='0' msgcat='admin'>
+                  <field name='TestReqID' required='N'/>
+                </message>
+              </messages>
+              <fields/>
+            </fix>";
+        assert!(unknown_field.parse::<DataDictionary>().is_err());
+
+        let unknown_component = "<fix major='4' minor='4'>
+              <messages>
+                <message name='Heartbeat' msgtype='0' msgcat='admin'>
+                  <component name='Nope' required='N'/>
+                </message>
+              </messages>
+              <fields/>
+            </fix>";
+        assert!(unknown_component.parse::<DataDictionary>().is_err());
+
+        assert!("<fix major='4'".parse::<DataDictionary>().is_err());
+    }
+}
@@ -0,0 +1,612 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::dictionary::DataDictionary;
+use crate::model::field::ParseError;
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+use crate::model::view::MessageView;
+use crate::model::BeginString;
+
+/// Whether `tag` is in a user-defined range: 5000-9999, or 20000 and up.
+/// 10000-19999 is reserved for internal use and isn't.
+#[must_use]
+pub const fn is_user_defined(tag: u32) -> bool {
+    matches!(tag, 5000..=9999 | 20_000..)
+}
+
+/// How strictly messages are checked against the dictionary
+#[derive(Debug, Clone)]
+pub struct ValidationSettings {
+    /// Accept tags the dictionary doesn't define, or doesn't define for the
+    /// message being validated
+    pub allow_unknown_fields: bool,
+    /// Accept tags from the user-defined range which the dictionary doesn't
+    /// define
+    pub allow_user_defined_fields: bool,
+    /// Check that header, body and trailer fields appear in their own section
+    pub check_field_sections: bool,
+    /// Check the order of the fields of a received message, see
+    /// [`Validator::validate_received`]
+    pub check_field_order: bool,
+}
+
+impl Default for ValidationSettings {
+    fn default() -> Self {
+        Self {
+            allow_unknown_fields: false,
+            allow_user_defined_fields: true,
+            check_field_sections: true,
+            check_field_order: true,
+        }
+    }
+}
+
+/// Checks messages against a `DataDictionary`: required fields, tags
+/// belonging to the message, legal enum values and field placement.
+pub struct Validator<'a> {
+    dictionary: &'a DataDictionary,
+    settings: ValidationSettings,
+}
+
+impl<'a> Validator<'a> {
+    #[must_use]
+    pub const fn new(dictionary: &'a DataDictionary, settings: ValidationSettings) -> Self {
+        Self {
+            dictionary,
+            settings,
+        }
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with every problem found if the message is invalid
+    pub fn validate(&self, message: &Message) -> Result<(), Vec<ValidationError>> {
+        let msg_type = match message.get_field_safe(35).map(Field::as_str_safe) {
+            Ok(Ok(msg_type)) => msg_type.to_string(),
+            _ => return Err(vec![ValidationError::RequiredTagMissing { tag: 35 }]),
+        };
+        if self.dictionary.message(&msg_type).is_none() {
+            return Err(vec![ValidationError::InvalidMsgType { msg_type }]);
+        }
+
+        let mut errors = vec![];
+        self.check_required(message, &msg_type, &mut errors);
+        self.check_fields(message, &msg_type, &mut errors);
+        if self.settings.check_field_sections {
+            self.check_sections(message, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate a message as received. Beyond what [`Validator::validate`]
+    /// checks, this sees the fields in their wire order: BeginString (8),
+    /// BodyLength (9) and MsgType (35) first, then the header, the body and
+    /// the trailer, with CheckSum (10) last. Returns the decoded message.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with every problem found if the message is invalid,
+    /// or with the first field that can't be decoded
+    pub fn validate_received(
+        &self,
+        view: &MessageView<'_>,
+    ) -> Result<Message, Vec<ValidationError>> {
+        let message = view
+            .to_message()
+            .map_err(|e| vec![ValidationError::IncorrectDataFormat(e)])?;
+        let mut errors = vec![];
+        if self.settings.check_field_order {
+            if let Some(error) = self.check_order(view) {
+                errors.push(error);
+            }
+        }
+        if let Err(mut invalid) = self.validate(&message) {
+            errors.append(&mut invalid);
+        }
+        if errors.is_empty() {
+            Ok(message)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// First field out of order; once one is, those after it would be too
+    fn check_order(&self, view: &MessageView<'_>) -> Option<ValidationError> {
+        let header = self.dictionary.header_fields();
+        let trailer = self.dictionary.trailer_fields();
+        let tags: Vec<u32> = view
+            .iter()
+            .filter_map(Result::ok)
+            .map(|f| f.tag())
+            .collect();
+
+        for (position, expected) in [8, 9, 35].into_iter().enumerate() {
+            if tags.get(position) != Some(&expected) {
+                return Some(ValidationError::TagOutOfOrder { tag: expected });
+            }
+        }
+        // 0 header, 1 body, 2 trailer
+        let mut section = 0;
+        for (position, tag) in tags.iter().enumerate() {
+            if *tag == 10 && position + 1 != tags.len() {
+                return Some(ValidationError::TagOutOfOrder { tag: 10 });
+            }
+            let tag_section = if header.contains(tag) {
+                0
+            } else if trailer.contains(tag) {
+                2
+            } else {
+                1
+            };
+            if tag_section < section {
+                return Some(ValidationError::TagOutOfOrder { tag: *tag });
+            }
+            section = tag_section;
+        }
+        None
+    }
+
+    fn check_required(&self, message: &Message, msg_type: &str, errors: &mut Vec<ValidationError>) {
+        let dd = self.dictionary;
+        dd.required_header_fields()
+            .into_iter()
+            .chain(dd.required_fields(msg_type))
+            .chain(dd.required_trailer_fields())
+            .filter(|tag| message.get_field_safe(*tag).is_err())
+            .for_each(|tag| errors.push(ValidationError::RequiredTagMissing { tag }));
+    }
+
+    fn check_fields(&self, message: &Message, msg_type: &str, errors: &mut Vec<ValidationError>) {
+        let dd = self.dictionary;
+        let message_fields = dd.message_fields(msg_type);
+
+        let all_fields = message
+            .header()
+            .iter()
+            .chain(message.body().iter())
+            .chain(message.trailer().iter());
+        for field in all_fields {
+            let tag = field.tag();
+            let Some(def) = dd.field(tag) else {
+                let user_defined = is_user_defined(tag);
+                if !(self.settings.allow_unknown_fields
+                    || user_defined && self.settings.allow_user_defined_fields)
+                {
+                    errors.push(ValidationError::InvalidTagNumber { tag });
+                }
+                continue;
+            };
+
+            let in_message = message_fields.contains(&tag)
+                || dd.is_header_field(tag)
+                || dd.is_trailer_field(tag);
+            if !in_message && !self.settings.allow_unknown_fields {
+                errors.push(ValidationError::TagNotDefinedForMessage { tag });
+            }
+
+            let value = field.value_string();
+            let legal = if def.field_type.starts_with("MULTIPLE") {
+                value.split(' ').all(|v| dd.is_valid_value(tag, v))
+            } else {
+                dd.is_valid_value(tag, &value)
+            };
+            if !legal {
+                errors.push(ValidationError::ValueIsIncorrect { tag, value });
+            }
+        }
+    }
+
+    fn check_sections(&self, message: &Message, errors: &mut Vec<ValidationError>) {
+        let dd = self.dictionary;
+        let misplaced_header = message
+            .body()
+            .iter()
+            .chain(message.trailer().iter())
+            .filter(|f| dd.is_header_field(f.tag()));
+        let misplaced_trailer = message
+            .header()
+            .iter()
+            .chain(message.body().iter())
+            .filter(|f| dd.is_trailer_field(f.tag()));
+        let misplaced_body = message
+            .header()
+            .iter()
+            .filter(|f| !dd.is_header_field(f.tag()) && dd.field(f.tag()).is_some());
+
+        misplaced_header
+            .chain(misplaced_trailer)
+            .chain(misplaced_body)
+            .for_each(|f| errors.push(ValidationError::TagInWrongSection { tag: f.tag() }));
+    }
+}
+
+/// Problem found validating a message, named after the `SessionRejectReason`
+/// (373) it's reported with.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ValidationError {
+    InvalidTagNumber {
+        tag: u32,
+    },
+    RequiredTagMissing {
+        tag: u32,
+    },
+    TagNotDefinedForMessage {
+        tag: u32,
+    },
+    ValueIsIncorrect {
+        tag: u32,
+        value: String,
+    },
+    InvalidMsgType {
+        msg_type: String,
+    },
+    /// A header, body or trailer field sits in another section, reported as
+    /// TagSpecifiedOutOfRequiredOrder
+    TagInWrongSection {
+        tag: u32,
+    },
+    /// A received field comes before or after where it must, reported as
+    /// TagSpecifiedOutOfRequiredOrder
+    TagOutOfOrder {
+        tag: u32,
+    },
+    /// A received field can't be decoded
+    IncorrectDataFormat(ParseError),
+}
+
+impl ValidationError {
+    /// `SessionRejectReason` (373) for the error
+    #[must_use]
+    pub const fn session_reject_reason(&self) -> i32 {
+        match self {
+            ValidationError::InvalidTagNumber { .. } => 0,
+            ValidationError::RequiredTagMissing { .. } => 1,
+            ValidationError::TagNotDefinedForMessage { .. } => 2,
+            ValidationError::ValueIsIncorrect { .. } => 5,
+            ValidationError::InvalidMsgType { .. } => 11,
+            ValidationError::TagInWrongSection { .. } | ValidationError::TagOutOfOrder { .. } => 14,
+            // a field that isn't `tag=value` has no tag to be wrong about
+            ValidationError::IncorrectDataFormat(e) => {
+                if e.tag.is_some() {
+                    6
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// `RefTagID` (371) for the error
+    #[must_use]
+    pub const fn ref_tag_id(&self) -> Option<u32> {
+        match self {
+            ValidationError::InvalidTagNumber { tag }
+            | ValidationError::RequiredTagMissing { tag }
+            | ValidationError::TagNotDefinedForMessage { tag }
+            | ValidationError::ValueIsIncorrect { tag, .. }
+            | ValidationError::TagInWrongSection { tag }
+            | ValidationError::TagOutOfOrder { tag } => Some(*tag),
+            ValidationError::IncorrectDataFormat(e) => e.tag,
+            ValidationError::InvalidMsgType { .. } => None,
+        }
+    }
+
+    /// Session Reject (35=3) of `message` for this error. FIX.4.0 and FIX.4.1
+    /// have no `RefTagID`, `RefMsgType` or `SessionRejectReason`, so only the
+    /// text is given.
+    #[must_use]
+    pub fn reject(&self, message: &Message) -> Message {
+        let mut reject = Message::of_type("3");
+        if let Ok(Ok(seq_num)) = message.get_field_safe(34).map(Field::as_i32_safe) {
+            reject.set_field(Field::Int(45, seq_num));
+        }
+
+        let begin_string = message
+            .get_field_safe(8)
+            .ok()
+            .and_then(|f| f.as_str_safe().ok())
+            .and_then(|s| BeginString::try_from(s.to_string()).ok());
+        if !matches!(begin_string, Some(BeginString::Fix40 | BeginString::Fix41)) {
+            if let Some(tag) = self.ref_tag_id().and_then(|tag| i32::try_from(tag).ok()) {
+                reject.set_field(Field::Int(371, tag));
+            }
+            if let Ok(Ok(msg_type)) = message.get_field_safe(35).map(Field::as_str_safe) {
+                reject.set_field(Field::String(372, msg_type.to_string()));
+            }
+            reject.set_field(Field::Int(373, self.session_reject_reason()));
+        }
+        reject.set_field(Field::String(58, self.to_string()));
+        reject
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InvalidTagNumber { tag } => write!(f, "Invalid tag number ({})", tag),
+            ValidationError::RequiredTagMissing { tag } => {
+                write!(f, "Required tag missing ({})", tag)
+            }
+            ValidationError::TagNotDefinedForMessage { tag } => {
+                write!(f, "Tag not defined for this message type ({})", tag)
+            }
+            ValidationError::ValueIsIncorrect { tag, value } => {
+                write!(
+                    f,
+                    "Value is incorrect (out of range) for this tag ({}={})",
+                    tag, value
+                )
+            }
+            ValidationError::InvalidMsgType { msg_type } => {
+                write!(f, "Invalid MsgType ({})", msg_type)
+            }
+            ValidationError::TagInWrongSection { tag } | ValidationError::TagOutOfOrder { tag } => {
+                write!(f, "Tag specified out of required order ({})", tag)
+            }
+            ValidationError::IncorrectDataFormat(e) => {
+                write!(f, "Incorrect data format for value: {}", e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use crate::dictionary::validator::{ValidationError, ValidationSettings, Validator};
+    use crate::dictionary::DataDictionary;
+    use crate::model::field::FieldSet;
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+    use crate::model::view::MessageView;
+
+    fn fix42() -> DataDictionary {
+        DataDictionary::load(concat!(env!("CARGO_MANIFEST_DIR"), "/spec/FIX42.xml")).unwrap()
+    }
+
+    fn new_order_single() -> Message {
+        let mut msg = Message::of_type("D");
+        for field in [
+            Field::String(8, "FIX.4.2".to_string()),
+            Field::Int(9, 100),
+            Field::String(49, "Rocks".to_string()),
+            Field::String(56, "Exchange".to_string()),
+            Field::Int(34, 12),
+            Field::String(52, "20220101-12:00:00".to_string()),
+            Field::String(11, "order-1".to_string()),
+            Field::Char(21, '1'),
+            Field::String(55, "VOD.L".to_string()),
+            Field::Char(54, '1'),
+            Field::String(60, "20220101-12:00:00".to_string()),
+            Field::Int(38, 100),
+            Field::Char(40, '2'),
+            Field::Decimal(44, Decimal::new(1005, 1)),
+            Field::String(10, "123".to_string()),
+        ] {
+            msg.set_field(field);
+        }
+        msg
+    }
+
+    #[test]
+    fn valid_message() {
+        let dd = fix42();
+        let validator = Validator::new(&dd, ValidationSettings::default());
+        assert_eq!(Ok(()), validator.validate(&new_order_single()));
+    }
+
+    #[test]
+    fn missing_fields_and_bad_values() {
+        let dd = fix42();
+        let validator = Validator::new(&dd, ValidationSettings::default());
+
+        let mut fields = FieldSet::default();
+        new_order_single()
+            .header()
+            .iter()
+            .chain(new_order_single().body().iter())
+            .chain(new_order_single().trailer().iter())
+            .filter(|f| f.tag() != 55)
+            .for_each(|f| fields.set_field(f.clone()));
+        let mut msg: Message = fields.into();
+        msg.set_field(Field::Char(54, 'Z'));
+        msg.set_field(Field::String(150, "0".to_string())); // ExecType
+
+        let errors = validator.validate(&msg).unwrap_err();
+        assert!(errors.contains(&ValidationError::RequiredTagMissing { tag: 55 }));
+        assert!(errors.contains(&ValidationError::ValueIsIncorrect {
+            tag: 54,
+            value: "Z".to_string()
+        }));
+        assert!(errors.contains(&ValidationError::TagNotDefinedForMessage { tag: 150 }));
+        assert_eq!(3, errors.len());
+    }
+
+    #[test]
+    fn unknown_and_user_defined_fields() {
+        let dd = fix42();
+
+        let mut msg = new_order_single();
+        msg.set_field(Field::String(6000, "venue".to_string()));
+        assert!(Validator::new(&dd, ValidationSettings::default())
+            .validate(&msg)
+            .is_ok());
+
+        let strict = ValidationSettings {
+            allow_user_defined_fields: false,
+            ..ValidationSettings::default()
+        };
+        assert_eq!(
+            Err(vec![ValidationError::InvalidTagNumber { tag: 6000 }]),
+            Validator::new(&dd, strict).validate(&msg)
+        );
+
+        let mut msg = new_order_single();
+        msg.set_field(Field::String(12000, "internal".to_string()));
+        assert_eq!(
+            Err(vec![ValidationError::InvalidTagNumber { tag: 12000 }]),
+            Validator::new(&dd, ValidationSettings::default()).validate(&msg)
+        );
+
+        let mut msg = new_order_single();
+        msg.set_field(Field::String(4999, "?".to_string()));
+        assert!(Validator::new(&dd, ValidationSettings::default())
+            .validate(&msg)
+            .is_err());
+        let lenient = ValidationSettings {
+            allow_unknown_fields: true,
+            ..ValidationSettings::default()
+        };
+        assert!(Validator::new(&dd, lenient).validate(&msg).is_ok());
+    }
+
+    #[test]
+    fn venue_fields() {
+        let mut dd =
+            DataDictionary::load(concat!(env!("CARGO_MANIFEST_DIR"), "/spec/FIX44.xml")).unwrap();
+        dd.load_extension(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/spec/extensions/examples/venue.xml"
+        ))
+        .unwrap();
+        let strict = ValidationSettings {
+            allow_user_defined_fields: false,
+            ..ValidationSettings::default()
+        };
+        let validator = Validator::new(&dd, strict);
+
+        let mut msg = Message::of_type("U1");
+        for field in [
+            Field::String(8, "FIX.4.4".to_string()),
+            Field::Int(9, 100),
+            Field::String(49, "Venue".to_string()),
+            Field::String(56, "Rocks".to_string()),
+            Field::Int(34, 2),
+            Field::String(52, "20220101-12:00:00".to_string()),
+            Field::String(5001, "BOOK SHELF".to_string()),
+            Field::Int(20001, 1),
+            Field::String(10, "123".to_string()),
+        ] {
+            msg.set_field(field);
+        }
+        assert_eq!(Ok(()), validator.validate(&msg));
+
+        msg.set_field(Field::Int(20001, 7));
+        assert_eq!(
+            Err(vec![ValidationError::ValueIsIncorrect {
+                tag: 20001,
+                value: "7".to_string()
+            }]),
+            validator.validate(&msg)
+        );
+    }
+
+    #[test]
+    fn field_sections() {
+        let dd = fix42();
+
+        // LastMsgSeqNumProcessed (369) belongs in the header
+        let mut msg = new_order_single();
+        msg.set_field(Field::Int(369, 9));
+        assert!(msg.body().get_field(369).is_ok());
+
+        let errors = Validator::new(&dd, ValidationSettings::default())
+            .validate(&msg)
+            .unwrap_err();
+        assert_eq!(
+            vec![ValidationError::TagInWrongSection { tag: 369 }],
+            errors
+        );
+
+        let relaxed = ValidationSettings {
+            check_field_sections: false,
+            ..ValidationSettings::default()
+        };
+        assert!(Validator::new(&dd, relaxed).validate(&msg).is_ok());
+    }
+
+    #[test]
+    fn field_order_on_the_wire() {
+        let dd = fix42();
+        let validator = Validator::new(&dd, ValidationSettings::default());
+        let received = |fields: &str| fields.replace('|', "\x01");
+        let in_order = "8=FIX.4.2|9=100|35=D|49=Rocks|56=Exchange|34=12|52=20220101-12:00:00|\
+                        11=order-1|21=1|55=VOD.L|54=1|60=20220101-12:00:00|38=100|40=2|44=100.5|\
+                        10=123|";
+        let check = |fields: &str| {
+            let bytes = received(fields);
+            validator
+                .validate_received(&MessageView::new(bytes.as_bytes()).unwrap())
+                .map(|message| message.to_string())
+        };
+        assert_eq!(Ok(new_order_single().to_string()), check(in_order));
+
+        // SenderCompID (49) after the body has started
+        let header_late = in_order
+            .replace("49=Rocks|", "")
+            .replace("11=order-1|", "11=order-1|49=Rocks|");
+        assert_eq!(
+            Err(vec![ValidationError::TagOutOfOrder { tag: 49 }]),
+            check(&header_late)
+        );
+        // MsgType (35) must be third
+        let msg_type_late = in_order
+            .replace("35=D|", "")
+            .replace("49=Rocks|", "49=Rocks|35=D|");
+        assert_eq!(
+            Err(vec![ValidationError::TagOutOfOrder { tag: 35 }]),
+            check(&msg_type_late)
+        );
+        // CheckSum (10) must be last
+        let checksum_early = in_order.replace("44=100.5|10=123|", "10=123|44=100.5|");
+        assert_eq!(
+            Err(vec![ValidationError::TagOutOfOrder { tag: 10 }]),
+            check(&checksum_early)
+        );
+
+        let reject = ValidationError::TagOutOfOrder { tag: 49 }.reject(&new_order_single());
+        assert_eq!(14, reject.get_field(373).as_i32());
+        assert_eq!(49, reject.get_field(371).as_i32());
+
+        let relaxed = ValidationSettings {
+            check_field_order: false,
+            ..ValidationSettings::default()
+        };
+        let bytes = received(&header_late);
+        assert!(Validator::new(&dd, relaxed)
+            .validate_received(&MessageView::new(bytes.as_bytes()).unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_feed_session_reject() {
+        let dd = fix42();
+        let msg = Message::of_type("ZZ");
+        let errors = Validator::new(&dd, ValidationSettings::default())
+            .validate(&msg)
+            .unwrap_err();
+        assert_eq!(
+            vec![ValidationError::InvalidMsgType {
+                msg_type: "ZZ".to_string()
+            }],
+            errors
+        );
+
+        let msg = new_order_single();
+        let reject = ValidationError::RequiredTagMissing { tag: 55 }.reject(&msg);
+        assert_eq!("3", reject.get_field(35).as_str());
+        assert_eq!(12, reject.get_field(45).as_i32());
+        assert_eq!(55, reject.get_field(371).as_i32());
+        assert_eq!("D", reject.get_field(372).as_str());
+        assert_eq!(1, reject.get_field(373).as_i32());
+        assert_eq!("Required tag missing (55)", reject.get_field(58).as_str());
+    }
+}
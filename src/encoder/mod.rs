@@ -0,0 +1,218 @@
+use std::fmt::Write;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::cracker;
+use crate::cracker::{EncodingType, SOFH_LENGTH};
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+
+const SOH: u8 = b'\x01';
+
+/// Append the wire format of `message` to `buf`, with `BodyLength` (9) and
+/// `CheckSum` (10) filled in.
+///
+/// Fields are written straight into `buf`, so encoding into a buffer that is
+/// cleared and reused between messages doesn't allocate once it has grown to
+/// fit. `BeginString` (8) and `MsgType` (35) lead, followed by the rest of the
+/// header, the body and the trailer, which puts `SignatureLength` (93) right
+/// before the `Signature` (89) it measures.
+pub fn encode(message: &Message, buf: &mut BytesMut) {
+    let start = buf.len();
+    let header = message.header();
+
+    if let Ok(begin_string) = header.get_field(8) {
+        put_field(buf, begin_string);
+    }
+    // one digit is reserved for the length, the body is moved along once
+    // its actual length is known
+    buf.put_slice(b"9=0\x01");
+    let length_at = buf.len() - 2;
+    let body_start = buf.len();
+
+    if let Ok(msg_type) = header.get_field(35) {
+        put_field(buf, msg_type);
+    }
+    let trailer = message.trailer();
+    header
+        .iter()
+        .filter(|f| !matches!(f.tag(), 8 | 9 | 35))
+        .chain(message.body().iter())
+        .chain(trailer.get_field(93))
+        .chain(trailer.get_field(89))
+        .chain(trailer.iter().filter(|f| !matches!(f.tag(), 10 | 89 | 93)))
+        .for_each(|f| put_field(buf, f));
+
+    let mut digits = [0_u8; 20];
+    let length = format_u64((buf.len() - body_start) as u64, &mut digits);
+    let extra = length.len() - 1;
+    if extra > 0 {
+        let end = buf.len();
+        buf.resize(end + extra, 0);
+        buf.copy_within(length_at + 1..end, length_at + 1 + extra);
+    }
+    buf[length_at..length_at + length.len()].copy_from_slice(length);
+
+    let checksum = cracker::checksum(&buf[start..]);
+    buf.put_slice(b"10=");
+    buf.put_slice(&[
+        b'0' + checksum / 100,
+        b'0' + checksum / 10 % 10,
+        b'0' + checksum % 10,
+        SOH,
+    ]);
+}
+
+/// Append `message` to `buf` behind a Simple Open Framing Header, as `encode`
+/// would without one
+pub fn encode_sofh(message: &Message, buf: &mut BytesMut) {
+    let start = buf.len();
+    buf.put_bytes(0, SOFH_LENGTH);
+    encode(message, buf);
+    let length = sofh_length(buf.len() - start);
+    buf[start..start + 4].copy_from_slice(&length.to_be_bytes());
+    buf[start + 4..start + SOFH_LENGTH]
+        .copy_from_slice(&u16::from(EncodingType::TagValue).to_be_bytes());
+}
+
+/// Append an already encoded message, e.g. one in SBE, to `buf` behind a
+/// Simple Open Framing Header
+pub fn put_sofh(buf: &mut BytesMut, encoding_type: EncodingType, payload: &[u8]) {
+    buf.put_u32(sofh_length(SOFH_LENGTH + payload.len()));
+    buf.put_u16(u16::from(encoding_type));
+    buf.put_slice(payload);
+}
+
+/// SOFH can't frame messages of 4GiB or more; they'd never be accepted anyway
+fn sofh_length(length: usize) -> u32 {
+    u32::try_from(length).unwrap_or(u32::MAX)
+}
+
+/// Append a single `tag=value<SOH>` field to `buf`
+pub fn put_field(buf: &mut BytesMut, field: &Field) {
+    let mut digits = [0_u8; 20];
+    buf.put_slice(format_u64(u64::from(field.tag()), &mut digits));
+    buf.put_u8(b'=');
+    match field {
+        Field::String(_, v) => buf.put_slice(v.as_bytes()),
+        Field::Int(_, v) => {
+            if *v < 0 {
+                buf.put_u8(b'-');
+            }
+            buf.put_slice(format_u64(u64::from(v.unsigned_abs()), &mut digits));
+        }
+        Field::Char(_, v) => buf.put_slice(v.encode_utf8(&mut [0; 4]).as_bytes()),
+        // BytesMut's fmt::Write appends in place, it can't fail
+        Field::Decimal(_, v) => {
+            let _ = write!(buf, "{}", v);
+        }
+    }
+    buf.put_u8(SOH);
+}
+
+/// Decimal digits of `n`, written into the end of `digits`
+fn format_u64(mut n: u64, digits: &mut [u8; 20]) -> &[u8] {
+    let mut at = digits.len();
+    loop {
+        at -= 1;
+        digits[at] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            return &digits[at..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use rust_decimal::Decimal;
+
+    use crate::cracker;
+    use crate::encoder::encode;
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+
+    fn heartbeat(text: &str) -> Message {
+        let mut msg = Message::of_type("0");
+        msg.set_field(Field::String(8, "FIX.4.2".to_string()));
+        msg.set_field(Field::String(58, text.to_string()));
+        msg
+    }
+
+    #[test]
+    fn encodes_body_length_and_checksum() {
+        let mut buf = BytesMut::new();
+        encode(&heartbeat("a|b"), &mut buf);
+        assert_eq!(b"8=FIX.4.2\x019=12\x0135=0\x0158=a|b\x0110=", &buf[..30]);
+
+        let frame = cracker::next_frame(&mut buf).unwrap().unwrap();
+        let cracked = cracker::crack_message(&frame).unwrap();
+        assert_eq!("a|b", cracked.get_field(58).as_str());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn body_length_of_any_width() {
+        let mut buf = BytesMut::new();
+        for len in [0, 1, 5, 6, 95, 96, 995, 996, 10_000] {
+            buf.clear();
+            encode(&heartbeat(&"x".repeat(len)), &mut buf);
+
+            // "35=0<SOH>58=<text><SOH>"
+            let body_length = 5 + 4 + len;
+            let expected = format!("8=FIX.4.2\x019={}\x0135=0\x01", body_length);
+            assert_eq!(expected.as_bytes(), &buf[..expected.len()]);
+
+            let frame = cracker::next_frame(&mut buf).unwrap().unwrap();
+            let cracked = cracker::crack_message(&frame).unwrap();
+            assert_eq!(len, cracked.get_field(58).as_str().len());
+        }
+    }
+
+    #[test]
+    fn formats_values_and_reuses_the_buffer() {
+        let mut order = Message::of_type("D");
+        order.set_field(Field::String(8, "FIX.4.4".to_string()));
+        order.set_field(Field::Int(34, 7));
+        order.set_field(Field::Decimal(44, Decimal::new(-10_250, 3)));
+        order.set_field(Field::Char(54, '1'));
+        order.set_field(Field::Int(38, i32::MIN));
+        order.set_field(Field::Int(1_000_000, 0));
+
+        let mut buf = BytesMut::with_capacity(1024);
+        encode(&order, &mut buf);
+        encode(&heartbeat("second"), &mut buf);
+
+        let first = cracker::next_frame(&mut buf).unwrap().unwrap();
+        let second = cracker::next_frame(&mut buf).unwrap().unwrap();
+        assert!(buf.is_empty());
+
+        let body = "35=D\x0134=7\x0138=-2147483648\x0144=-10.250\x0154=1\x011000000=0\x01";
+        let head = format!("8=FIX.4.4\x019={}\x01{}", body.len(), body);
+        assert_eq!(head.as_bytes(), &first[..first.len() - 7]);
+        assert_eq!(
+            "second",
+            cracker::crack_message(&second)
+                .unwrap()
+                .get_field(58)
+                .as_str()
+        );
+    }
+
+    #[test]
+    fn signature_length_goes_before_signature() {
+        let mut signed = heartbeat("hi");
+        signed.set_field(Field::String(89, "sig".to_string()));
+        signed.set_field(Field::Int(93, 3));
+
+        let mut buf = BytesMut::new();
+        encode(&signed, &mut buf);
+        let tail = b"58=hi\x0193=3\x0189=sig\x0110=";
+        assert_eq!(tail, &buf[buf.len() - 4 - tail.len()..buf.len() - 4]);
+
+        let frame = cracker::next_frame(&mut buf).unwrap().unwrap();
+        let cracked = cracker::crack_message(&frame).unwrap();
+        assert_eq!("sig", cracked.get_field(89).as_str());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::session::SessionID;
 
-pub trait Store {
+pub trait Store: Send {
     fn init(&mut self, session_id: &SessionID);
 
     // todo u32 is large enough for more than 1500 messages per second for a month.
@@ -27,6 +27,6 @@ pub struct FileStore {
 
 impl FileStore {
     fn to_sender_seqnum_file(&self, session_id: &SessionID) -> String {
-        format!("{}.seqnum", session_id)
+        format!("{}.seqnum", session_id.file_prefix())
     }
 }
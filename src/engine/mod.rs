@@ -1,14 +1,20 @@
 pub mod filestore;
+pub mod runner;
 
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
 
+use tracing::{debug, warn};
+
 use crate::engine::FailedToCreateSession::SessionNotFound;
 use crate::model::generated::fields::Field;
 use crate::model::message::Message;
+use crate::model::BeginString;
 
-use crate::session::settings::Settings;
+use crate::session::channel::{SendError, SessionSender};
+use crate::session::credentials::{CredentialCheck, LogonCredentials, SecretError};
+use crate::session::settings::{InvalidSetting, SessionType, Settings};
 use crate::session::{SessionID, State};
 
 // #[derive(Default)]
@@ -17,6 +23,9 @@ pub struct Engine {
     session_settings: HashMap<SessionID, Settings>,
     session_state: HashMap<SessionID, State>,
     filestore: Box<dyn filestore::Store>,
+    credential_check: Option<Box<dyn CredentialCheck>>,
+    /// Senders of the sessions' transports while the engine runs
+    senders: HashMap<SessionID, SessionSender>,
 }
 
 impl Engine {
@@ -27,9 +36,16 @@ impl Engine {
             session_settings: HashMap::default(),
             session_state: HashMap::default(),
             filestore: store,
+            credential_check: None,
+            senders: HashMap::default(),
         }
     }
 
+    /// Check the credentials of initiators logging on to acceptor sessions
+    pub fn set_credential_check(&mut self, check: impl CredentialCheck + 'static) {
+        self.credential_check = Some(Box::new(check));
+    }
+
     #[must_use]
     pub fn sessions(&self) -> &[SessionID] {
         self.sessions.as_slice()
@@ -42,56 +58,540 @@ impl Engine {
         &mut self,
         settings: Settings,
     ) -> Result<&SessionID, FailedToCreateSession> {
-        println!("Creating session using {:?}", settings);
+        debug!(?settings, "creating session");
 
-        // todo validate settings
-        // Err(InvalidSettings...)
+        if let Err(e) = settings.validate() {
+            warn!(error = %e, "invalid settings");
+            return Err(FailedToCreateSession::InvalidSettings(e));
+        }
 
         let session_id = SessionID::from_settings(&settings);
         if self.sessions.contains(&session_id) {
-            eprintln!("Not creating {}", session_id);
+            warn!(%session_id, "session already exists");
             return Err(FailedToCreateSession::DuplicateSessionID);
         }
 
         self.filestore.init(&session_id);
 
         self.sessions.push(session_id.clone());
+        self.session_settings.insert(session_id.clone(), settings);
         self.session_state.insert(session_id, State::Created);
         self.sessions.last().ok_or(SessionNotFound)
     }
 
+    /// Forget a session, returning its settings
+    pub fn remove_session(&mut self, session_id: &SessionID) -> Option<Settings> {
+        self.sessions.retain(|id| id != session_id);
+        self.session_state.remove(session_id);
+        self.senders.remove(session_id);
+        self.session_settings.remove(session_id)
+    }
+
     #[must_use]
     pub fn session_status(&self, session_id: &SessionID) -> Option<&State> {
         self.session_state.get(session_id)
     }
 
     pub fn logon_session(&mut self, session: &SessionID) {
+        let mut logon_msg = self.logon_message(session);
+        if let Err(e) = self.add_credentials(session, &mut logon_msg) {
+            warn!(%session, error = %e, "could not look up logon credentials");
+            return;
+        }
+        if self.send(session, logon_msg).is_ok() {
+            self.session_state.insert(session.clone(), State::LoginSent); // todo Login Sent
+        }
+    }
+
+    fn logon_message(&self, session: &SessionID) -> Message {
         let mut logon_msg = Message::default(); // todo change this when message generaation is done
         logon_msg.set_field(Field::String(8, session.begin_string.to_string()));
         logon_msg.set_field(Field::String(35, "A".to_string()));
+        let Some(settings) = self.session_settings.get(session) else {
+            return logon_msg;
+        };
+        // EncryptMethod (98) none, and HeartBtInt (108) in seconds
+        logon_msg.set_field(Field::Int(98, 0));
+        logon_msg.set_field(Field::Int(
+            108,
+            i32::try_from(settings.connection_settings.heartbeat_interval().as_secs())
+                .unwrap_or(i32::MAX),
+        ));
+        // FIXT.1.1 sessions agree the application version on Logon
+        if let Some(appl_ver_id) = settings
+            .default_appl_ver_id
+            .and_then(BeginString::appl_ver_id)
+        {
+            logon_msg.set_field(Field::String(1137, appl_ver_id.to_string()));
+        }
+        logon_msg
+    }
 
-        if self.send(session, logon_msg).is_ok() {
-            self.session_state.insert(session.clone(), State::LoginSent); // todo Login Sent
+    /// Username (553), Password (554) and NewPassword (925) of an initiator
+    fn add_credentials(
+        &self,
+        session: &SessionID,
+        logon_msg: &mut Message,
+    ) -> Result<(), SecretError> {
+        let Some(settings) = self.session_settings.get(session) else {
+            return Ok(());
+        };
+        let (SessionType::Initiator, Some(credentials)) =
+            (settings.session_type, &settings.credentials)
+        else {
+            return Ok(());
+        };
+        logon_msg.set_field(Field::String(553, credentials.username.clone()));
+        if let Some(password) = &credentials.password {
+            logon_msg.set_field(Field::String(554, password.secret()?));
+        }
+        if let Some(new_password) = &credentials.new_password {
+            logon_msg.set_field(Field::String(925, new_password.secret()?));
         }
+        Ok(())
     }
 
-    pub fn logout_session(&self, _session: &SessionID) {}
+    /// Handle a Logon received on `session`. Acceptors check the initiator's
+    /// credentials and answer with a Logon, or a Logout giving the reason
+    /// and a disconnect when the check refuses them.
+    pub fn on_logon(&mut self, session: &SessionID, logon: &Message) {
+        match self.logon_reply(session, logon) {
+            Ok(reply) => {
+                if let Some(reply) = reply {
+                    let _ = self.send(session, reply);
+                }
+                self.session_state.insert(session.clone(), State::LoggedIn);
+            }
+            Err(logout) => {
+                let _ = self.send(session, logout);
+                self.disconnect(session);
+                self.session_state.insert(session.clone(), State::LoggedOut);
+            }
+        }
+    }
 
-    pub fn send(&self, session: &SessionID, msg: Message) -> Result<(), ()> {
-        println!("{} >> {}", session, msg);
-        Ok(())
+    fn logon_reply(
+        &self,
+        session: &SessionID,
+        logon: &Message,
+    ) -> Result<Option<Message>, Message> {
+        let acceptor = matches!(
+            self.session_settings
+                .get(session)
+                .map(|settings| settings.session_type),
+            Some(SessionType::Acceptor)
+        );
+        if !acceptor {
+            return Ok(None);
+        }
+        if let Some(check) = &self.credential_check {
+            let text = |tag| {
+                logon
+                    .get_field_safe(tag)
+                    .ok()
+                    .and_then(|field| field.as_str_safe().ok())
+            };
+            let credentials = LogonCredentials {
+                username: text(553),
+                password: text(554),
+                new_password: text(925),
+            };
+            if let Err(reason) = check.check(session, &credentials) {
+                warn!(%session, username = credentials.username, %reason, "logon refused");
+                let mut logout = Message::default();
+                logout.set_field(Field::String(8, session.begin_string.to_string()));
+                logout.set_field(Field::String(35, "5".to_string()));
+                logout.set_field(Field::String(58, reason));
+                return Err(logout);
+            }
+        }
+        Ok(Some(self.logon_message(session)))
+    }
+
+    /// Send a Logout on `session` and wait for the counterparty's
+    pub fn logout_session(&mut self, session: &SessionID) {
+        let mut logout_msg = Message::default();
+        logout_msg.set_field(Field::String(8, session.begin_string.to_string()));
+        logout_msg.set_field(Field::String(35, "5".to_string()));
+        if self.send(session, logout_msg).is_ok() {
+            self.session_state
+                .insert(session.clone(), State::LogoutSent);
+        }
+    }
+
+    /// Handle a Logout received on `session`: answer it if the session was
+    /// logged on, or logging on, then disconnect. A Logout answering ours,
+    /// or one of ours, gets no answer, so the two sides don't keep logging
+    /// each other out.
+    pub fn on_logout(&mut self, session: &SessionID, logout: &Message) {
+        if let Ok(text) = logout.get_field_safe(58) {
+            debug!(%session, text = %text.value_string(), "logged out");
+        }
+        if matches!(
+            self.session_state.get(session),
+            Some(State::LoginSent | State::LoggedIn)
+        ) {
+            let mut reply = Message::default();
+            reply.set_field(Field::String(8, session.begin_string.to_string()));
+            reply.set_field(Field::String(35, "5".to_string()));
+            let _ = self.send(session, reply);
+        }
+        self.disconnect(session);
+        self.session_state.insert(session.clone(), State::LoggedOut);
+    }
+
+    /// Close the connection of `session` once what was sent before has gone
+    /// out
+    pub fn disconnect(&self, session: &SessionID) {
+        debug!(%session, "disconnecting");
+        if let Some(sender) = self.senders.get(session) {
+            sender.disconnect();
+        }
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the session isn't running, handing back the message
+    pub fn send(&self, session: &SessionID, msg: Message) -> Result<(), SendError> {
+        debug!(%session, message = %msg.redacted(), "sending");
+        match self.senders.get(session) {
+            Some(sender) => sender.send(msg),
+            None => Err(SendError {
+                session_id: session.clone(),
+                message: msg,
+            }),
+        }
     }
 }
 
 #[derive(Debug)]
 pub enum FailedToCreateSession {
-    InvalidSettings,
+    InvalidSettings(InvalidSetting),
     DuplicateSessionID,
     SessionNotFound,
 }
 
 impl fmt::Display for FailedToCreateSession {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "error.")
+        match self {
+            FailedToCreateSession::InvalidSettings(e) => write!(f, "invalid settings: {}", e),
+            FailedToCreateSession::DuplicateSessionID => {
+                write!(f, "a session with the same SessionID already exists")
+            }
+            FailedToCreateSession::SessionNotFound => write!(f, "session not found"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+
+    use crate::engine::filestore::FileStore;
+    use crate::engine::{Engine, FailedToCreateSession};
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+    use crate::model::BeginString;
+    use crate::session::channel::{Outbound, SessionSender};
+    use crate::session::credentials::{Credentials, LogonCredentials, PlainSecret, SecretError};
+    use crate::session::settings::{
+        InvalidSetting, ScheduleSettings, SessionType, SettingsBuilder, TimeOfDay, Weekday,
+    };
+    use crate::session::{SessionID, State};
+
+    fn engine() -> Engine {
+        Engine::create(Box::new(FileStore {
+            directory: "".to_string(),
+        }))
+    }
+
+    #[test]
+    fn fixt_logon_carries_default_appl_ver_id() {
+        let mut engine = Engine::create(Box::new(FileStore {
+            directory: "".to_string(),
+        }));
+
+        let fixt = SettingsBuilder::new(
+            BeginString::Fixt11,
+            "Rocks".to_string(),
+            "Exchange".to_string(),
+            SessionType::Initiator,
+        )
+        .with_endpoint("localhost:9876")
+        .with_default_appl_ver_id(BeginString::Fix50Sp2)
+        .build();
+        let fixt = engine.create_session(fixt).unwrap().clone();
+        assert_eq!("FIXT.1.1", fixt.begin_string);
+
+        let logon = engine.logon_message(&fixt);
+        assert_eq!("8=FIXT.1.1|35=A|98=0|108=5|1137=9|", logon.to_string());
+
+        let fix44 = SettingsBuilder::new(
+            BeginString::Fix44,
+            "Rocks".to_string(),
+            "Venue".to_string(),
+            SessionType::Initiator,
+        )
+        .with_endpoint("localhost:9876")
+        .build();
+        let fix44 = engine.create_session(fix44).unwrap().clone();
+        assert_eq!(
+            "8=FIX.4.4|35=A|98=0|108=5|",
+            engine.logon_message(&fix44).to_string()
+        );
+    }
+
+    #[test]
+    fn initiator_logon_carries_credentials() {
+        let mut engine = engine();
+        let settings = SettingsBuilder::new(
+            BeginString::Fix44,
+            "Rocks".to_string(),
+            "Exchange".to_string(),
+            SessionType::Initiator,
+        )
+        .with_endpoint("localhost:9876")
+        .with_credentials(
+            Credentials::new("rocks")
+                .with_password(PlainSecret("hunter2".to_string()))
+                .with_new_password(|| Ok("hunter3".to_string())),
+        )
+        .build();
+        assert!(!format!("{:?}", settings).contains("hunter2"));
+        let session = engine.create_session(settings).unwrap().clone();
+
+        let mut logon = engine.logon_message(&session);
+        engine.add_credentials(&session, &mut logon).unwrap();
+        assert_eq!(
+            "8=FIX.4.4|35=A|98=0|108=5|553=rocks|554=hunter2|925=hunter3|",
+            logon.to_string()
+        );
+
+        // nothing goes out without the password
+        let settings = SettingsBuilder::new(
+            BeginString::Fix44,
+            "Rocks".to_string(),
+            "Venue".to_string(),
+            SessionType::Initiator,
+        )
+        .with_endpoint("localhost:9876")
+        .with_credentials(
+            Credentials::new("rocks")
+                .with_password(|| Err(SecretError::Other("vault sealed".to_string()))),
+        )
+        .build();
+        let session = engine.create_session(settings).unwrap().clone();
+        engine.logon_session(&session);
+        assert_eq!(Some(&State::Created), engine.session_status(&session));
+    }
+
+    #[test]
+    fn acceptor_refuses_bad_credentials_with_logout() {
+        let mut engine = engine();
+        engine.set_credential_check(|_: &SessionID, credentials: &LogonCredentials<'_>| {
+            match (credentials.username, credentials.password) {
+                (Some("rocks"), Some("hunter2")) => Ok(()),
+                (Some(_), _) => Err("invalid username or password".to_string()),
+                (None, _) => Err("Username (553) is required".to_string()),
+            }
+        });
+        let settings = SettingsBuilder::new(
+            BeginString::Fix44,
+            "Exchange".to_string(),
+            "Rocks".to_string(),
+            SessionType::Acceptor,
+        )
+        .with_accept_address("0.0.0.0:9876")
+        .build();
+        let session = engine.create_session(settings).unwrap().clone();
+
+        let mut logon = Message::of_type("A");
+        logon.set_field(Field::String(553, "rocks".to_string()));
+        logon.set_field(Field::String(554, "wrong".to_string()));
+        let logout = engine.logon_reply(&session, &logon).unwrap_err();
+        assert_eq!(
+            "8=FIX.4.4|35=5|58=invalid username or password|",
+            logout.to_string()
+        );
+        engine.on_logon(&session, &logon);
+        assert_eq!(Some(&State::LoggedOut), engine.session_status(&session));
+
+        logon.set_field(Field::String(554, "hunter2".to_string()));
+        let reply = engine.logon_reply(&session, &logon).unwrap().unwrap();
+        assert_eq!("8=FIX.4.4|35=A|98=0|108=5|", reply.to_string());
+        engine.on_logon(&session, &logon);
+        assert_eq!(Some(&State::LoggedIn), engine.session_status(&session));
+    }
+
+    #[test]
+    fn logout_is_answered_once() {
+        let mut engine = engine();
+        let settings = SettingsBuilder::new(
+            BeginString::Fix44,
+            "Rocks".to_string(),
+            "Exchange".to_string(),
+            SessionType::Initiator,
+        )
+        .with_endpoint("localhost:9876")
+        .build();
+        let session = engine.create_session(settings).unwrap().clone();
+        let (tx, mut outbound) = mpsc::unbounded_channel();
+        engine
+            .senders
+            .insert(session.clone(), SessionSender::new(session.clone(), tx));
+        let mut sent = || {
+            let mut sent = vec![];
+            while let Ok(item) = outbound.try_recv() {
+                sent.push(match item {
+                    Outbound::Message(message) => message.get_field(35).value_string(),
+                    Outbound::Disconnect => "disconnect".to_string(),
+                });
+            }
+            sent
+        };
+
+        let logout = Message::of_type("5");
+        engine.logon_session(&session);
+        assert_eq!(vec!["A"], sent());
+        engine.on_logout(&session, &logout);
+        assert_eq!(vec!["5", "disconnect"], sent());
+        assert_eq!(Some(&State::LoggedOut), engine.session_status(&session));
+
+        // the counterparty answering our answer is not answered again
+        engine.on_logout(&session, &logout);
+        assert_eq!(vec!["disconnect"], sent());
+
+        engine
+            .session_state
+            .insert(session.clone(), State::LoggedIn);
+        engine.logout_session(&session);
+        assert_eq!(vec!["5"], sent());
+        engine.on_logout(&session, &logout);
+        assert_eq!(vec!["disconnect"], sent());
+    }
+
+    #[test]
+    fn invalid_settings_name_the_setting() {
+        let builder = || {
+            let mut builder = SettingsBuilder::new(
+                BeginString::Fix44,
+                "Rocks".to_string(),
+                "Exchange".to_string(),
+                SessionType::Initiator,
+            );
+            builder.with_endpoint("localhost:9876");
+            builder
+        };
+        let error = |settings| engine().create_session(settings).unwrap_err().to_string();
+
+        assert_eq!(
+            "invalid settings: SenderCompID must not be empty",
+            error(
+                SettingsBuilder::new(
+                    BeginString::Fix44,
+                    " ".to_string(),
+                    "Exchange".to_string(),
+                    SessionType::Initiator,
+                )
+                .build()
+            )
+        );
+        assert_eq!(
+            "invalid settings: HeartBtInt must be greater than zero",
+            error(builder().with_hb_interval(Duration::ZERO).build())
+        );
+        assert_eq!(
+            "invalid settings: HeartBtInt must be a whole number of seconds",
+            error(
+                builder()
+                    .with_hb_interval(Duration::from_millis(500))
+                    .build()
+            )
+        );
+        assert_eq!(
+            "invalid settings: SocketConnectHost is needed for an initiator to connect to",
+            error(
+                SettingsBuilder::new(
+                    BeginString::Fix44,
+                    "Rocks".to_string(),
+                    "Exchange".to_string(),
+                    SessionType::Initiator,
+                )
+                .build()
+            )
+        );
+        assert_eq!(
+            "invalid settings: SocketConnectPort is missing from localhost",
+            error(
+                SettingsBuilder::new(
+                    BeginString::Fix44,
+                    "Rocks".to_string(),
+                    "Exchange".to_string(),
+                    SessionType::Initiator,
+                )
+                .with_endpoint("localhost")
+                .build()
+            )
+        );
+        assert_eq!(
+            "invalid settings: SocketAcceptPort is needed for an acceptor to listen on",
+            error(
+                SettingsBuilder::new(
+                    BeginString::Fix44,
+                    "Exchange".to_string(),
+                    "Rocks".to_string(),
+                    SessionType::Acceptor,
+                )
+                .with_accept_address("0.0.0.0")
+                .build()
+            )
+        );
+        let nine = TimeOfDay {
+            hour: 9,
+            minute: 0,
+            second: 0,
+        };
+        // the same start and end is a full day
+        assert!(engine()
+            .create_session(
+                builder()
+                    .with_schedule(ScheduleSettings {
+                        start_time: Some(nine),
+                        end_time: Some(nine),
+                        ..ScheduleSettings::default()
+                    })
+                    .build()
+            )
+            .is_ok());
+        assert_eq!(
+            "invalid settings: EndDay is needed with StartDay",
+            error(
+                builder()
+                    .with_schedule(ScheduleSettings {
+                        start_time: Some(nine),
+                        end_time: Some(TimeOfDay { hour: 17, ..nine }),
+                        start_day: Some(Weekday::Monday),
+                        ..ScheduleSettings::default()
+                    })
+                    .build()
+            )
+        );
+        let mut engine = engine();
+        assert!(matches!(
+            engine.create_session(
+                builder()
+                    .with_reset_on_logon(true)
+                    .with_refresh_on_logon(true)
+                    .build()
+            ),
+            Err(FailedToCreateSession::InvalidSettings(InvalidSetting {
+                setting: "RefreshOnLogon",
+                ..
+            }))
+        ));
+        assert!(engine.sessions().is_empty());
     }
 }
@@ -0,0 +1,833 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::Formatter;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
+use tracing::{debug, warn};
+
+use crate::engine::{Engine, FailedToCreateSession};
+use crate::model::generated::fields::Field;
+use crate::session::channel::{SessionEvent, SessionSender};
+use crate::session::settings::{SessionType, Settings};
+use crate::session::{SessionID, State};
+use crate::transport::{run_acceptor, run_initiator, ConnectError, Listener, TransportSettings};
+
+impl Engine {
+    /// Run every session: initiators connect to their endpoints and log on,
+    /// acceptors listen on their address for one initiator at a time. What
+    /// the sessions report, and the state changes of the engine, go to
+    /// `events`.
+    ///
+    /// The returned `JoinHandle` finishes, handing back the engine, once the
+    /// engine has been stopped, or every `EngineHandle` dropped, and all the
+    /// sessions' connections have closed.
+    ///
+    /// # Errors
+    ///
+    /// If an acceptor can't listen on its address, in which case no session
+    /// is started and the engine is handed back.
+    pub async fn start(
+        mut self,
+        events: mpsc::Sender<SessionEvent>,
+    ) -> Result<(EngineHandle, JoinHandle<Engine>), StartError> {
+        let (session_events, session_rx) = mpsc::channel(1024);
+        let (done, done_rx) = mpsc::unbounded_channel();
+        let (commands_tx, commands) = mpsc::unbounded_channel();
+
+        let mut listeners = HashMap::new();
+        for (session_id, settings) in &self.session_settings {
+            if matches!(settings.session_type, SessionType::Acceptor) {
+                match listen(session_id, settings).await {
+                    Ok(listener) => listeners.insert(session_id.clone(), listener),
+                    Err(error) => {
+                        return Err(StartError {
+                            engine: self,
+                            error,
+                        })
+                    }
+                };
+            }
+        }
+        self.senders.clear();
+
+        let mut runner = Runner {
+            engine: self,
+            events,
+            session_events,
+            done,
+            tasks: HashMap::new(),
+            next_task: 0,
+            logging_out: HashMap::new(),
+            removing: HashSet::new(),
+            waiting: vec![],
+            stopping: false,
+        };
+        for session_id in runner.engine.sessions.clone() {
+            let listener = listeners.remove(&session_id);
+            runner.spawn(&session_id, listener);
+        }
+        let task = tokio::spawn(runner.run(commands, session_rx, done_rx));
+        Ok((
+            EngineHandle {
+                commands: commands_tx,
+            },
+            task,
+        ))
+    }
+}
+
+/// Controls a started `Engine` from any task
+#[derive(Clone, Debug)]
+pub struct EngineHandle {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl EngineHandle {
+    /// Create a session and start it as `Engine::start` would have
+    ///
+    /// # Errors
+    ///
+    /// If the settings are invalid or duplicate a session's, an acceptor
+    /// can't listen on its address or the engine has stopped.
+    pub async fn add_session(&self, settings: Settings) -> Result<SessionID, EngineError> {
+        let (reply, rx) = oneshot::channel();
+        self.request(Command::Add { settings, reply }, rx).await?
+    }
+
+    /// Log a session out, waiting for the counterparty's Logout for up to
+    /// its `logout_timeout`, then close its connection and forget it
+    ///
+    /// # Errors
+    ///
+    /// If there's no such session or the engine has stopped.
+    pub async fn remove_session(&self, session_id: &SessionID) -> Result<(), EngineError> {
+        let (reply, rx) = oneshot::channel();
+        let command = Command::Remove {
+            session_id: session_id.clone(),
+            reply,
+        };
+        self.request(command, rx).await?
+    }
+
+    /// Stop every session and wait for their connections to close.
+    ///
+    /// When `graceful`, sessions that are logged on first send a Logout and
+    /// wait up to their `logout_timeout` for the counterparty's; otherwise
+    /// connections are closed straight away.
+    pub async fn stop(&self, graceful: bool) {
+        let (reply, rx) = oneshot::channel();
+        let _ = self.request(Command::Stop { graceful, reply }, rx).await;
+    }
+
+    /// Sender for application messages on a running session
+    pub async fn sender(&self, session_id: &SessionID) -> Option<SessionSender> {
+        let (reply, rx) = oneshot::channel();
+        let command = Command::Sender {
+            session_id: session_id.clone(),
+            reply,
+        };
+        self.request(command, rx).await.ok().flatten()
+    }
+
+    async fn request<T>(
+        &self,
+        command: Command,
+        reply: oneshot::Receiver<T>,
+    ) -> Result<T, EngineError> {
+        self.commands
+            .send(command)
+            .map_err(|_| EngineError::Stopped)?;
+        reply.await.map_err(|_| EngineError::Stopped)
+    }
+}
+
+#[derive(Debug)]
+pub enum EngineError {
+    CreateSession(FailedToCreateSession),
+    /// An acceptor session can't listen on its address
+    Listen {
+        session_id: SessionID,
+        error: ConnectError,
+    },
+    SessionNotFound(SessionID),
+    Stopped,
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::CreateSession(e) => write!(f, "{}", e),
+            EngineError::Listen { session_id, error } => {
+                write!(f, "session {} can't listen: {}", session_id, error)
+            }
+            EngineError::SessionNotFound(session_id) => {
+                write!(f, "session {} not found", session_id)
+            }
+            EngineError::Stopped => write!(f, "the engine has stopped"),
+        }
+    }
+}
+
+/// `Engine::start` failed, handing back the engine
+pub struct StartError {
+    pub engine: Engine,
+    pub error: EngineError,
+}
+
+impl fmt::Debug for StartError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StartError")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "could not start the engine: {}", self.error)
+    }
+}
+
+enum Command {
+    Add {
+        settings: Settings,
+        reply: oneshot::Sender<Result<SessionID, EngineError>>,
+    },
+    Remove {
+        session_id: SessionID,
+        reply: oneshot::Sender<Result<(), EngineError>>,
+    },
+    Stop {
+        graceful: bool,
+        reply: oneshot::Sender<Result<(), EngineError>>,
+    },
+    Sender {
+        session_id: SessionID,
+        reply: oneshot::Sender<Option<SessionSender>>,
+    },
+}
+
+async fn listen(session_id: &SessionID, settings: &Settings) -> Result<Listener, EngineError> {
+    let connection = &settings.connection_settings;
+    let addr = connection.accept_address().unwrap_or_default();
+    Listener::bind(addr, connection)
+        .await
+        .map_err(|error| EngineError::Listen {
+            session_id: session_id.clone(),
+            error,
+        })
+}
+
+/// The task driving a started engine: it owns the transports of the
+/// sessions, sees everything they report before the application does and
+/// answers the `EngineHandle`s
+struct Runner {
+    engine: Engine,
+    events: mpsc::Sender<SessionEvent>,
+    /// Cloned into every transport, so the runner sees their events first
+    session_events: mpsc::Sender<SessionEvent>,
+    /// Transports report here, with their task's number, when they end by
+    /// themselves
+    done: mpsc::UnboundedSender<(SessionID, u64)>,
+    tasks: HashMap<SessionID, Task>,
+    /// Number of the next transport task
+    next_task: u64,
+    /// Sessions waiting for the counterparty's Logout, until the deadline
+    logging_out: HashMap<SessionID, Instant>,
+    /// Sessions to forget once closed
+    removing: HashSet<SessionID>,
+    /// Replies due once all the sessions they wait for have closed
+    waiting: Vec<(HashSet<SessionID>, oneshot::Sender<Result<(), EngineError>>)>,
+    stopping: bool,
+}
+
+/// The transport of a session
+struct Task {
+    /// Tells this task from those of the session before it was re-added
+    number: u64,
+    handle: JoinHandle<()>,
+}
+
+impl Runner {
+    async fn run(
+        mut self,
+        mut commands: mpsc::UnboundedReceiver<Command>,
+        mut session_rx: mpsc::Receiver<SessionEvent>,
+        mut done_rx: mpsc::UnboundedReceiver<(SessionID, u64)>,
+    ) -> Engine {
+        let mut handles_dropped = false;
+        while !(self.tasks.is_empty() && (self.stopping || handles_dropped)) {
+            let deadline = self.logging_out.values().min().copied();
+            tokio::select! {
+                command = commands.recv(), if !self.stopping && !handles_dropped => match command {
+                    Some(command) => self.on_command(command).await,
+                    None => handles_dropped = true,
+                },
+                Some(event) = session_rx.recv() => self.on_event(event).await,
+                Some((session_id, number)) = done_rx.recv() => {
+                    // not a task since replaced by a re-added session
+                    if self.tasks.get(&session_id).is_some_and(|task| task.number == number) {
+                        self.close(&session_id).await;
+                    }
+                }
+                () = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    let now = Instant::now();
+                    let timed_out: Vec<SessionID> = self
+                        .logging_out
+                        .iter()
+                        .filter(|(_, deadline)| **deadline <= now)
+                        .map(|(session_id, _)| session_id.clone())
+                        .collect();
+                    for session_id in timed_out {
+                        warn!(%session_id, "no Logout from the counterparty, disconnecting");
+                        self.close(&session_id).await;
+                    }
+                }
+            }
+        }
+        debug!("engine stopped");
+        self.engine
+    }
+
+    async fn on_command(&mut self, command: Command) {
+        match command {
+            Command::Add { settings, reply } => {
+                let _ = reply.send(self.add(settings).await);
+            }
+            Command::Remove { session_id, reply } => {
+                if !self.tasks.contains_key(&session_id) {
+                    let _ = reply.send(Err(EngineError::SessionNotFound(session_id)));
+                    return;
+                }
+                self.removing.insert(session_id.clone());
+                self.waiting
+                    .push((HashSet::from([session_id.clone()]), reply));
+                self.log_out(&session_id).await;
+            }
+            Command::Stop { graceful, reply } => {
+                self.stopping = true;
+                let sessions: Vec<SessionID> = self.tasks.keys().cloned().collect();
+                self.waiting
+                    .push((sessions.iter().cloned().collect(), reply));
+                for session_id in &sessions {
+                    if graceful {
+                        self.log_out(session_id).await;
+                    } else {
+                        self.close(session_id).await;
+                    }
+                }
+                self.reply_to_waiting();
+            }
+            Command::Sender { session_id, reply } => {
+                let _ = reply.send(self.engine.senders.get(&session_id).cloned());
+            }
+        }
+    }
+
+    async fn add(&mut self, settings: Settings) -> Result<SessionID, EngineError> {
+        let session_id = self
+            .engine
+            .create_session(settings)
+            .map_err(EngineError::CreateSession)?
+            .clone();
+        let settings = &self.engine.session_settings[&session_id];
+        let listener = match settings.session_type {
+            SessionType::Initiator => None,
+            SessionType::Acceptor => match listen(&session_id, settings).await {
+                Ok(listener) => Some(listener),
+                Err(e) => {
+                    self.engine.remove_session(&session_id);
+                    return Err(e);
+                }
+            },
+        };
+        self.spawn(&session_id, listener);
+        Ok(session_id)
+    }
+
+    /// Start the transport of a session, an acceptor's on `listener`
+    fn spawn(&mut self, session_id: &SessionID, listener: Option<Listener>) {
+        let Some(settings) = self.engine.session_settings.get(session_id) else {
+            return;
+        };
+        let transport = TransportSettings::from_settings(settings);
+        let connection = settings.connection_settings.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let events = self.session_events.clone();
+        let done = self.done.clone();
+        let id = session_id.clone();
+        let number = self.next_task;
+        self.next_task += 1;
+        let handle = tokio::spawn(async move {
+            match listener {
+                Some(listener) => run_acceptor(id.clone(), transport, listener, rx, events).await,
+                None => run_initiator(id.clone(), transport, connection, rx, events).await,
+            }
+            let _ = done.send((id, number));
+        });
+        self.engine.senders.insert(
+            session_id.clone(),
+            SessionSender::new(session_id.clone(), tx),
+        );
+        self.tasks
+            .insert(session_id.clone(), Task { number, handle });
+    }
+
+    /// Send a Logout if the session is logged on, or close it if it isn't
+    async fn log_out(&mut self, session_id: &SessionID) {
+        if self.engine.session_status(session_id) != Some(&State::LoggedIn) {
+            self.close(session_id).await;
+            return;
+        }
+        self.update(session_id, |engine| engine.logout_session(session_id))
+            .await;
+        let timeout = self.engine.session_settings[session_id]
+            .session_settings
+            .logout_timeout();
+        self.logging_out
+            .insert(session_id.clone(), Instant::now() + timeout);
+    }
+
+    /// Close the session's connection, waiting for its transport to end
+    async fn close(&mut self, session_id: &SessionID) {
+        self.logging_out.remove(session_id);
+        self.engine.senders.remove(session_id);
+        if let Some(task) = self.tasks.remove(session_id) {
+            task.handle.abort();
+            let _ = task.handle.await;
+        }
+        debug!(%session_id, "closed");
+        if matches!(
+            self.engine.session_status(session_id),
+            Some(State::LoginSent | State::LoggedIn | State::LogoutSent)
+        ) {
+            self.update(session_id, |engine| {
+                engine
+                    .session_state
+                    .insert(session_id.clone(), State::LoggedOut);
+            })
+            .await;
+        }
+        if self.removing.remove(session_id) {
+            self.engine.remove_session(session_id);
+        }
+        self.reply_to_waiting();
+    }
+
+    fn reply_to_waiting(&mut self) {
+        for (sessions, _) in &mut self.waiting {
+            sessions.retain(|session_id| self.tasks.contains_key(session_id));
+        }
+        let (done, waiting) = std::mem::take(&mut self.waiting)
+            .into_iter()
+            .partition(|(sessions, _)| sessions.is_empty());
+        self.waiting = waiting;
+        for (_, reply) in done {
+            let _ = reply.send(Ok(()));
+        }
+    }
+
+    /// Pass a transport's event on to the application, then act on it
+    async fn on_event(&mut self, event: SessionEvent) {
+        let session_id = event.session_id().clone();
+        let session_type = self
+            .engine
+            .session_settings
+            .get(&session_id)
+            .map(|settings| settings.session_type);
+        // Logons and Logouts change the session's state
+        let admin = match &event {
+            SessionEvent::Message { message, .. } => message
+                .get_field_safe(35)
+                .ok()
+                .map(Field::value_string)
+                .filter(|msg_type| msg_type == "A" || msg_type == "5")
+                .map(|msg_type| (msg_type, message.clone())),
+            _ => None,
+        };
+        let connected = matches!(event, SessionEvent::Connected { .. });
+        let disconnected = matches!(
+            event,
+            SessionEvent::StateChanged {
+                state: State::LoggedOut,
+                ..
+            }
+        );
+        if disconnected {
+            self.engine
+                .session_state
+                .insert(session_id.clone(), State::LoggedOut);
+        }
+        let _ = self.events.send(event).await;
+
+        if connected && matches!(session_type, Some(SessionType::Initiator)) {
+            self.update(&session_id, |engine| engine.logon_session(&session_id))
+                .await;
+        }
+        let logged_out = match admin {
+            Some((msg_type, message)) if msg_type == "A" => {
+                self.update(&session_id, |engine| engine.on_logon(&session_id, &message))
+                    .await;
+                false
+            }
+            Some((_, message)) => {
+                self.update(&session_id, |engine| {
+                    engine.on_logout(&session_id, &message);
+                })
+                .await;
+                true
+            }
+            None => disconnected,
+        };
+        if logged_out && self.logging_out.contains_key(&session_id) {
+            self.close(&session_id).await;
+        }
+    }
+
+    /// Apply `change` to the engine, reporting the session's new state if it
+    /// changed
+    async fn update(&mut self, session_id: &SessionID, change: impl FnOnce(&mut Engine)) {
+        let before = self.engine.session_status(session_id).copied();
+        change(&mut self.engine);
+        let after = self.engine.session_status(session_id).copied();
+        if let Some(state) = after.filter(|after| Some(*after) != before) {
+            let _ = self
+                .events
+                .send(SessionEvent::StateChanged {
+                    session_id: session_id.clone(),
+                    state,
+                })
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::BytesMut;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+
+    use crate::cracker;
+    use crate::encoder;
+    use crate::engine::filestore::FileStore;
+    use crate::engine::runner::EngineError;
+    use crate::engine::{Engine, FailedToCreateSession};
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+    use crate::model::BeginString;
+    use crate::session::channel::SessionEvent;
+    use crate::session::credentials::{Credentials, LogonCredentials, PlainSecret};
+    use crate::session::settings::{ReconnectSettings, SessionType, SettingsBuilder};
+    use crate::session::{SessionID, State};
+
+    fn engine() -> Engine {
+        Engine::create(Box::new(FileStore {
+            directory: "".to_string(),
+        }))
+    }
+
+    fn builder(sender: &str, target: &str, session_type: SessionType) -> SettingsBuilder {
+        SettingsBuilder::new(
+            BeginString::Fix44,
+            sender.to_string(),
+            target.to_string(),
+            session_type,
+        )
+    }
+
+    /// An address nothing listens on yet
+    fn free_address() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    async fn wait_for(events: &mut mpsc::Receiver<SessionEvent>, expected: State) -> SessionID {
+        loop {
+            if let SessionEvent::StateChanged { session_id, state } = events.recv().await.unwrap() {
+                if state == expected {
+                    return session_id;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn sessions_log_on_and_out() {
+        let address = free_address();
+
+        let mut exchange = engine();
+        exchange
+            .create_session(
+                builder("Exchange", "Rocks", SessionType::Acceptor)
+                    .with_accept_address(&address)
+                    .build(),
+            )
+            .unwrap();
+        let (exchange_tx, mut exchange_events) = mpsc::channel(64);
+        let (exchange, exchange_join) = exchange.start(exchange_tx).await.unwrap();
+
+        let mut rocks = engine();
+        rocks
+            .create_session(
+                builder("Rocks", "Exchange", SessionType::Initiator)
+                    .with_endpoint(&address)
+                    .build(),
+            )
+            .unwrap();
+        let (rocks_tx, mut rocks_events) = mpsc::channel(64);
+        let (rocks, rocks_join) = rocks.start(rocks_tx).await.unwrap();
+
+        let session_id = wait_for(&mut rocks_events, State::LoggedIn).await;
+        assert_eq!("FIX.4.4:Rocks->Exchange", session_id.to_string());
+        wait_for(&mut exchange_events, State::LoggedIn).await;
+        assert!(rocks.sender(&session_id).await.is_some());
+
+        // the exchange answers the Logout, so the initiator closes
+        rocks.stop(true).await;
+        wait_for(&mut exchange_events, State::LoggedOut).await;
+        let rocks = rocks_join.await.unwrap();
+        assert_eq!(Some(&State::LoggedOut), rocks.session_status(&session_id));
+
+        exchange.stop(false).await;
+        assert_eq!(1, exchange_join.await.unwrap().sessions().len());
+    }
+
+    async fn next_sent(connection: &mut TcpStream, buf: &mut BytesMut) -> Message {
+        loop {
+            if let Some(frame) = cracker::next_frame(buf).unwrap() {
+                return cracker::crack_message(&frame).unwrap();
+            }
+            connection.read_buf(buf).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn stop_gives_up_waiting_for_logout() {
+        let exchange = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = exchange.local_addr().unwrap().to_string();
+
+        let mut rocks = engine();
+        rocks
+            .create_session(
+                builder("Rocks", "Exchange", SessionType::Initiator)
+                    .with_endpoint(&address)
+                    .with_logout_timeout(Duration::from_millis(100))
+                    .build(),
+            )
+            .unwrap();
+        let (events_tx, mut events) = mpsc::channel(64);
+        let (rocks, join) = rocks.start(events_tx).await.unwrap();
+
+        // an exchange that logs on but never logs out
+        let (mut connection, _) = exchange.accept().await.unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let logon = next_sent(&mut connection, &mut buf).await;
+        assert_eq!("A", logon.get_field(35).as_str());
+        let mut logon = Message::of_type("A");
+        logon.set_field(Field::String(8, "FIX.4.4".to_string()));
+        logon.set_field(Field::String(49, "Exchange".to_string()));
+        logon.set_field(Field::String(56, "Rocks".to_string()));
+        let mut wire = BytesMut::new();
+        encoder::encode(&logon, &mut wire);
+        connection.write_all(&wire).await.unwrap();
+        wait_for(&mut events, State::LoggedIn).await;
+
+        rocks.stop(true).await;
+        join.await.unwrap();
+        let logout = next_sent(&mut connection, &mut buf).await;
+        assert_eq!("5", logout.get_field(35).as_str());
+    }
+
+    #[tokio::test]
+    async fn refused_logon_is_logged_out_and_disconnected() {
+        let address = free_address();
+        let mut exchange = engine();
+        exchange
+            .create_session(
+                builder("Exchange", "Rocks", SessionType::Acceptor)
+                    .with_accept_address(&address)
+                    .build(),
+            )
+            .unwrap();
+        exchange.set_credential_check(|_: &SessionID, credentials: &LogonCredentials<'_>| {
+            match credentials.password {
+                Some("hunter2") => Ok(()),
+                _ => Err("wrong password".to_string()),
+            }
+        });
+        let (events_tx, _events) = mpsc::channel(64);
+        let (exchange, join) = exchange.start(events_tx).await.unwrap();
+
+        let mut connection = TcpStream::connect(&address).await.unwrap();
+        let mut logon = Message::of_type("A");
+        logon.set_field(Field::String(8, "FIX.4.4".to_string()));
+        logon.set_field(Field::String(49, "Rocks".to_string()));
+        logon.set_field(Field::String(56, "Exchange".to_string()));
+        logon.set_field(Field::String(554, "hunter3".to_string()));
+        let mut wire = BytesMut::new();
+        encoder::encode(&logon, &mut wire);
+        connection.write_all(&wire).await.unwrap();
+
+        // one Logout giving the reason, then the exchange hangs up
+        let mut buf = BytesMut::with_capacity(1024);
+        let logout = next_sent(&mut connection, &mut buf).await;
+        assert_eq!("5", logout.get_field(35).as_str());
+        assert_eq!("wrong password", logout.get_field(58).as_str());
+        let hung_up = async { while connection.read_buf(&mut buf).await.unwrap() > 0 {} };
+        tokio::time::timeout(Duration::from_secs(5), hung_up)
+            .await
+            .expect("the exchange kept the connection open");
+        assert!(cracker::next_frame(&mut buf).unwrap().is_none());
+
+        exchange.stop(false).await;
+        join.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn refused_initiator_is_logged_out_once() {
+        let address = free_address();
+        let mut exchange = engine();
+        exchange
+            .create_session(
+                builder("Exchange", "Rocks", SessionType::Acceptor)
+                    .with_accept_address(&address)
+                    .build(),
+            )
+            .unwrap();
+        exchange.set_credential_check(|_: &SessionID, _: &LogonCredentials<'_>| {
+            Err("wrong password".to_string())
+        });
+        let (exchange_tx, _exchange_events) = mpsc::channel(64);
+        let (exchange, exchange_join) = exchange.start(exchange_tx).await.unwrap();
+
+        let mut rocks = engine();
+        rocks
+            .create_session(
+                builder("Rocks", "Exchange", SessionType::Initiator)
+                    .with_endpoint(&address)
+                    .with_credentials(
+                        Credentials::new("rocks").with_password(PlainSecret("hunter3".to_string())),
+                    )
+                    .build(),
+            )
+            .unwrap();
+        let (rocks_tx, mut rocks_events) = mpsc::channel(64);
+        let (rocks, rocks_join) = rocks.start(rocks_tx).await.unwrap();
+
+        let mut logouts = vec![];
+        loop {
+            match rocks_events.recv().await.unwrap() {
+                SessionEvent::Message { message, .. } => {
+                    logouts.push(message.get_field(58).value_string());
+                }
+                SessionEvent::StateChanged {
+                    state: State::LoggedOut,
+                    ..
+                } => break,
+                _ => {}
+            }
+        }
+        assert_eq!(vec!["wrong password"], logouts);
+
+        rocks.stop(false).await;
+        rocks_join.await.unwrap();
+        exchange.stop(false).await;
+        exchange_join.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_start_hands_back_the_engine() {
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = taken.local_addr().unwrap().to_string();
+        let mut exchange = engine();
+        exchange
+            .create_session(
+                builder("Exchange", "Rocks", SessionType::Acceptor)
+                    .with_accept_address(&address)
+                    .build(),
+            )
+            .unwrap();
+        let (events_tx, _events) = mpsc::channel(64);
+        let Err(error) = exchange.start(events_tx.clone()).await else {
+            panic!("started on an address in use");
+        };
+        assert!(matches!(error.error, EngineError::Listen { .. }));
+        assert_eq!(1, error.engine.sessions().len());
+
+        // once the address is free the same engine starts
+        drop(taken);
+        let (exchange, join) = error.engine.start(events_tx).await.unwrap();
+        exchange.stop(false).await;
+        join.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn engine_ends_once_transports_give_up() {
+        let mut rocks = engine();
+        rocks
+            .create_session(
+                builder("Rocks", "Exchange", SessionType::Initiator)
+                    .with_endpoint(&free_address())
+                    .with_reconnect(ReconnectSettings {
+                        max_attempts: Some(1),
+                        ..ReconnectSettings::default()
+                    })
+                    .build(),
+            )
+            .unwrap();
+        let (events_tx, _events) = mpsc::channel(64);
+        let (handle, join) = rocks.start(events_tx).await.unwrap();
+        drop(handle);
+
+        // the session's transport ending by itself closes it
+        let rocks = tokio::time::timeout(Duration::from_secs(5), join)
+            .await
+            .expect("the engine kept running")
+            .unwrap();
+        assert_eq!(1, rocks.sessions().len());
+    }
+
+    #[tokio::test]
+    async fn sessions_are_added_and_removed_at_runtime() {
+        let (events_tx, _events) = mpsc::channel(64);
+        let (handle, join) = engine().start(events_tx).await.unwrap();
+
+        let settings = builder("Rocks", "Exchange", SessionType::Initiator)
+            .with_endpoint(&free_address())
+            .build();
+        let session_id = handle.add_session(settings.clone()).await.unwrap();
+        assert!(matches!(
+            handle.add_session(settings).await,
+            Err(EngineError::CreateSession(
+                FailedToCreateSession::DuplicateSessionID
+            ))
+        ));
+        assert!(handle.sender(&session_id).await.is_some());
+
+        handle.remove_session(&session_id).await.unwrap();
+        assert!(handle.sender(&session_id).await.is_none());
+        assert!(matches!(
+            handle.remove_session(&session_id).await,
+            Err(EngineError::SessionNotFound(_))
+        ));
+
+        handle.stop(true).await;
+        assert!(join.await.unwrap().sessions().is_empty());
+        assert!(matches!(
+            handle
+                .add_session(
+                    builder("Rocks", "Venue", SessionType::Initiator)
+                        .with_endpoint("localhost:9876")
+                        .build()
+                )
+                .await,
+            Err(EngineError::Stopped)
+        ));
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Formatter;
+
+use minidom::{Element, NSChoice};
+
+use crate::dictionary::{ComponentDef, DataDictionary, Member, MessageDef};
+use crate::model::field::ParseError;
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+use crate::model::BeginString;
+
+const ROOT: &str = "FIXML";
+const HEADER: &str = "Hdr";
+
+/// Fields FIXML carries in its structure rather than as attributes
+const IMPLIED_TAGS: [u32; 4] = [8, 9, 10, 35];
+
+/// Render `message` as FIXML, e.g.
+/// `<FIXML><Order ID="1" Side="1"><Hdr SID="A"/><Instrmt Sym="IBM"/></Order></FIXML>`.
+///
+/// The dictionary's layout of the message decides where each field goes:
+/// the header becomes `Hdr`, components become child elements and fields
+/// become attributes, named by their `abbr` in the dictionary or else their
+/// full name. The namespace carries the version, so `BeginString` (8) and
+/// `MsgType` (35) aren't written, and neither are `BodyLength` (9) or
+/// `CheckSum` (10).
+///
+/// # Errors
+///
+/// Will return `Err` if the dictionary doesn't define the message, or has no
+/// place in its layout for one of its fields. Repeating groups are not
+/// supported yet.
+pub fn encode(dictionary: &DataDictionary, message: &Message) -> Result<Element, FixmlError> {
+    let msg_type = message
+        .get_field_safe(35)
+        .map_err(|_| FixmlError::MissingMsgType)?
+        .value_string();
+    let def = dictionary
+        .message(&msg_type)
+        .ok_or_else(|| FixmlError::UnknownMsgType(msg_type.clone()))?;
+    let ns = namespace(dictionary.begin_string());
+
+    let mut placed = HashSet::from(IMPLIED_TAGS);
+    let mut element = Element::bare(message_name(def), ns.as_str());
+    let mut header = Element::bare(HEADER, ns.as_str());
+    fill(
+        dictionary,
+        &mut header,
+        dictionary.header(),
+        message,
+        &mut placed,
+    );
+    if !is_empty(&header) {
+        element.append_child(header);
+    }
+    fill(dictionary, &mut element, &def.members, message, &mut placed);
+
+    let unplaced = message
+        .header()
+        .iter()
+        .chain(message.body().iter())
+        .chain(message.trailer().iter())
+        .find(|f| !placed.contains(&f.tag()));
+    if let Some(field) = unplaced {
+        return Err(FixmlError::NoPlace {
+            tag: field.tag(),
+            msg_type,
+        });
+    }
+
+    let mut root = Element::bare(ROOT, ns);
+    root.append_child(element);
+    Ok(root)
+}
+
+///
+/// # Errors
+///
+/// Will return `Err` for the reasons `encode` does
+pub fn to_string(dictionary: &DataDictionary, message: &Message) -> Result<String, FixmlError> {
+    let mut xml = vec![];
+    encode(dictionary, message)?
+        .write_to(&mut xml)
+        .map_err(FixmlError::Xml)?;
+    Ok(String::from_utf8_lossy(&xml).into_owned())
+}
+
+/// Parse FIXML back into a message, with values typed as the dictionary's
+/// version defines them. `element` is either the `FIXML` root or the message
+/// element itself.
+///
+/// # Errors
+///
+/// Will return `Err` if an element or attribute has no counterpart in the
+/// dictionary's layout of the message, or a value doesn't parse as its
+/// field's type
+pub fn decode(dictionary: &DataDictionary, element: &Element) -> Result<Message, FixmlError> {
+    let begin_string = BeginString::try_from(dictionary.begin_string())
+        .map_err(|_| FixmlError::UnknownBeginString(dictionary.begin_string().to_string()))?;
+    let element = if element.name() == ROOT {
+        element.children().next().ok_or(FixmlError::Empty)?
+    } else {
+        element
+    };
+    let def = dictionary
+        .messages()
+        .find(|m| message_name(m) == element.name())
+        .ok_or_else(|| FixmlError::UnknownElement(element.name().to_string()))?;
+
+    let mut message = Message::of_type(def.msg_type.clone());
+    message.set_field(Field::String(8, dictionary.begin_string().to_string()));
+    if let Some(header) = element.get_child(HEADER, NSChoice::Any) {
+        read(
+            dictionary,
+            begin_string,
+            header,
+            dictionary.header(),
+            &mut message,
+        )?;
+    }
+    read(
+        dictionary,
+        begin_string,
+        element,
+        &def.members,
+        &mut message,
+    )?;
+    Ok(message)
+}
+
+///
+/// # Errors
+///
+/// Will return `Err` if `xml` isn't well-formed, or for the reasons `decode`
+/// does
+pub fn from_str(dictionary: &DataDictionary, xml: &str) -> Result<Message, FixmlError> {
+    // minidom insists on a namespace, which hand-written FIXML often lacks
+    let xml = match xml.find('<') {
+        Some(start) if !xml.contains("xmlns") && xml[start..].starts_with("<FIXML") => {
+            let at = start + ROOT.len() + 1;
+            format!(
+                "{} xmlns='{}'{}",
+                &xml[..at],
+                namespace(dictionary.begin_string()),
+                &xml[at..]
+            )
+        }
+        _ => xml.to_string(),
+    };
+    decode(
+        dictionary,
+        &xml.parse::<Element>().map_err(FixmlError::Xml)?,
+    )
+}
+
+/// `http://www.fixprotocol.org/FIXML-4-4` for FIX.4.4, `...FIXML-5-0-SP2` for
+/// FIX.5.0SP2
+fn namespace(begin_string: &str) -> String {
+    let version = begin_string
+        .trim_start_matches("FIXT.")
+        .trim_start_matches("FIX.")
+        .replace('.', "-")
+        .replace("SP", "-SP");
+    format!("http://www.fixprotocol.org/FIXML-{}", version)
+}
+
+fn fill(
+    dictionary: &DataDictionary,
+    element: &mut Element,
+    members: &[Member],
+    message: &Message,
+    placed: &mut HashSet<u32>,
+) {
+    for member in members {
+        match member {
+            Member::Field { tag, .. } if !placed.contains(tag) => {
+                if let Ok(field) = message.get_field_safe(*tag) {
+                    element.set_attr(field_name(dictionary, *tag), field.value_string());
+                    placed.insert(*tag);
+                }
+            }
+            Member::Component { name, .. } => {
+                if let Some(component) = dictionary.component(name) {
+                    let mut child = Element::bare(component_name(component), element.ns());
+                    fill(dictionary, &mut child, &component.members, message, placed);
+                    if !is_empty(&child) {
+                        element.append_child(child);
+                    }
+                }
+            }
+            // a message holds a single instance of each tag, which leaves
+            // no way to tell group entries apart
+            Member::Field { .. } | Member::Group { .. } => {}
+        }
+    }
+}
+
+fn read(
+    dictionary: &DataDictionary,
+    begin_string: BeginString,
+    element: &Element,
+    members: &[Member],
+    message: &mut Message,
+) -> Result<(), FixmlError> {
+    for (name, value) in element.attrs() {
+        let tag = members
+            .iter()
+            .find_map(|m| match m {
+                Member::Field { tag, .. } if field_name(dictionary, *tag) == name => Some(*tag),
+                _ => None,
+            })
+            .ok_or_else(|| FixmlError::UnknownAttribute {
+                element: element.name().to_string(),
+                name: name.to_string(),
+            })?;
+        let field =
+            Field::crack(begin_string, &format!("{}={}", tag, value)).map_err(FixmlError::Value)?;
+        message.set_field(field);
+    }
+
+    // the header is read by `decode`
+    for child in element.children().filter(|c| c.name() != HEADER) {
+        let component = members
+            .iter()
+            .find_map(|m| match m {
+                Member::Component { name, .. } => dictionary
+                    .component(name)
+                    .filter(|c| component_name(c) == child.name()),
+                _ => None,
+            })
+            .ok_or_else(|| FixmlError::UnknownElement(child.name().to_string()))?;
+        read(dictionary, begin_string, child, &component.members, message)?;
+    }
+    Ok(())
+}
+
+fn field_name(dictionary: &DataDictionary, tag: u32) -> String {
+    dictionary.field(tag).map_or_else(
+        || tag.to_string(),
+        |def| def.abbr.clone().unwrap_or_else(|| def.name.clone()),
+    )
+}
+
+fn message_name(def: &MessageDef) -> &str {
+    def.abbr.as_deref().unwrap_or(&def.name)
+}
+
+fn component_name(def: &ComponentDef) -> &str {
+    def.abbr.as_deref().unwrap_or(&def.name)
+}
+
+fn is_empty(element: &Element) -> bool {
+    element.attrs().next().is_none() && element.children().next().is_none()
+}
+
+#[derive(Debug)]
+pub enum FixmlError {
+    Xml(minidom::Error),
+    UnknownBeginString(String),
+    MissingMsgType,
+    UnknownMsgType(String),
+    /// The message's layout in the dictionary doesn't include `tag`
+    NoPlace {
+        tag: u32,
+        msg_type: String,
+    },
+    Empty,
+    UnknownElement(String),
+    UnknownAttribute {
+        element: String,
+        name: String,
+    },
+    Value(ParseError),
+}
+
+impl fmt::Display for FixmlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FixmlError::Xml(e) => write!(f, "invalid FIXML: {}", e),
+            FixmlError::UnknownBeginString(v) => {
+                write!(f, "dictionary version {:?} is not a known version", v)
+            }
+            FixmlError::MissingMsgType => write!(f, "MsgType (35) is missing"),
+            FixmlError::UnknownMsgType(v) => {
+                write!(f, "MsgType (35) {:?} is not in the dictionary", v)
+            }
+            FixmlError::NoPlace { tag, msg_type } => write!(
+                f,
+                "tag {} is not part of the layout of MsgType (35) {:?}",
+                tag, msg_type
+            ),
+            FixmlError::Empty => write!(f, "FIXML element holds no message"),
+            FixmlError::UnknownElement(name) => write!(f, "unexpected element <{}>", name),
+            FixmlError::UnknownAttribute { element, name } => {
+                write!(f, "unexpected attribute {} on <{}>", name, element)
+            }
+            FixmlError::Value(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use crate::dictionary::DataDictionary;
+    use crate::fixml::{from_str, to_string, FixmlError};
+    use crate::model::field::{ParseError, ValueType};
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+
+    fn fix44() -> DataDictionary {
+        DataDictionary::load(concat!(env!("CARGO_MANIFEST_DIR"), "/spec/FIX44.xml")).unwrap()
+    }
+
+    fn fix44_with_abbreviations() -> DataDictionary {
+        let mut dd = fix44();
+        dd.load_extension(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/spec/extensions/examples/fixml.xml"
+        ))
+        .unwrap();
+        dd
+    }
+
+    fn order() -> Message {
+        let mut order = Message::of_type("D");
+        order.set_field(Field::String(8, "FIX.4.4".to_string()));
+        order.set_field(Field::String(49, "OMS".to_string()));
+        order.set_field(Field::String(56, "BROKER".to_string()));
+        order.set_field(Field::String(11, "order-1".to_string()));
+        order.set_field(Field::String(55, "IBM".to_string()));
+        order.set_field(Field::Char(54, '1'));
+        order.set_field(Field::Decimal(38, Decimal::new(100, 0)));
+        order.set_field(Field::Decimal(44, Decimal::new(1025, 2)));
+        order.set_field(Field::Char(40, '2'));
+        order
+    }
+
+    #[test]
+    fn abbreviated_fixml() {
+        let dd = fix44_with_abbreviations();
+        let fixml = to_string(&dd, &order()).unwrap();
+        assert_eq!(
+            concat!(
+                r#"<FIXML xmlns="http://www.fixprotocol.org/FIXML-4-4">"#,
+                r#"<Order ID="order-1" Px="10.25" Side="1" Typ="2">"#,
+                r#"<Hdr SID="OMS" TID="BROKER"/><Instrmt Sym="IBM"/><OrdQty Qty="100"/>"#,
+                "</Order></FIXML>"
+            ),
+            fixml
+        );
+
+        let decoded = from_str(&dd, &fixml).unwrap();
+        assert_eq!(order().to_string(), decoded.to_string());
+        assert_eq!(
+            Field::Decimal(44, Decimal::new(1025, 2)),
+            *decoded.get_field(44)
+        );
+    }
+
+    #[test]
+    fn full_names_without_abbreviations() {
+        let dd = fix44();
+        let fixml = to_string(&dd, &order()).unwrap();
+        assert!(fixml.contains(r#"<NewOrderSingle ClOrdID="order-1" OrdType="2""#));
+        assert!(fixml.contains(r#"<Instrument Symbol="IBM"/>"#));
+
+        // no namespace, as counterparties tend to send it
+        let decoded = from_str(
+            &dd,
+            r#"<FIXML><NewOrderSingle ClOrdID="order-1" Side="1"><Hdr SenderCompID="OMS"/></NewOrderSingle></FIXML>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            "8=FIX.4.4|35=D|49=OMS|11=order-1|54=1|",
+            decoded.to_string()
+        );
+    }
+
+    #[test]
+    fn invalid_fixml() {
+        let dd = fix44_with_abbreviations();
+
+        let mut custom = order();
+        custom.set_field(Field::String(20_001, "venue".to_string()));
+        assert!(matches!(
+            to_string(&dd, &custom),
+            Err(FixmlError::NoPlace { tag: 20_001, .. })
+        ));
+
+        assert!(matches!(
+            from_str(&dd, r#"<FIXML><Order Sym="IBM"/></FIXML>"#),
+            Err(FixmlError::UnknownAttribute { element, name }) if element == "Order" && name == "Sym"
+        ));
+        assert!(matches!(
+            from_str(&dd, r#"<FIXML><Order><Pty ID="a"/></Order></FIXML>"#),
+            Err(FixmlError::UnknownElement(name)) if name == "Pty"
+        ));
+        assert!(matches!(
+            from_str(&dd, r#"<FIXML><Order Px="cheap"/></FIXML>"#),
+            Err(FixmlError::Value(e)) if e == ParseError::new(Some(44), "cheap", ValueType::Decimal, 3)
+        ));
+        assert!(matches!(
+            from_str(&dd, "<FIXML><Order></FIXML>"),
+            Err(FixmlError::Xml(_))
+        ));
+    }
+}
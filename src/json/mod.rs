@@ -0,0 +1,229 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+use serde_json::{Map, Value};
+
+use crate::dictionary::DataDictionary;
+use crate::model::field::{FieldSet, ParseError};
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+use crate::model::BeginString;
+
+const SECTIONS: [&str; 3] = ["Header", "Body", "Trailer"];
+
+/// Render `message` in the FIX Trading Community's JSON encoding, e.g.
+/// `{"Header":{"MsgType":"D",..},"Body":{"Side":"1",..},"Trailer":{}}`.
+///
+/// Fields are named as in `dictionary`, tags it doesn't define are keyed by
+/// their number. Values are always strings, so prices keep their precision.
+/// `BodyLength` (9) and `CheckSum` (10) only mean something on the wire and
+/// are left out.
+#[must_use]
+pub fn to_value(dictionary: &DataDictionary, message: &Message) -> Value {
+    let sections = [message.header(), message.body(), message.trailer()];
+    let encoded = SECTIONS
+        .iter()
+        .zip(sections)
+        .map(|(name, fields)| (name.to_string(), Value::Object(section(dictionary, fields))))
+        .collect();
+    Value::Object(encoded)
+}
+
+#[must_use]
+pub fn to_string(dictionary: &DataDictionary, message: &Message) -> String {
+    to_value(dictionary, message).to_string()
+}
+
+/// Parse a message in the FIX JSON encoding, with field names looked up in
+/// `dictionary` and values typed as its version defines them.
+///
+/// # Errors
+///
+/// Will return `Err` if a field name isn't in `dictionary`, a value doesn't
+/// parse as its field's type, or the JSON isn't shaped like a FIX message.
+/// Repeating groups are not supported yet.
+pub fn from_value(dictionary: &DataDictionary, value: &Value) -> Result<Message, JsonError> {
+    let begin_string = BeginString::try_from(dictionary.begin_string())
+        .map_err(|_| JsonError::UnknownBeginString(dictionary.begin_string().to_string()))?;
+    let sections = value
+        .as_object()
+        .ok_or_else(|| JsonError::NotAnObject("message".to_string()))?;
+
+    let mut message = Message::default();
+    for name in SECTIONS {
+        let Some(fields) = sections.get(name) else {
+            continue;
+        };
+        let fields = fields
+            .as_object()
+            .ok_or_else(|| JsonError::NotAnObject(name.to_string()))?;
+        for (key, value) in fields {
+            message.set_field(field(dictionary, begin_string, key, value)?);
+        }
+    }
+    Ok(message)
+}
+
+///
+/// # Errors
+///
+/// Will return `Err` if `json` isn't valid JSON, or for the reasons
+/// `from_value` does
+pub fn from_str(dictionary: &DataDictionary, json: &str) -> Result<Message, JsonError> {
+    from_value(
+        dictionary,
+        &serde_json::from_str(json).map_err(JsonError::Json)?,
+    )
+}
+
+fn section(dictionary: &DataDictionary, fields: &FieldSet) -> Map<String, Value> {
+    fields
+        .iter()
+        .filter(|f| !matches!(f.tag(), 9 | 10))
+        .map(|f| {
+            let name = dictionary
+                .field(f.tag())
+                .map_or_else(|| f.tag().to_string(), |def| def.name.clone());
+            (name, Value::String(f.value_string()))
+        })
+        .collect()
+}
+
+fn field(
+    dictionary: &DataDictionary,
+    begin_string: BeginString,
+    key: &str,
+    value: &Value,
+) -> Result<Field, JsonError> {
+    let tag = match dictionary.field_by_name(key) {
+        Some(def) => def.tag,
+        None => key
+            .parse()
+            .map_err(|_| JsonError::UnknownField(key.to_string()))?,
+    };
+    let value = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => if *b { "Y" } else { "N" }.to_string(),
+        Value::Array(_) => return Err(JsonError::Group(key.to_string())),
+        Value::Null | Value::Object(_) => return Err(JsonError::NotAValue(key.to_string())),
+    };
+    Field::crack(begin_string, &format!("{}={}", tag, value)).map_err(JsonError::Value)
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    Json(serde_json::Error),
+    UnknownBeginString(String),
+    NotAnObject(String),
+    UnknownField(String),
+    NotAValue(String),
+    Group(String),
+    Value(ParseError),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Json(e) => write!(f, "invalid JSON: {}", e),
+            JsonError::UnknownBeginString(v) => {
+                write!(f, "dictionary version {:?} is not a known version", v)
+            }
+            JsonError::NotAnObject(name) => write!(f, "{} is not a JSON object", name),
+            JsonError::UnknownField(name) => {
+                write!(f, "{:?} is neither a field name nor a tag", name)
+            }
+            JsonError::NotAValue(name) => write!(f, "{} must be a string or number", name),
+            JsonError::Group(name) => {
+                write!(f, "repeating group {} is not supported", name)
+            }
+            JsonError::Value(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use serde_json::json;
+
+    use crate::dictionary::DataDictionary;
+    use crate::json::{from_str, from_value, to_value, JsonError};
+    use crate::model::field::{ParseError, ValueType};
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+
+    fn fix44() -> DataDictionary {
+        DataDictionary::load(concat!(env!("CARGO_MANIFEST_DIR"), "/spec/FIX44.xml")).unwrap()
+    }
+
+    fn order() -> Message {
+        let mut order = Message::of_type("D");
+        order.set_field(Field::String(8, "FIX.4.4".to_string()));
+        order.set_field(Field::Int(9, 42));
+        order.set_field(Field::String(34, "7".to_string()));
+        order.set_field(Field::String(11, "order-1".to_string()));
+        order.set_field(Field::Char(54, '1'));
+        order.set_field(Field::Decimal(44, Decimal::new(10_250, 3)));
+        order.set_field(Field::String(20_001, "venue".to_string()));
+        order.set_field(Field::String(10, "123".to_string()));
+        order
+    }
+
+    #[test]
+    fn fix_json_encoding() {
+        let dd = fix44();
+        let encoded = to_value(&dd, &order());
+        assert_eq!(
+            json!({
+                "Header": {"BeginString": "FIX.4.4", "MsgSeqNum": "7", "MsgType": "D"},
+                "Body": {"ClOrdID": "order-1", "Price": "10.250", "Side": "1", "20001": "venue"},
+                "Trailer": {}
+            }),
+            encoded
+        );
+
+        let decoded = from_value(&dd, &encoded).unwrap();
+        assert_eq!(Field::Char(54, '1'), *decoded.get_field(54));
+        assert_eq!(
+            Field::Decimal(44, Decimal::new(10_250, 3)),
+            *decoded.get_field(44)
+        );
+        assert_eq!(
+            "8=FIX.4.4|34=7|35=D|11=order-1|44=10.250|54=1|20001=venue|",
+            decoded.to_string()
+        );
+    }
+
+    #[test]
+    fn invalid_fix_json() {
+        let dd = fix44();
+        assert!(matches!(
+            from_str(&dd, r#"{"Body": {"NoSuchField": "1"}}"#),
+            Err(JsonError::UnknownField(name)) if name == "NoSuchField"
+        ));
+        assert!(matches!(
+            from_str(&dd, r#"{"Body": {"NoPartyIDs": [{"PartyID": "a"}]}}"#),
+            Err(JsonError::Group(name)) if name == "NoPartyIDs"
+        ));
+        assert!(matches!(
+            from_str(&dd, r#"{"Body": []}"#),
+            Err(JsonError::NotAnObject(name)) if name == "Body"
+        ));
+        assert!(matches!(from_str(&dd, "{"), Err(JsonError::Json(_))));
+        assert!(matches!(
+            from_str(&dd, r#"{"Body": {"Price": "cheap"}}"#),
+            Err(JsonError::Value(e)) if e == ParseError::new(Some(44), "cheap", ValueType::Decimal, 3)
+        ));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let json = serde_json::to_string(&order()).unwrap();
+        assert!(json.contains(r#"{"Decimal":[44,"10.250"]}"#));
+
+        let message: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(order().to_string(), message.to_string());
+        assert_eq!(Field::Char(54, '1'), *message.get_field(54));
+    }
+}
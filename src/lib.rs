@@ -2,9 +2,18 @@
 
 pub mod application;
 pub mod cracker;
+pub mod dictionary;
+pub mod encoder;
 pub mod engine;
+#[cfg(feature = "fixml")]
+pub mod fixml;
+#[cfg(feature = "serde")]
+pub mod json;
 pub mod model;
+pub mod sbe;
 pub mod session;
+pub mod translator;
+pub mod transport;
 
 #[cfg(test)]
 mod tests {
@@ -1,16 +1,16 @@
 use core::fmt;
 
 use std::borrow::BorrowMut;
-use std::collections::HashMap;
+use std::collections::{btree_map, BTreeMap};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
-use std::vec::IntoIter;
 
 use crate::model;
 use crate::model::generated::fields::Field;
 use crate::model::generated::fix42::MsgTypeField;
 use crate::model::BeginString;
 use itertools::Itertools;
+use rust_decimal::Decimal;
 
 // todo thinking, nothing here should be generated; those impls in a different file
 
@@ -34,9 +34,16 @@ impl FromStr for MsgCat {
 
 // --- FieldSet -----------
 
+/// Serialized as a sequence of fields in tag order
 #[derive(Default, Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Vec<Field>", into = "Vec<Field>")
+)]
 pub struct FieldSet {
-    fields: HashMap<u16, Field>,
+    /// Keyed by tag, so iterating yields fields in tag order without sorting
+    fields: BTreeMap<u32, Field>,
 }
 
 impl Display for FieldSet {
@@ -44,7 +51,7 @@ impl Display for FieldSet {
         write!(
             f,
             "{}",
-            self.fields.iter().map(|t| t.1.to_string()).join("")
+            self.fields.values().map(ToString::to_string).join("")
         )
     }
 }
@@ -85,34 +92,171 @@ impl FieldSet {
     /// # Errors
     ///
     /// Will return `Err` if a field for `tag` is not present in the `FieldSet`
-    pub fn get_field(&self, tag: u16) -> Result<&Field, NoSuchField> {
+    pub fn get_field(&self, tag: u32) -> Result<&Field, NoSuchField> {
         self.fields.get(&tag).ok_or(NoSuchField { tag })
     }
 
-    // todo impl trait
+    /// Fields in tag order
     #[must_use]
-    pub fn iter(&self) -> IntoIter<&Field> {
-        self.fields.iter().map(|s| s.1).sorted_by_key(|k| k.tag())
+    pub fn iter(&self) -> btree_map::Values<'_, u32, Field> {
+        self.fields.values()
     }
 
-    // todo impl trait
+    /// Fields in tag order
     #[must_use]
-    pub fn into_iter(self) -> IntoIter<Field> {
-        self.fields
-            .iter()
-            .map(|s| s.1.clone())
-            .sorted_by_key(Field::tag)
+    pub fn into_iter(self) -> btree_map::IntoValues<u32, Field> {
+        self.fields.into_values()
+    }
+}
+
+impl From<Vec<Field>> for FieldSet {
+    fn from(fields: Vec<Field>) -> Self {
+        Self::with(fields)
+    }
+}
+
+impl From<FieldSet> for Vec<Field> {
+    fn from(field_set: FieldSet) -> Self {
+        field_set.into_iter().collect()
+    }
+}
+
+/// How a version represents a tag
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FieldType {
+    /// Type as named in the version's spec, e.g. `CHAR` or `UTCTIMESTAMP`
+    pub fix_type: &'static str,
+    /// `Field` variant holding the value; types without one of their own are
+    /// held as strings
+    pub kind: FieldKind,
+}
+
+/// The `Field` variants
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FieldKind {
+    String,
+    Int,
+    Char,
+    Decimal,
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldKind::String => write!(f, "string"),
+            FieldKind::Int => write!(f, "int"),
+            FieldKind::Char => write!(f, "char"),
+            FieldKind::Decimal => write!(f, "decimal"),
+        }
     }
 }
 
 /// --- Field impls which _do not_ need to be generated
 
 impl Field {
-    // todo do we need is_body and is_trailer also?
-    // todo NEED THIS but should pull from config
+    #[must_use]
+    pub const fn kind(&self) -> FieldKind {
+        match self {
+            Field::String(..) => FieldKind::String,
+            Field::Int(..) => FieldKind::Int,
+            Field::Char(..) => FieldKind::Char,
+            Field::Decimal(..) => FieldKind::Decimal,
+        }
+    }
+
+    /// The same value held as `kind`, e.g. FIX.4.0's string Side (54) as the
+    /// char later versions use.
+    ///
+    /// The value's wire form is re-parsed, so a conversion either keeps the
+    /// value exactly as it would be sent or fails.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value can't be held as `kind`
+    pub fn convert(&self, kind: FieldKind) -> Result<Self, ConversionError> {
+        if self.kind() == kind {
+            return Ok(self.clone());
+        }
+
+        let tag = self.tag();
+        let value = self.value_string();
+        let converted = match kind {
+            FieldKind::String => Some(Field::String(tag, value.clone())),
+            FieldKind::Int => value.parse().ok().map(|v| Field::Int(tag, v)),
+            FieldKind::Char => value.parse().ok().map(|v| Field::Char(tag, v)),
+            FieldKind::Decimal => Decimal::from_str(&value)
+                .ok()
+                .map(|v| Field::Decimal(tag, v)),
+        };
+        converted.ok_or(ConversionError::Incompatible { tag, value, kind })
+    }
+
+    /// The field as `begin_string` types its tag
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `begin_string` doesn't define the tag or the value
+    /// can't be held as the type it does
+    pub fn to_version(&self, begin_string: BeginString) -> Result<Self, ConversionError> {
+        match Self::field_type(begin_string, self.tag()) {
+            Some(field_type) => self.convert(field_type.kind),
+            None => Err(ConversionError::NotDefined {
+                tag: self.tag(),
+                begin_string,
+            }),
+        }
+    }
+
     #[must_use]
     pub const fn is_header_field(&self) -> bool {
-        matches!(self.tag(), 8 | 35)
+        Self::is_header_tag(self.tag())
+    }
+
+    #[must_use]
+    pub const fn is_trailer_field(&self) -> bool {
+        Self::is_trailer_tag(self.tag())
+    }
+
+    // todo NEED THIS but should pull from config
+    #[must_use]
+    pub const fn is_header_tag(tag: u32) -> bool {
+        matches!(
+            tag,
+            8 | 9
+                | 34
+                | 35
+                | 43
+                | 49
+                | 50
+                | 52
+                | 56
+                | 57
+                | 97
+                | 115
+                | 116
+                | 122
+                | 128
+                | 129
+                | 142
+                | 143
+                | 144
+                | 145
+                | 1128
+                | 1129
+        )
+    }
+
+    #[must_use]
+    pub const fn is_trailer_tag(tag: u32) -> bool {
+        matches!(tag, 10 | 89 | 93)
+    }
+
+    /// Value as it appears on the wire, without the tag
+    #[must_use]
+    pub fn value_string(&self) -> String {
+        let delimited = self.to_delimited_string('\x01');
+        let start = delimited.find('=').map_or(0, |i| i + 1);
+        delimited[start..delimited.len() - 1].to_string()
     }
 
     #[must_use]
@@ -134,7 +278,7 @@ impl fmt::Display for Field {
 /// Field was not found on the Message
 #[derive(Debug)]
 pub struct NoSuchField {
-    pub tag: u16,
+    pub tag: u32,
 }
 impl fmt::Display for NoSuchField {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -142,6 +286,117 @@ impl fmt::Display for NoSuchField {
     }
 }
 
+/// A received field could not be parsed
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// Tag of the offending field, if the tag itself could be parsed
+    pub tag: Option<u32>,
+    /// The raw text which failed to parse
+    pub value: String,
+    pub expected: ValueType,
+    /// Byte offset of `value` from the start of the message
+    pub offset: usize,
+}
+
+impl ParseError {
+    #[must_use]
+    pub fn new(tag: Option<u32>, value: &str, expected: ValueType, offset: usize) -> Self {
+        Self {
+            tag,
+            value: value.to_string(),
+            expected,
+            offset,
+        }
+    }
+
+    /// Same error, with the offset moved on by `by` bytes
+    #[must_use]
+    pub const fn offset_by(mut self, by: usize) -> Self {
+        self.offset += by;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.tag {
+            Some(tag) => write!(
+                f,
+                "Field {} value {:?} at byte {} is not a valid {}",
+                tag, self.value, self.offset, self.expected
+            ),
+            None => write!(
+                f,
+                "{:?} at byte {} is not a valid {}",
+                self.value, self.offset, self.expected
+            ),
+        }
+    }
+}
+
+/// What a `ParseError`'s value should have been
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ValueType {
+    /// A `tag=value` pair
+    Field,
+    Tag,
+    BeginString,
+    ApplVerID,
+    String,
+    Char,
+    Decimal,
+    Int,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueType::Field => write!(f, "tag=value field"),
+            ValueType::Tag => write!(f, "tag number"),
+            ValueType::BeginString => write!(f, "BeginString"),
+            ValueType::ApplVerID => write!(f, "ApplVerID"),
+            ValueType::String => write!(f, "UTF-8 string"),
+            ValueType::Char => write!(f, "char"),
+            ValueType::Decimal => write!(f, "decimal"),
+            ValueType::Int => write!(f, "int"),
+        }
+    }
+}
+
+/// A field could not be converted to another version's type
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConversionError {
+    NotDefined {
+        tag: u32,
+        begin_string: BeginString,
+    },
+    Incompatible {
+        tag: u32,
+        value: String,
+        kind: FieldKind,
+    },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConversionError::NotDefined { tag, begin_string } => write!(
+                f,
+                "Field {} is not defined in {}",
+                tag,
+                String::from(*begin_string)
+            ),
+            ConversionError::Incompatible { tag, value, kind } => {
+                write!(
+                    f,
+                    "Field {} value {:?} can't be held as {}",
+                    tag, value, kind
+                )
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FieldTypeMismatchError;
 impl fmt::Display for FieldTypeMismatchError {
@@ -162,13 +417,24 @@ impl TryFrom<String> for BeginString {
     type Error = NoSuchField;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
+        Self::try_from(value.as_str())
+    }
+}
+
+impl TryFrom<&str> for BeginString {
+    type Error = NoSuchField;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
             "FIX.4.0" => Ok(BeginString::Fix40),
             "FIX.4.1" => Ok(BeginString::Fix41),
             "FIX.4.2" => Ok(BeginString::Fix42),
             "FIX.4.3" => Ok(BeginString::Fix43),
             "FIX.4.4" => Ok(BeginString::Fix44),
             "FIX.5.0" => Ok(BeginString::Fix50),
+            "FIX.5.0SP1" => Ok(BeginString::Fix50Sp1),
+            "FIX.5.0SP2" => Ok(BeginString::Fix50Sp2),
+            "FIXT.1.1" => Ok(BeginString::Fixt11),
             _ => Err(NoSuchField { tag: 8 }),
         }
     }
@@ -183,9 +449,9 @@ impl From<BeginString> for String {
             BeginString::Fix43 => "FIX.4.3".to_string(),
             BeginString::Fix44 => "FIX.4.4".to_string(),
             BeginString::Fix50 => "FIX.5.0".to_string(),
-            BeginString::Fix50Sp1 => "TODO".to_string(),
-            BeginString::Fix50Sp2 => "TODO".to_string(),
-            BeginString::Fixt11 => "TODO".to_string(),
+            BeginString::Fix50Sp1 => "FIX.5.0SP1".to_string(),
+            BeginString::Fix50Sp2 => "FIX.5.0SP2".to_string(),
+            BeginString::Fixt11 => "FIXT.1.1".to_string(),
         }
     }
 }
@@ -8,6 +8,7 @@ pub mod fix50;
 pub mod fix50sp1;
 pub mod fix50sp2;
 pub mod fixt11;
+pub mod sbe_orders;
 
 #[cfg(test)]
 mod tests {
@@ -65,8 +66,21 @@ mod tests {
         fs.set_field_2(ClOrdIDField::new("123"));
         fs.set_field_2(OrderQtyField::new(123));
         fs.set_field_2(Field::String(60000, "BOOK SHELF".to_string()));
-        // todo u16 max val too low?
 
         println!("{}", fs);
     }
+
+    #[test]
+    fn high_custom_tags() {
+        let mut fs = FieldSet::default();
+        fs.set_field(Field::String(65_536, "above u16".to_string()));
+        fs.set_field(Field::Int(4_000_000_000, 7));
+
+        assert_eq!("above u16", fs.get_field(65_536).unwrap().as_str());
+        assert_eq!(4_000_000_000, fs.get_field(4_000_000_000).unwrap().tag());
+        assert_eq!(
+            "65536=above u16|4000000000=7|",
+            fs.iter().map(ToString::to_string).collect::<String>()
+        );
+    }
 }
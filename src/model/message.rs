@@ -1,11 +1,18 @@
 use std::fmt;
 use std::fmt::Formatter;
 
+use bytes::BytesMut;
+
+use crate::encoder;
 use crate::model::field::{FieldSet, NoSuchField};
 use crate::model::generated::fields::Field;
 use crate::model::message_type::UnknownMsgTypeError;
 
-#[derive(Default, Debug)]
+/// Password (554) and NewPassword (925), masked when a message is logged
+const SECRET_TAGS: [u32; 2] = [554, 925];
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Message {
     header: FieldSet,
     body: FieldSet,
@@ -62,11 +69,13 @@ impl Message {
     ///
     /// # Errors
     ///
-    pub fn get_field_safe(&self, tag: u16) -> Result<&Field, NoSuchField> {
+    pub fn get_field_safe(&self, tag: u32) -> Result<&Field, NoSuchField> {
         if let Ok(s) = self.header.get_field(tag) {
             Ok(s)
         } else if let Ok(s) = self.body.get_field(tag) {
             Ok(s)
+        } else if let Ok(s) = self.trailer.get_field(tag) {
+            Ok(s)
         } else {
             Err(NoSuchField { tag })
         }
@@ -78,11 +87,13 @@ impl Message {
     /// # Panics
     ///
     #[must_use]
-    pub fn get_field(&self, tag: u16) -> &Field {
+    pub fn get_field(&self, tag: u32) -> &Field {
         if let Ok(s) = self.header.get_field(tag) {
             s
         } else if let Ok(s) = self.body.get_field(tag) {
             s
+        } else if let Ok(s) = self.trailer.get_field(tag) {
+            s
         } else {
             self.body.get_field(tag).unwrap()
         }
@@ -91,18 +102,52 @@ impl Message {
     pub fn set_field(&mut self, field: Field) {
         if field.is_header_field() {
             self.header.set_field(field);
+        } else if field.is_trailer_field() {
+            self.trailer.set_field(field);
         } else {
             self.body.set_field(field);
         }
     }
 
+    #[must_use]
+    pub const fn header(&self) -> &FieldSet {
+        &self.header
+    }
+
+    #[must_use]
+    pub const fn body(&self) -> &FieldSet {
+        &self.body
+    }
+
+    #[must_use]
+    pub const fn trailer(&self) -> &FieldSet {
+        &self.trailer
+    }
+
+    /// Fields as they appear on the wire, without `BodyLength` or `CheckSum`;
+    /// see `encoder::encode` for a complete message
     #[must_use]
     pub fn to_wire_bytes(&self) -> Vec<u8> {
-        // todo temp. impl.
-        self.to_string()
-            // .replace('|', '\x01'.to_string().as_str())
-            .replace('|', "\x01")
-            .into_bytes()
+        let mut buf = BytesMut::new();
+        self.header
+            .iter()
+            .chain(self.body.iter())
+            .chain(self.trailer.iter())
+            .for_each(|f| encoder::put_field(&mut buf, f));
+        buf.to_vec()
+    }
+
+    /// Displays like the message but with passwords masked, for logging
+    #[must_use]
+    pub const fn redacted(&self) -> Redacted<'_> {
+        Redacted(self)
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &Field> {
+        self.header
+            .iter()
+            .chain(self.body.iter())
+            .chain(self.trailer.iter())
     }
 
     fn add_all(&mut self, field_set: FieldSet) {
@@ -123,16 +168,29 @@ impl From<FieldSet> for Message {
 impl fmt::Display for Message {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let pretty_string: String = self
-            .header
-            .iter()
-            .chain(self.body.iter())
-            .chain(self.trailer.iter())
+            .fields()
             .map(std::string::ToString::to_string)
             .collect();
         write!(f, "{}", pretty_string)
     }
 }
 
+/// A message displayed with its passwords masked, see [`Message::redacted`]
+pub struct Redacted<'a>(&'a Message);
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for field in self.0.fields() {
+            if SECRET_TAGS.contains(&field.tag()) {
+                write!(f, "{}=***|", field.tag())?;
+            } else {
+                write!(f, "{}", field)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::model::generated::fields::Field;
@@ -155,6 +213,14 @@ mod tests {
         assert_eq!(expected, logon_msg.to_wire_bytes());
     }
 
+    #[test]
+    fn wire_bytes_keep_pipes_in_values() {
+        let mut msg = Message::of_type("0");
+        msg.set_field(Field::String(58, "a|b".to_string()));
+
+        assert_eq!(b"35=0\x0158=a|b\x01".to_vec(), msg.to_wire_bytes());
+    }
+
     #[test]
     fn adding_fields() {
         let mut msg = Message::of_type("ABC");
@@ -174,4 +240,16 @@ mod tests {
         let expected = "35=ABC|11=NewClOrdID|58=Testing!|";
         assert_eq!(expected, msg.to_string());
     }
+
+    #[test]
+    fn redacted_masks_passwords() {
+        let mut logon = Message::of_type("A");
+        logon.set_field(Field::String(553, "rocks".to_string()));
+        logon.set_field(Field::String(554, "hunter2".to_string()));
+        logon.set_field(Field::String(925, "hunter3".to_string()));
+
+        let logged = logon.redacted().to_string();
+        assert_eq!("35=A|553=rocks|554=***|925=***|", logged);
+        assert!(!logged.contains("hunter"));
+    }
 }
@@ -1,5 +1,9 @@
 use std::fmt;
 
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+use crate::model::BeginString;
+
 #[derive(Debug, Clone)]
 pub struct UnknownMsgTypeError {
     pub val: String,
@@ -11,6 +15,65 @@ impl fmt::Display for UnknownMsgTypeError {
     }
 }
 
+/// No handler is registered for the message's MsgType
+#[derive(Debug, Clone)]
+pub struct UnsupportedMessageType {
+    pub begin_string: Option<BeginString>,
+    pub msg_type: String,
+    pub ref_seq_num: Option<i32>,
+}
+
+impl UnsupportedMessageType {
+    #[must_use]
+    pub fn of(message: &Message) -> Self {
+        Self {
+            begin_string: message
+                .get_field_safe(8)
+                .ok()
+                .and_then(|f| f.as_str_safe().ok())
+                .and_then(|s| BeginString::try_from(s.to_string()).ok()),
+            msg_type: message
+                .get_field_safe(35)
+                .ok()
+                .and_then(|f| f.as_str_safe().ok())
+                .unwrap_or_default()
+                .to_string(),
+            ref_seq_num: message
+                .get_field_safe(34)
+                .ok()
+                .and_then(|f| f.as_i32_safe().ok()),
+        }
+    }
+
+    /// Reply rejecting the message; a BusinessMessageReject (35=j) from
+    /// FIX.4.2 onwards, a session Reject (35=3) for older versions.
+    #[must_use]
+    pub fn business_reject(&self) -> Message {
+        let text = format!("Unsupported Message Type: {}", self.msg_type);
+        let mut reject = match self.begin_string {
+            Some(BeginString::Fix40 | BeginString::Fix41) => Message::of_type("3"),
+            _ => {
+                let mut reject = Message::of_type("j");
+                reject.set_field(Field::String(372, self.msg_type.clone()));
+                // BusinessRejectReason: Unsupported Message Type
+                reject.set_field(Field::Int(380, 3));
+                reject
+            }
+        };
+        if let Some(ref_seq_num) = self.ref_seq_num {
+            reject.set_field(Field::Int(45, ref_seq_num));
+        }
+        reject.set_field(Field::String(58, text));
+        reject
+    }
+}
+
+impl fmt::Display for UnsupportedMessageType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unsupported MsgType: {}", self.msg_type)
+    }
+}
+
 // impl TryFrom<&FieldSet> for MsgTypeField {
 //     type Error = ();
 //
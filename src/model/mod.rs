@@ -2,15 +2,20 @@ pub mod field;
 pub mod generated;
 pub mod message;
 pub mod message_type;
+pub mod timestamp;
+pub mod view;
 
 #[cfg(test)]
 mod tests {
 
     use std::ops::AddAssign;
 
-    use crate::model::field::FieldSet;
+    use rust_decimal::Decimal;
+
+    use crate::model::field::{ConversionError, FieldKind, FieldSet};
     use crate::model::generated::fields::Field;
     use crate::model::message::Message;
+    use crate::model::BeginString;
 
     #[test]
     fn basic_fieldset_tests() {
@@ -30,6 +35,111 @@ mod tests {
         assert_eq!(1, cnt);
     }
 
+    #[test]
+    fn begin_string_round_trips() {
+        let all = [
+            BeginString::Fix40,
+            BeginString::Fix41,
+            BeginString::Fix42,
+            BeginString::Fix43,
+            BeginString::Fix44,
+            BeginString::Fix50,
+            BeginString::Fix50Sp1,
+            BeginString::Fix50Sp2,
+            BeginString::Fixt11,
+        ];
+        for begin_string in all {
+            let s: String = begin_string.into();
+            assert_eq!(begin_string, BeginString::try_from(s).unwrap());
+
+            if let Some(appl_ver_id) = begin_string.appl_ver_id() {
+                assert_eq!(
+                    Some(begin_string),
+                    BeginString::from_appl_ver_id(appl_ver_id)
+                );
+            }
+        }
+        assert_eq!("FIXT.1.1", String::from(BeginString::Fixt11));
+        assert_eq!("FIX.5.0SP2", String::from(BeginString::Fix50Sp2));
+        assert!(BeginString::Fixt11.is_transport_only());
+        assert_eq!(None, BeginString::from_appl_ver_id("1"));
+    }
+
+    #[test]
+    fn each_version_keeps_its_own_types() {
+        let side = |bs| Field::field_type(bs, 54).unwrap();
+        assert_eq!(FieldKind::String, side(BeginString::Fix40).kind);
+        assert_eq!(FieldKind::Char, side(BeginString::Fix44).kind);
+        assert_eq!("CHAR", side(BeginString::Fix40).fix_type);
+
+        // types without a Field variant of their own are held as strings
+        let price = Field::field_type(BeginString::Fix40, 44).unwrap();
+        assert_eq!(("FLOAT", FieldKind::String), (price.fix_type, price.kind));
+        let price = Field::field_type(BeginString::Fix44, 44).unwrap();
+        assert_eq!(("PRICE", FieldKind::Decimal), (price.fix_type, price.kind));
+        let sending_time = Field::field_type(BeginString::Fixt11, 52).unwrap();
+        assert_eq!("UTCTIMESTAMP", sending_time.fix_type);
+
+        assert_eq!(None, Field::field_type(BeginString::Fix44, 1128));
+        assert!(Field::field_type(BeginString::Fixt11, 1128).is_some());
+    }
+
+    #[test]
+    fn converting_fields_between_versions() {
+        let fix40_side = Field::String(54, "1".to_string());
+        let fix44_side = fix40_side.to_version(BeginString::Fix44).unwrap();
+        assert_eq!(Field::Char(54, '1'), fix44_side);
+        assert_eq!(
+            fix40_side,
+            fix44_side.to_version(BeginString::Fix40).unwrap()
+        );
+
+        let fix44_price = Field::Decimal(44, Decimal::new(10_500, 3));
+        let fix40_price = fix44_price.to_version(BeginString::Fix40).unwrap();
+        assert_eq!(Field::String(44, "10.500".to_string()), fix40_price);
+        assert_eq!(
+            fix44_price,
+            fix40_price.to_version(BeginString::Fix44).unwrap()
+        );
+
+        assert_eq!(
+            Field::Int(38, 100),
+            Field::Decimal(38, Decimal::new(100, 0))
+                .convert(FieldKind::Int)
+                .unwrap()
+        );
+        assert_eq!(
+            Err(ConversionError::Incompatible {
+                tag: 38,
+                value: "10.5".to_string(),
+                kind: FieldKind::Int
+            }),
+            Field::Decimal(38, Decimal::new(105, 1)).convert(FieldKind::Int)
+        );
+        assert_eq!(
+            Err(ConversionError::Incompatible {
+                tag: 54,
+                value: "12".to_string(),
+                kind: FieldKind::Char
+            }),
+            Field::String(54, "12".to_string()).to_version(BeginString::Fix42)
+        );
+        assert_eq!(
+            Err(ConversionError::NotDefined {
+                tag: 1128,
+                begin_string: BeginString::Fix44
+            }),
+            Field::String(1128, "9".to_string()).to_version(BeginString::Fix44)
+        );
+        assert_eq!(
+            "Field 1128 is not defined in FIX.4.4",
+            Field::String(1128, "9".to_string())
+                .to_version(BeginString::Fix44)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
     #[test]
     fn basic_logout_message_test() {
         let msg = Message::of_type("A");
@@ -41,7 +151,7 @@ mod tests {
     }
 }
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, Eq, PartialEq)]
 pub enum BeginString {
     Fix40,
     Fix41,
@@ -53,3 +163,44 @@ pub enum BeginString {
     Fix50Sp2,
     Fixt11,
 }
+
+impl BeginString {
+    /// `FIXT.1.1` only carries sessions; the application messages on it are
+    /// of the version given by `ApplVerID` (1128) or `DefaultApplVerID` (1137)
+    #[must_use]
+    pub const fn is_transport_only(self) -> bool {
+        matches!(self, BeginString::Fixt11)
+    }
+
+    /// Value identifying this version in `ApplVerID` (1128) and
+    /// `DefaultApplVerID` (1137)
+    #[must_use]
+    pub const fn appl_ver_id(self) -> Option<&'static str> {
+        match self {
+            BeginString::Fix40 => Some("2"),
+            BeginString::Fix41 => Some("3"),
+            BeginString::Fix42 => Some("4"),
+            BeginString::Fix43 => Some("5"),
+            BeginString::Fix44 => Some("6"),
+            BeginString::Fix50 => Some("7"),
+            BeginString::Fix50Sp1 => Some("8"),
+            BeginString::Fix50Sp2 => Some("9"),
+            BeginString::Fixt11 => None,
+        }
+    }
+
+    #[must_use]
+    pub fn from_appl_ver_id(appl_ver_id: &str) -> Option<Self> {
+        match appl_ver_id {
+            "2" => Some(BeginString::Fix40),
+            "3" => Some(BeginString::Fix41),
+            "4" => Some(BeginString::Fix42),
+            "5" => Some(BeginString::Fix43),
+            "6" => Some(BeginString::Fix44),
+            "7" => Some(BeginString::Fix50),
+            "8" => Some(BeginString::Fix50Sp1),
+            "9" => Some(BeginString::Fix50Sp2),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Format `time` as a `UTCTIMESTAMP`, `YYYYMMDD-HH:MM:SS.sss`
+#[must_use]
+pub fn format_utc_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days(i64::try_from(secs / 86_400).unwrap_or(i64::MAX));
+    let secs_of_day = secs % 86_400;
+    format!(
+        "{:04}{:02}{:02}-{:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Parse a `UTCTIMESTAMP`, `YYYYMMDD-HH:MM:SS` with up to nine digits of
+/// fractional seconds
+#[must_use]
+pub fn parse_utc_timestamp(value: &str) -> Option<SystemTime> {
+    let (date, time) = value.split_once('-')?;
+    if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let year: i64 = date[0..4].parse().ok()?;
+    let month: u32 = date[4..6].parse().ok()?;
+    let day: u32 = date[6..8].parse().ok()?;
+
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+    let mut parts = time.split(':');
+    let mut part = |max: u64| {
+        let part = parts.next()?;
+        let value: u64 = (part.len() == 2).then(|| part.parse().ok())??;
+        (value <= max).then_some(value)
+    };
+    // 60 seconds for leap seconds
+    let (hour, minute, second) = (part(23)?, part(59)?, part(60)?);
+    if parts.next().is_some() || !(1..=12).contains(&month) || day == 0 || day > 31 {
+        return None;
+    }
+    let nanos = match fraction {
+        Some(fraction) if (1..=9).contains(&fraction.len()) => {
+            let digits: u32 = fraction.parse().ok()?;
+            digits * 10_u32.pow(9 - u32::try_from(fraction.len()).ok()?)
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = u32::try_from(day_of_year - (153 * month_index + 2) / 5 + 1).unwrap_or(1);
+    let month = u32::try_from(if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    })
+    .unwrap_or(1);
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::model::timestamp::{format_utc_timestamp, parse_utc_timestamp};
+
+    #[test]
+    fn utc_timestamps_round_trip() {
+        // 2024-02-29 13:45:30.250 UTC
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_214_330_250);
+        assert_eq!("20240229-13:45:30.250", format_utc_timestamp(time));
+        assert_eq!(Some(time), parse_utc_timestamp("20240229-13:45:30.250"));
+        assert_eq!(
+            Some(time - Duration::from_millis(250)),
+            parse_utc_timestamp("20240229-13:45:30")
+        );
+        assert_eq!(
+            Some(UNIX_EPOCH),
+            parse_utc_timestamp("19700101-00:00:00.000000")
+        );
+
+        for invalid in [
+            "20240229",
+            "20241301-00:00:00",
+            "20240229-24:00:00",
+            "20240229-1:00:00",
+        ] {
+            assert_eq!(None, parse_utc_timestamp(invalid), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use std::cell::OnceCell;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::cracker;
+use crate::model::field::{FieldSet, ParseError, ValueType};
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+use crate::model::BeginString;
+
+const SOH: u8 = b'\x01';
+
+/// Read-only view of a received message which borrows the received bytes.
+///
+/// Nothing is copied: tag offsets are indexed on first access and values are
+/// only decoded when asked for, so a consumer reading a handful of fields
+/// from each market data message doesn't pay for the rest.
+#[derive(Debug)]
+pub struct MessageView<'a> {
+    bytes: &'a [u8],
+    begin_string: BeginString,
+    index: OnceCell<Vec<FieldRef>>,
+}
+
+/// Location of a single `tag=value` field within the message
+#[derive(Debug, Clone, Copy)]
+struct FieldRef {
+    /// `None` if the tag isn't a number or the field has no `=`
+    tag: Option<u32>,
+    start: usize,
+    value_start: usize,
+    end: usize,
+}
+
+impl<'a> MessageView<'a> {
+    /// View `bytes`, which must start with `BeginString` (8). The rest of the
+    /// message isn't looked at until a field is asked for.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the message does not start with a known `BeginString`
+    pub fn new(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let begin_string = cracker::scan_header(bytes)?.begin_string;
+
+        Ok(Self {
+            bytes,
+            begin_string,
+            index: OnceCell::new(),
+        })
+    }
+
+    #[must_use]
+    pub const fn begin_string(&self) -> BeginString {
+        self.begin_string
+    }
+
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// `MsgType` (35), if present and valid UTF-8
+    #[must_use]
+    pub fn msg_type(&self) -> Option<&'a str> {
+        self.get(35).and_then(|f| f.as_str().ok())
+    }
+
+    /// First occurrence of `tag`
+    #[must_use]
+    pub fn get(&self, tag: u32) -> Option<FieldView<'a>> {
+        self.index()
+            .iter()
+            .find(|f| f.tag == Some(tag))
+            .map(|f| self.view(f, tag))
+    }
+
+    #[must_use]
+    pub fn contains(&self, tag: u32) -> bool {
+        self.index().iter().any(|f| f.tag == Some(tag))
+    }
+
+    /// Number of fields, including malformed ones
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.index().len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.index().is_empty()
+    }
+
+    /// Fields in the order they were received; malformed fields are
+    /// returned as errors in place.
+    pub fn iter(&self) -> impl Iterator<Item = Result<FieldView<'a>, ParseError>> + '_ {
+        self.index().iter().map(|f| match f.tag {
+            Some(tag) => Ok(self.view(f, tag)),
+            None => Err(self.malformed(f)),
+        })
+    }
+
+    /// Decode every field into an owned `Message`, as `cracker::crack_message` does.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` for the first field that can't be parsed, or if
+    /// the application version of a FIXT.1.1 message can't be determined
+    pub fn to_message(&self) -> Result<Message, ParseError> {
+        self.to_message_with(None)
+    }
+
+    /// Decode every field into an owned `Message`, as
+    /// `cracker::crack_message_with` does: body fields of a FIXT.1.1 message
+    /// are typed as its `ApplVerID` (1128) says, or as `default_appl_ver_id`
+    /// without one.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` for the first field that can't be parsed, or if
+    /// the application version of a FIXT.1.1 message can't be determined
+    pub fn to_message_with(
+        &self,
+        default_appl_ver_id: Option<BeginString>,
+    ) -> Result<Message, ParseError> {
+        let header = cracker::scan_header(self.bytes)?;
+        let application = cracker::application_version(self.bytes, &header, default_appl_ver_id)?;
+        let fields = self
+            .iter()
+            .map(|f| {
+                f.and_then(|f| {
+                    f.to_field(cracker::field_version(
+                        Some(f.tag()),
+                        self.begin_string,
+                        application,
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(FieldSet::with(fields).into())
+    }
+
+    fn index(&self) -> &[FieldRef] {
+        self.index.get_or_init(|| {
+            let mut index = vec![];
+            let mut start = 0;
+            for raw in self.bytes.split(|b| *b == SOH) {
+                let end = start + raw.len();
+                if !raw.is_empty() {
+                    let eq = raw.iter().position(|b| *b == b'=');
+                    let tag = eq.and_then(|eq| parse_tag(&raw[..eq]));
+                    index.push(FieldRef {
+                        tag,
+                        start,
+                        value_start: eq.map_or(end, |eq| start + eq + 1),
+                        end,
+                    });
+                }
+                start = end + 1;
+            }
+            index
+        })
+    }
+
+    fn view(&self, f: &FieldRef, tag: u32) -> FieldView<'a> {
+        FieldView {
+            tag,
+            raw: &self.bytes[f.start..f.end],
+            value: &self.bytes[f.value_start..f.end],
+            offset: f.value_start,
+        }
+    }
+
+    fn malformed(&self, f: &FieldRef) -> ParseError {
+        let raw = String::from_utf8_lossy(&self.bytes[f.start..f.end]);
+        match raw.split_once('=') {
+            Some((tag, _)) => ParseError::new(None, tag, ValueType::Tag, f.start),
+            None => ParseError::new(None, &raw, ValueType::Field, f.start),
+        }
+    }
+}
+
+/// Tag numbers are plain ASCII digits; anything else isn't a tag
+fn parse_tag(digits: &[u8]) -> Option<u32> {
+    if digits.is_empty() {
+        return None;
+    }
+    digits.iter().try_fold(0_u32, |tag, b| {
+        if b.is_ascii_digit() {
+            tag.checked_mul(10)?.checked_add(u32::from(b - b'0'))
+        } else {
+            None
+        }
+    })
+}
+
+/// A single field of a `MessageView`, still in its wire form
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FieldView<'a> {
+    tag: u32,
+    raw: &'a [u8],
+    value: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> FieldView<'a> {
+    #[must_use]
+    pub const fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// Value bytes, without the tag or delimiter
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.value
+    }
+
+    /// Byte offset of the value from the start of the message
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is not valid UTF-8
+    pub fn as_str(&self) -> Result<&'a str, ParseError> {
+        std::str::from_utf8(self.value).map_err(|_| self.error(ValueType::String))
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is not an integer
+    pub fn as_i32(&self) -> Result<i32, ParseError> {
+        self.parse(ValueType::Int)
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is not a single character
+    pub fn as_char(&self) -> Result<char, ParseError> {
+        self.parse(ValueType::Char)
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is not a decimal
+    pub fn as_decimal(&self) -> Result<Decimal, ParseError> {
+        self.as_str()
+            .ok()
+            .and_then(|s| Decimal::from_str(s).ok())
+            .ok_or_else(|| self.error(ValueType::Decimal))
+    }
+
+    /// Decode into an owned `Field`, typed as `begin_string` defines the tag
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value doesn't parse as the tag's type
+    pub fn to_field(&self, begin_string: BeginString) -> Result<Field, ParseError> {
+        let start = self.offset - (self.raw.len() - self.value.len());
+        Field::crack(begin_string, &String::from_utf8_lossy(self.raw))
+            .map_err(|e| e.offset_by(start))
+    }
+
+    fn parse<T: FromStr>(&self, expected: ValueType) -> Result<T, ParseError> {
+        self.as_str()
+            .ok()
+            .and_then(|s| s.parse::<T>().ok())
+            .ok_or_else(|| self.error(expected))
+    }
+
+    fn error(&self, expected: ValueType) -> ParseError {
+        ParseError::new(
+            Some(self.tag),
+            &String::from_utf8_lossy(self.value),
+            expected,
+            self.offset,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use rust_decimal::Decimal;
+
+    use crate::cracker::{crack_message, crack_message_with};
+    use crate::model::field::{ParseError, ValueType};
+    use crate::model::generated::fields::Field;
+    use crate::model::view::MessageView;
+    use crate::model::BeginString;
+
+    #[test]
+    fn reads_fields_without_copying() {
+        let received = Bytes::from(
+            "8=FIX.4.4\x0135=W\x0155=ABC\x01268=2\x01270=10.25\x01269=0\x01270=10.5\x01269=1\x01",
+        );
+        let view = MessageView::new(&received).unwrap();
+
+        assert_eq!(BeginString::Fix44, view.begin_string());
+        assert_eq!(Some("W"), view.msg_type());
+        assert_eq!(8, view.len());
+
+        let symbol = view.get(55).unwrap();
+        assert_eq!("ABC", symbol.as_str().unwrap());
+        assert_eq!(received[18..21].as_ptr(), symbol.as_bytes().as_ptr());
+        assert_eq!(18, symbol.offset());
+
+        assert_eq!(2, view.get(268).unwrap().as_i32().unwrap());
+        assert_eq!(
+            Decimal::new(1025, 2),
+            view.get(270).unwrap().as_decimal().unwrap()
+        );
+        assert_eq!('0', view.get(269).unwrap().as_char().unwrap());
+        assert!(view.get(58).is_none());
+
+        // repeating group entries are all there in wire order
+        let prices: Vec<Decimal> = view
+            .iter()
+            .filter_map(Result::ok)
+            .filter(|f| f.tag() == 270)
+            .map(|f| f.as_decimal().unwrap())
+            .collect();
+        assert_eq!(vec![Decimal::new(1025, 2), Decimal::new(105, 1)], prices);
+    }
+
+    #[test]
+    fn converts_to_the_same_message_as_the_cracker() {
+        let received = Bytes::from(
+            "8=FIX.4.2\x0135=D\x0111=order-1\x0138=100\x0144=10.5\x0154=1\x0170000=custom\x01",
+        );
+        let view = MessageView::new(&received).unwrap();
+
+        assert_eq!(
+            crack_message(&received).unwrap().to_string(),
+            view.to_message().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn fixt_body_is_typed_as_the_application_version() {
+        let with_appl_ver_id =
+            Bytes::from("8=FIXT.1.1\x0135=D\x011128=9\x0111=order-1\x0138=100\x0154=1\x01");
+        let view = MessageView::new(&with_appl_ver_id).unwrap();
+        let message = view.to_message().unwrap();
+        assert_eq!(
+            crack_message(&with_appl_ver_id).unwrap().to_string(),
+            message.to_string()
+        );
+        // Side (54) is a FIX.5.0SP2 char, unknown to FIXT.1.1
+        assert_eq!(Field::Char(54, '1'), *message.get_field(54));
+
+        // the session's default without an ApplVerID
+        let without = Bytes::from("8=FIXT.1.1\x0135=D\x0111=order-1\x0138=100\x0154=1\x01");
+        let view = MessageView::new(&without).unwrap();
+        assert_eq!(
+            ParseError::new(Some(1128), "", ValueType::ApplVerID, 0),
+            view.to_message().unwrap_err()
+        );
+        assert_eq!(
+            crack_message_with(&without, Some(BeginString::Fix50Sp2))
+                .unwrap()
+                .to_string(),
+            view.to_message_with(Some(BeginString::Fix50Sp2))
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn malformed_values_are_errors_on_access() {
+        let received = Bytes::from("8=FIX.4.2\x0135=D\x0144=1x\x01bad=1\x0158=ok\x01");
+        let view = MessageView::new(&received).unwrap();
+
+        // the bad fields don't get in the way of the good ones
+        assert_eq!("ok", view.get(58).unwrap().as_str().unwrap());
+        assert_eq!(
+            ParseError::new(Some(44), "1x", ValueType::Decimal, 18),
+            view.get(44).unwrap().as_decimal().unwrap_err()
+        );
+        assert_eq!(
+            ParseError::new(Some(44), "1x", ValueType::Decimal, 18),
+            view.to_message().unwrap_err()
+        );
+        assert_eq!(
+            vec![ParseError::new(None, "bad", ValueType::Tag, 21)],
+            view.iter().filter_map(Result::err).collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            ParseError::new(None, "35=D", ValueType::BeginString, 0),
+            MessageView::new(b"35=D\x01").unwrap_err()
+        );
+        assert_eq!(
+            ParseError::new(Some(8), "FIX.9.9", ValueType::BeginString, 2),
+            MessageView::new(b"8=FIX.9.9\x01").unwrap_err()
+        );
+    }
+}
@@ -0,0 +1,768 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+use bytes::{BufMut, BytesMut};
+use rust_decimal::Decimal;
+
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+
+/// blockLength, templateId, schemaId and version, each a `uint16`
+const HEADER_LENGTH: usize = 8;
+
+/// Layout of the messages of an SBE schema, generated from `spec/sbe/*.xml`
+/// into `model::generated::sbe_*`, e.g. `sbe_orders::SCHEMA`.
+///
+/// Messages are encoded from and decoded into the same `Message` used for
+/// tag=value, matched up by the `semanticType` (`MsgType`) of each template
+/// and the `id` (tag) of each field. Repeating groups are not supported yet.
+#[derive(Debug, Clone, Copy)]
+pub struct Schema {
+    pub package: &'static str,
+    pub id: u16,
+    pub version: u16,
+    pub byte_order: ByteOrder,
+    pub messages: &'static [MessageLayout],
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MessageLayout {
+    pub name: &'static str,
+    pub template_id: u16,
+    /// `MsgType` (35), from the template's `semanticType`
+    pub msg_type: &'static str,
+    pub block_length: u16,
+    pub fields: &'static [FieldLayout],
+    pub data: &'static [DataLayout],
+}
+
+/// Field at a fixed offset within the message's block
+#[derive(Debug, Clone, Copy)]
+pub struct FieldLayout {
+    pub tag: u32,
+    pub name: &'static str,
+    pub offset: usize,
+    pub encoding: Encoding,
+    pub presence: Presence,
+}
+
+/// Variable length field following the block, prefixed by its length
+#[derive(Debug, Clone, Copy)]
+pub struct DataLayout {
+    pub tag: u32,
+    pub name: &'static str,
+    pub length: Primitive,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Encoding {
+    Char,
+    /// Fixed length, padded with NULs
+    CharArray(usize),
+    Int(Primitive),
+    /// Mantissa and either a constant exponent or an `int8` exponent
+    /// following it
+    Decimal(Primitive, Option<i8>),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Primitive {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Presence {
+    Required,
+    /// Absent fields are sent as the type's null value
+    Optional,
+    /// Not sent; always the given value
+    Constant(&'static str),
+}
+
+impl Schema {
+    #[must_use]
+    pub fn message(&self, template_id: u16) -> Option<&MessageLayout> {
+        self.messages.iter().find(|m| m.template_id == template_id)
+    }
+
+    #[must_use]
+    pub fn message_for(&self, msg_type: &str) -> Option<&MessageLayout> {
+        self.messages.iter().find(|m| m.msg_type == msg_type)
+    }
+
+    /// Append `message`, with its message header, to `buf`.
+    ///
+    /// Header and trailer fields the template has no place for, such as
+    /// `SenderCompID` (49), are left to the session layer; body fields it has
+    /// no place for are an error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the schema has no template for the message, a
+    /// required field is missing or a value doesn't fit its encoding
+    pub fn encode(&self, message: &Message, buf: &mut BytesMut) -> Result<(), SbeError> {
+        let msg_type = message
+            .get_field_safe(35)
+            .map_err(|_| SbeError::MissingMsgType)?
+            .as_str();
+        let layout = self
+            .message_for(msg_type)
+            .ok_or_else(|| SbeError::UnknownMsgType(msg_type.to_string()))?;
+
+        let unplaced = message.body().iter().find(|f| {
+            !layout.fields.iter().any(|l| l.tag == f.tag())
+                && !layout.data.iter().any(|l| l.tag == f.tag())
+        });
+        if let Some(field) = unplaced {
+            return Err(SbeError::NoPlace {
+                tag: field.tag(),
+                msg_type: msg_type.to_string(),
+            });
+        }
+
+        let start = buf.len();
+        let block_length = usize::from(layout.block_length);
+        buf.resize(start + HEADER_LENGTH + block_length, 0);
+        let header = [
+            layout.block_length,
+            layout.template_id,
+            self.id,
+            self.version,
+        ];
+        for (i, value) in header.into_iter().enumerate() {
+            Primitive::UInt16.write(
+                i128::from(value),
+                &mut buf[start + i * 2..],
+                self.byte_order,
+            );
+        }
+
+        let block = &mut buf[start + HEADER_LENGTH..];
+        for layout in layout.fields {
+            let field = message.get_field_safe(layout.tag).ok();
+            self.encode_field(layout, field, block)?;
+        }
+
+        for layout in layout.data {
+            let value = message
+                .get_field_safe(layout.tag)
+                .map(Field::value_string)
+                .unwrap_or_default();
+            let length = i128::try_from(value.len())
+                .ok()
+                .filter(|l| layout.length.holds(*l))
+                .ok_or_else(|| invalid(layout.tag, &value))?;
+            let at = buf.len();
+            buf.resize(at + layout.length.size(), 0);
+            layout.length.write(length, &mut buf[at..], self.byte_order);
+            buf.put_slice(value.as_bytes());
+        }
+        Ok(())
+    }
+
+    /// Decode a single message, starting with its message header.
+    ///
+    /// Fields which are null, or beyond the end of a block sent by an older
+    /// version of the schema, are left out of the message.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `bytes` is from another schema or template, or is
+    /// too short for the message it holds
+    pub fn decode(&self, bytes: &[u8]) -> Result<Message, SbeError> {
+        let header = read_slice(bytes, 0, HEADER_LENGTH)?;
+        let [block_length, template_id, schema_id, _version] = [0, 2, 4, 6].map(|at| {
+            u16::try_from(Primitive::UInt16.read(&header[at..], self.byte_order)).unwrap_or(0)
+        });
+        if schema_id != self.id {
+            return Err(SbeError::WrongSchema(schema_id));
+        }
+        let layout = self
+            .message(template_id)
+            .ok_or(SbeError::UnknownTemplate(template_id))?;
+        let block_length = usize::from(block_length);
+        let block = read_slice(bytes, HEADER_LENGTH, block_length)?;
+
+        let mut message = Message::of_type(layout.msg_type);
+        for layout in layout.fields {
+            if let Some(field) = self.decode_field(layout, block)? {
+                message.set_field(field);
+            }
+        }
+
+        let mut at = HEADER_LENGTH + block_length;
+        for layout in layout.data {
+            let size = layout.length.size();
+            let length = layout
+                .length
+                .read(read_slice(bytes, at, size)?, self.byte_order);
+            let length =
+                usize::try_from(length).map_err(|_| invalid(layout.tag, &length.to_string()))?;
+            let value = read_slice(bytes, at + size, length)?;
+            if !value.is_empty() {
+                let value = String::from_utf8_lossy(value).into_owned();
+                message.set_field(Field::String(layout.tag, value));
+            }
+            at += size + length;
+        }
+        Ok(message)
+    }
+
+    fn encode_field(
+        &self,
+        layout: &FieldLayout,
+        field: Option<&Field>,
+        block: &mut [u8],
+    ) -> Result<(), SbeError> {
+        let tag = layout.tag;
+        let at = &mut block[layout.offset..];
+        let field = match (field, layout.presence) {
+            (_, Presence::Constant(_)) => return Ok(()),
+            (Some(field), _) => field,
+            (None, Presence::Required) => return Err(SbeError::MissingField(tag)),
+            // char arrays and chars are already all NULs
+            (None, Presence::Optional) => {
+                match layout.encoding {
+                    Encoding::Int(primitive) | Encoding::Decimal(primitive, _) => {
+                        primitive.write(primitive.null(), at, self.byte_order);
+                    }
+                    Encoding::Char | Encoding::CharArray(_) => {}
+                }
+                return Ok(());
+            }
+        };
+
+        let value = field.value_string();
+        match layout.encoding {
+            Encoding::Char => match value.as_bytes() {
+                [c] => at[0] = *c,
+                _ => return Err(invalid(tag, &value)),
+            },
+            Encoding::CharArray(length) => {
+                if value.len() > length {
+                    return Err(invalid(tag, &value));
+                }
+                at[..value.len()].copy_from_slice(value.as_bytes());
+            }
+            Encoding::Int(primitive) => {
+                let n = value
+                    .parse::<i128>()
+                    .ok()
+                    .filter(|n| primitive.holds(*n))
+                    .ok_or_else(|| invalid(tag, &value))?;
+                primitive.write(n, at, self.byte_order);
+            }
+            Encoding::Decimal(primitive, exponent) => {
+                let decimal = match field {
+                    Field::Decimal(_, d) => *d,
+                    _ => Decimal::from_str(&value).map_err(|_| invalid(tag, &value))?,
+                };
+                let (mantissa, exponent) =
+                    to_mantissa(decimal, exponent).ok_or_else(|| invalid(tag, &value))?;
+                if !primitive.holds(mantissa) {
+                    return Err(invalid(tag, &value));
+                }
+                primitive.write(mantissa, at, self.byte_order);
+                if layout.encoding == Encoding::Decimal(primitive, None) {
+                    Primitive::Int8.write(
+                        i128::from(exponent),
+                        &mut at[primitive.size()..],
+                        self.byte_order,
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_field(&self, layout: &FieldLayout, block: &[u8]) -> Result<Option<Field>, SbeError> {
+        let tag = layout.tag;
+        if let Presence::Constant(value) = layout.presence {
+            return Ok(Some(match (layout.encoding, value.chars().next()) {
+                (Encoding::Char, Some(c)) => Field::Char(tag, c),
+                _ => Field::String(tag, value.to_string()),
+            }));
+        }
+        // sent by an older version of the schema, with a shorter block
+        let Some(at) = block.get(layout.offset..) else {
+            return Ok(None);
+        };
+        if at.len() < layout.encoding.size() {
+            return Ok(None);
+        }
+
+        let optional = layout.presence == Presence::Optional;
+        let field = match layout.encoding {
+            Encoding::Char => (at[0] != 0).then(|| Field::Char(tag, char::from(at[0]))),
+            Encoding::CharArray(length) => {
+                let value = &at[..length];
+                let end = value.iter().position(|b| *b == 0).unwrap_or(length);
+                (end > 0).then(|| {
+                    Field::String(tag, String::from_utf8_lossy(&value[..end]).into_owned())
+                })
+            }
+            Encoding::Int(primitive) => {
+                let n = primitive.read(at, self.byte_order);
+                (!optional || n != primitive.null()).then(|| match i32::try_from(n) {
+                    Ok(n) => Field::Int(tag, n),
+                    Err(_) => Field::String(tag, n.to_string()),
+                })
+            }
+            Encoding::Decimal(primitive, exponent) => {
+                let mantissa = primitive.read(at, self.byte_order);
+                if optional && mantissa == primitive.null() {
+                    None
+                } else {
+                    let exponent = exponent.unwrap_or_else(|| {
+                        i8::try_from(Primitive::Int8.read(&at[primitive.size()..], self.byte_order))
+                            .unwrap_or_default()
+                    });
+                    let decimal = from_mantissa(mantissa, exponent)
+                        .ok_or_else(|| invalid(tag, &format!("{}e{}", mantissa, exponent)))?;
+                    Some(Field::Decimal(tag, decimal))
+                }
+            }
+        };
+        Ok(field)
+    }
+}
+
+impl Encoding {
+    /// Bytes taken up in the block
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        match self {
+            Encoding::Char => 1,
+            Encoding::CharArray(length) => *length,
+            Encoding::Int(primitive) | Encoding::Decimal(primitive, Some(_)) => primitive.size(),
+            Encoding::Decimal(primitive, None) => primitive.size() + 1,
+        }
+    }
+}
+
+impl Primitive {
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        match self {
+            Primitive::Int8 | Primitive::UInt8 => 1,
+            Primitive::Int16 | Primitive::UInt16 => 2,
+            Primitive::Int32 | Primitive::UInt32 => 4,
+            Primitive::Int64 | Primitive::UInt64 => 8,
+        }
+    }
+
+    const fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            Primitive::Int8 | Primitive::Int16 | Primitive::Int32 | Primitive::Int64
+        )
+    }
+
+    const fn min(&self) -> i128 {
+        if self.is_signed() {
+            -(1 << (self.size() * 8 - 1))
+        } else {
+            0
+        }
+    }
+
+    const fn max(&self) -> i128 {
+        if self.is_signed() {
+            (1 << (self.size() * 8 - 1)) - 1
+        } else {
+            (1 << (self.size() * 8)) - 1
+        }
+    }
+
+    /// SBE's null value: the minimum of signed types, the maximum of unsigned
+    const fn null(&self) -> i128 {
+        if self.is_signed() {
+            self.min()
+        } else {
+            self.max()
+        }
+    }
+
+    const fn holds(&self, n: i128) -> bool {
+        self.min() <= n && n <= self.max()
+    }
+
+    fn read(self, bytes: &[u8], byte_order: ByteOrder) -> i128 {
+        let bytes = &bytes[..self.size()];
+        let unsigned = match byte_order {
+            ByteOrder::LittleEndian => bytes
+                .iter()
+                .rev()
+                .fold(0_u64, |n, b| n << 8 | u64::from(*b)),
+            ByteOrder::BigEndian => bytes.iter().fold(0_u64, |n, b| n << 8 | u64::from(*b)),
+        };
+        let n = i128::from(unsigned);
+        if self.is_signed() && n > self.max() {
+            n - (1 << (self.size() * 8))
+        } else {
+            n
+        }
+    }
+
+    fn write(self, n: i128, bytes: &mut [u8], byte_order: ByteOrder) {
+        let size = self.size();
+        // two's complement of negative values, truncated to the size
+        let unsigned = n.rem_euclid(1 << (size * 8)) as u64;
+        for (i, b) in bytes[..size].iter_mut().enumerate() {
+            let shift = match byte_order {
+                ByteOrder::LittleEndian => i,
+                ByteOrder::BigEndian => size - 1 - i,
+            };
+            *b = (unsigned >> (shift * 8)) as u8;
+        }
+    }
+}
+
+/// Mantissa of `decimal` for `exponent`, or with the exponent that fits it
+/// exactly if the encoding carries one
+fn to_mantissa(decimal: Decimal, exponent: Option<i8>) -> Option<(i128, i8)> {
+    let decimal = decimal.normalize();
+    let scale = i32::try_from(decimal.scale()).ok()?;
+    let exponent = exponent.map_or(-scale, i32::from);
+    // decimal is mantissa * 10^-scale
+    let shift = -scale - exponent;
+    let mantissa = if shift >= 0 {
+        decimal
+            .mantissa()
+            .checked_mul(10_i128.checked_pow(u32::try_from(shift).ok()?)?)?
+    } else {
+        let divisor = 10_i128.checked_pow(u32::try_from(-shift).ok()?)?;
+        // would lose precision
+        if decimal.mantissa() % divisor != 0 {
+            return None;
+        }
+        decimal.mantissa() / divisor
+    };
+    Some((mantissa, i8::try_from(exponent).ok()?))
+}
+
+fn from_mantissa(mantissa: i128, exponent: i8) -> Option<Decimal> {
+    if exponent <= 0 {
+        Decimal::try_from_i128_with_scale(mantissa, u32::from(exponent.unsigned_abs())).ok()
+    } else {
+        let mantissa =
+            mantissa.checked_mul(10_i128.checked_pow(u32::from(exponent.unsigned_abs()))?)?;
+        Decimal::try_from_i128_with_scale(mantissa, 0).ok()
+    }
+}
+
+fn read_slice(bytes: &[u8], at: usize, length: usize) -> Result<&[u8], SbeError> {
+    at.checked_add(length)
+        .and_then(|end| bytes.get(at..end))
+        .ok_or(SbeError::Truncated {
+            needed: at.saturating_add(length),
+            available: bytes.len(),
+        })
+}
+
+fn invalid(tag: u32, value: &str) -> SbeError {
+    SbeError::InvalidValue {
+        tag,
+        value: value.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SbeError {
+    MissingMsgType,
+    UnknownMsgType(String),
+    /// The template for `msg_type` has no field for `tag`
+    NoPlace {
+        tag: u32,
+        msg_type: String,
+    },
+    MissingField(u32),
+    /// The value doesn't fit the field's encoding
+    InvalidValue {
+        tag: u32,
+        value: String,
+    },
+    Truncated {
+        needed: usize,
+        available: usize,
+    },
+    WrongSchema(u16),
+    UnknownTemplate(u16),
+}
+
+impl fmt::Display for SbeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SbeError::MissingMsgType => write!(f, "MsgType (35) is missing"),
+            SbeError::UnknownMsgType(v) => {
+                write!(f, "schema has no template for MsgType (35) {:?}", v)
+            }
+            SbeError::NoPlace { tag, msg_type } => write!(
+                f,
+                "template for MsgType (35) {:?} has no field for tag {}",
+                msg_type, tag
+            ),
+            SbeError::MissingField(tag) => write!(f, "required tag {} is missing", tag),
+            SbeError::InvalidValue { tag, value } => {
+                write!(
+                    f,
+                    "value {:?} of tag {} doesn't fit its encoding",
+                    value, tag
+                )
+            }
+            SbeError::Truncated { needed, available } => write!(
+                f,
+                "message is truncated, needed {} bytes but only {} are available",
+                needed, available
+            ),
+            SbeError::WrongSchema(id) => write!(f, "message is from schema {}", id),
+            SbeError::UnknownTemplate(id) => write!(f, "schema has no template {}", id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use rust_decimal::Decimal;
+
+    use crate::model::generated::fields::Field;
+    use crate::model::generated::sbe_orders::SCHEMA;
+    use crate::model::message::Message;
+    use crate::sbe::{
+        ByteOrder, Encoding, FieldLayout, MessageLayout, Presence, Primitive, SbeError, Schema,
+    };
+
+    fn order() -> Message {
+        let mut order = Message::of_type("D");
+        order.set_field(Field::String(8, "FIX.4.4".to_string()));
+        order.set_field(Field::String(49, "OMS".to_string()));
+        order.set_field(Field::String(34, "7".to_string()));
+        order.set_field(Field::String(11, "order-1".to_string()));
+        order.set_field(Field::String(55, "IBM".to_string()));
+        order.set_field(Field::Char(54, '1'));
+        order.set_field(Field::Decimal(38, Decimal::new(100, 0)));
+        order.set_field(Field::Char(40, '2'));
+        order.set_field(Field::Decimal(44, Decimal::new(10_250, 3)));
+        order
+    }
+
+    #[test]
+    fn encodes_a_template() {
+        let mut buf = BytesMut::new();
+        SCHEMA.encode(&order(), &mut buf).unwrap();
+
+        // blockLength 46, templateId 1, schemaId 91, version 0
+        assert_eq!([46, 0, 1, 0, 91, 0, 0, 0], buf[..8]);
+        let block = &buf[8..54];
+        assert_eq!([7, 0, 0, 0], block[..4]);
+        assert_eq!(b"order-1\0", &block[4..12]);
+        assert_eq!(b"IBM\0\0\0\0\0", &block[24..32]);
+        assert_eq!(b'1', block[32]);
+        assert_eq!(100_i32.to_le_bytes(), block[33..37]);
+        assert_eq!(b'2', block[37]);
+        // 10.250 with the schema's constant exponent of -4
+        assert_eq!(102_500_i64.to_le_bytes(), block[38..46]);
+        // no Text (58)
+        assert_eq!([0, 0], buf[54..]);
+
+        let decoded = SCHEMA.decode(&buf).unwrap();
+        assert_eq!(
+            "34=7|35=D|11=order-1|38=100|40=2|44=10.2500|54=1|55=IBM|",
+            decoded.to_string()
+        );
+        assert_eq!(Field::Int(34, 7), *decoded.get_field(34));
+        assert_eq!(
+            Field::Decimal(44, Decimal::new(102_500, 4)),
+            *decoded.get_field(44)
+        );
+    }
+
+    #[test]
+    fn optional_fields_and_data() {
+        let mut report = Message::of_type("8");
+        report.set_field(Field::Int(34, 8));
+        for (tag, value) in [(37, "1"), (11, "order-1"), (17, "exec-1"), (55, "IBM")] {
+            report.set_field(Field::String(tag, value.to_string()));
+        }
+        report.set_field(Field::Char(150, '0'));
+        report.set_field(Field::Char(39, '0'));
+        report.set_field(Field::Char(54, '2'));
+        report.set_field(Field::Decimal(151, Decimal::new(100, 0)));
+        report.set_field(Field::Decimal(14, Decimal::ZERO));
+        report.set_field(Field::String(58, "accepted, ünïcode".to_string()));
+
+        let mut buf = BytesMut::new();
+        SCHEMA.encode(&report, &mut buf).unwrap();
+        let decoded = SCHEMA.decode(&buf).unwrap();
+
+        // LastPx (31) and LastQty (32) are null
+        assert!(decoded.get_field_safe(31).is_err());
+        assert!(decoded.get_field_safe(32).is_err());
+        assert_eq!("accepted, ünïcode", decoded.get_field(58).as_str());
+        assert_eq!(report.to_string(), decoded.to_string());
+    }
+
+    #[test]
+    fn values_which_do_not_fit() {
+        let mut buf = BytesMut::new();
+        let mut order = order();
+        order.set_field(Field::Decimal(44, Decimal::new(123_456, 5)));
+        assert_eq!(
+            Err(SbeError::InvalidValue {
+                tag: 44,
+                value: "1.23456".to_string()
+            }),
+            SCHEMA.encode(&order, &mut buf)
+        );
+
+        let mut order = self::order();
+        order.set_field(Field::String(55, "TOO LONG SYMBOL".to_string()));
+        assert!(matches!(
+            SCHEMA.encode(&order, &mut buf),
+            Err(SbeError::InvalidValue { tag: 55, .. })
+        ));
+
+        let mut order = self::order();
+        order.set_field(Field::Int(34, -1));
+        assert!(matches!(
+            SCHEMA.encode(&order, &mut buf),
+            Err(SbeError::InvalidValue { tag: 34, .. })
+        ));
+
+        let mut order = self::order();
+        order.set_field(Field::String(20_001, "venue".to_string()));
+        assert!(matches!(
+            SCHEMA.encode(&order, &mut buf),
+            Err(SbeError::NoPlace { tag: 20_001, .. })
+        ));
+
+        let mut cancel = Message::of_type("F");
+        cancel.set_field(Field::Int(34, 1));
+        assert_eq!(
+            Err(SbeError::MissingField(11)),
+            SCHEMA.encode(&cancel, &mut buf)
+        );
+        assert_eq!(
+            Err(SbeError::UnknownMsgType("0".to_string())),
+            SCHEMA.encode(&Message::of_type("0"), &mut buf)
+        );
+    }
+
+    #[test]
+    fn invalid_messages() {
+        let mut buf = BytesMut::new();
+        SCHEMA.encode(&order(), &mut buf).unwrap();
+
+        for len in 0..buf.len() {
+            assert!(matches!(
+                SCHEMA.decode(&buf[..len]),
+                Err(SbeError::Truncated { .. })
+            ));
+        }
+
+        let mut other_template = buf.clone();
+        other_template[2] = 9;
+        assert_eq!(
+            Err(SbeError::UnknownTemplate(9)),
+            SCHEMA.decode(&other_template).map(|m| m.to_string())
+        );
+        let mut other_schema = buf;
+        other_schema[4] = 1;
+        assert_eq!(
+            Err(SbeError::WrongSchema(1)),
+            SCHEMA.decode(&other_schema).map(|m| m.to_string())
+        );
+    }
+
+    #[test]
+    fn big_endian_and_exponents() {
+        const PRICES: Schema = Schema {
+            package: "prices",
+            id: 2,
+            version: 1,
+            byte_order: ByteOrder::BigEndian,
+            messages: &[MessageLayout {
+                name: "Quote",
+                template_id: 300,
+                msg_type: "S",
+                block_length: 12,
+                fields: &[
+                    FieldLayout {
+                        tag: 132,
+                        name: "BidPx",
+                        offset: 0,
+                        encoding: Encoding::Decimal(Primitive::Int32, None),
+                        presence: Presence::Required,
+                    },
+                    FieldLayout {
+                        tag: 134,
+                        name: "BidSize",
+                        offset: 5,
+                        encoding: Encoding::Int(Primitive::Int16),
+                        presence: Presence::Required,
+                    },
+                    FieldLayout {
+                        tag: 135,
+                        name: "OfferSize",
+                        offset: 7,
+                        encoding: Encoding::Decimal(Primitive::UInt32, Some(3)),
+                        presence: Presence::Optional,
+                    },
+                    FieldLayout {
+                        tag: 15,
+                        name: "Currency",
+                        offset: 11,
+                        encoding: Encoding::CharArray(3),
+                        presence: Presence::Constant("USD"),
+                    },
+                ],
+                data: &[],
+            }],
+        };
+
+        let mut quote = Message::of_type("S");
+        quote.set_field(Field::Decimal(132, Decimal::new(-9_875, 2)));
+        quote.set_field(Field::Int(134, -300));
+        quote.set_field(Field::Decimal(135, Decimal::new(25_000, 0)));
+
+        let mut buf = BytesMut::new();
+        PRICES.encode(&quote, &mut buf).unwrap();
+        assert_eq!([0, 12, 1, 44, 0, 2, 0, 1], buf[..8]);
+        assert_eq!((-9_875_i32).to_be_bytes(), buf[8..12]);
+        assert_eq!(-2, buf[12] as i8);
+        assert_eq!((-300_i16).to_be_bytes(), buf[13..15]);
+        assert_eq!(25_u32.to_be_bytes(), buf[15..19]);
+
+        let decoded = PRICES.decode(&buf).unwrap();
+        assert_eq!(
+            "35=S|15=USD|132=-98.75|134=-300|135=25000|",
+            decoded.to_string()
+        );
+
+        // the null of an unsigned mantissa is its maximum
+        quote.set_field(Field::Decimal(135, Decimal::new(1, 0)));
+        assert!(PRICES.encode(&quote, &mut buf).is_err());
+        let mut quote = Message::of_type("S");
+        quote.set_field(Field::Decimal(132, Decimal::ONE));
+        quote.set_field(Field::Int(134, 1));
+        buf.clear();
+        PRICES.encode(&quote, &mut buf).unwrap();
+        assert_eq!([0xff; 4], buf[15..19]);
+        assert!(PRICES.decode(&buf).unwrap().get_field_safe(135).is_err());
+    }
+}
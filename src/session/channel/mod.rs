@@ -0,0 +1,161 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use crate::cracker::FrameError;
+use crate::model::field::ParseError;
+use crate::model::message::Message;
+use crate::session::{HeaderMismatch, SendingTimeProblem, SessionID, State};
+use crate::transport::ConnectError;
+
+/// Everything a running session reports back to the application.
+///
+/// Sessions share a single `mpsc::Sender<SessionEvent>`, so one receiver can
+/// fan-in events from every session the application runs.
+#[derive(Debug)]
+pub enum SessionEvent {
+    Message {
+        session_id: SessionID,
+        message: Message,
+    },
+    StateChanged {
+        session_id: SessionID,
+        state: State,
+    },
+    Error {
+        session_id: SessionID,
+        error: SessionError,
+    },
+    /// An initiator is about to connect to `endpoint`; `attempt` counts from 1
+    /// on every reconnect
+    Connecting {
+        session_id: SessionID,
+        endpoint: String,
+        attempt: u32,
+    },
+    Connected {
+        session_id: SessionID,
+        endpoint: String,
+    },
+    /// An attempt failed; the next one follows after `retry_in`, or none does
+    /// once the attempts are used up
+    ConnectFailed {
+        session_id: SessionID,
+        endpoint: String,
+        attempt: u32,
+        error: ConnectError,
+        retry_in: Option<Duration>,
+    },
+}
+
+impl SessionEvent {
+    #[must_use]
+    pub const fn session_id(&self) -> &SessionID {
+        match self {
+            SessionEvent::Message { session_id, .. }
+            | SessionEvent::StateChanged { session_id, .. }
+            | SessionEvent::Error { session_id, .. }
+            | SessionEvent::Connecting { session_id, .. }
+            | SessionEvent::Connected { session_id, .. }
+            | SessionEvent::ConnectFailed { session_id, .. } => session_id,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(std::io::Error),
+    Framing(FrameError),
+    Parse(ParseError),
+    /// A received message isn't addressed as the session expects
+    Header(HeaderMismatch),
+    /// A received message's SendingTime is outside the accepted window
+    SendingTime(SendingTimeProblem),
+    /// An acceptor could not accept an initiator's connection
+    Accept(ConnectError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(e) => write!(f, "transport error: {}", e),
+            SessionError::Framing(e) => write!(f, "could not frame message: {}", e),
+            SessionError::Parse(e) => write!(f, "could not parse message: {}", e),
+            SessionError::Header(e) => write!(f, "unexpected header: {}", e),
+            SessionError::SendingTime(e) => write!(f, "{}", e),
+            SessionError::Accept(e) => write!(f, "could not accept connection: {}", e),
+        }
+    }
+}
+
+/// What a `SessionSender` queues for the session's connection
+#[derive(Debug)]
+pub enum Outbound {
+    Message(Message),
+    /// Close the connection once what was queued before has been sent
+    Disconnect,
+}
+
+/// Cloneable handle for sending messages on a session from any task.
+#[derive(Clone, Debug)]
+pub struct SessionSender {
+    session_id: SessionID,
+    tx: mpsc::UnboundedSender<Outbound>,
+}
+
+impl SessionSender {
+    #[must_use]
+    pub const fn new(session_id: SessionID, tx: mpsc::UnboundedSender<Outbound>) -> Self {
+        Self { session_id, tx }
+    }
+
+    #[must_use]
+    pub const fn session_id(&self) -> &SessionID {
+        &self.session_id
+    }
+
+    /// Queue `message` to be sent on the session; the session stamps the
+    /// standard header before it goes out.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the session has stopped, handing back the message
+    pub fn send(&self, message: Message) -> Result<(), SendError> {
+        self.tx
+            .send(Outbound::Message(message))
+            .map_err(|e| match e.0 {
+                Outbound::Message(message) => SendError {
+                    session_id: self.session_id.clone(),
+                    message,
+                },
+                Outbound::Disconnect => unreachable!("a message was sent"),
+            })
+    }
+
+    /// Close the session's connection once the messages queued before it
+    /// have been sent. An initiator then reconnects, an acceptor waits for
+    /// the next connection.
+    pub fn disconnect(&self) {
+        let _ = self.tx.send(Outbound::Disconnect);
+    }
+
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+/// Message could not be queued because the session is no longer running
+#[derive(Debug)]
+pub struct SendError {
+    pub session_id: SessionID,
+    pub message: Message,
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "session {} is not running", self.session_id)
+    }
+}
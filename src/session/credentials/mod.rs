@@ -0,0 +1,152 @@
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::session::SessionID;
+
+/// Username (553) and passwords an initiator sends on Logon.
+///
+/// Passwords are looked up through a `SecretProvider` each time a Logon is
+/// built, so they don't have to sit in the session's configuration.
+#[derive(Clone)]
+pub struct Credentials {
+    pub username: String,
+    /// Sent as Password (554)
+    pub password: Option<Arc<dyn SecretProvider>>,
+    /// Sent as NewPassword (925) to change the password on this Logon
+    pub new_password: Option<Arc<dyn SecretProvider>>,
+}
+
+impl Credentials {
+    #[must_use]
+    pub fn new(username: &str) -> Self {
+        Credentials {
+            username: username.to_string(),
+            password: None,
+            new_password: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_password(mut self, password: impl SecretProvider + 'static) -> Self {
+        self.password = Some(Arc::new(password));
+        self
+    }
+
+    #[must_use]
+    pub fn with_new_password(mut self, new_password: impl SecretProvider + 'static) -> Self {
+        self.new_password = Some(Arc::new(new_password));
+        self
+    }
+}
+
+impl Debug for Credentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("new_password", &self.new_password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// Where a password comes from, e.g. the environment, a file mounted by the
+/// deployment or a vault client
+pub trait SecretProvider: Send + Sync {
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the secret can't be looked up
+    fn secret(&self) -> Result<String, SecretError>;
+}
+
+impl<F> SecretProvider for F
+where
+    F: Fn() -> Result<String, SecretError> + Send + Sync,
+{
+    fn secret(&self) -> Result<String, SecretError> {
+        self()
+    }
+}
+
+/// A secret held in memory, e.g. for tests
+pub struct PlainSecret(pub String);
+
+impl SecretProvider for PlainSecret {
+    fn secret(&self) -> Result<String, SecretError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A secret read from an environment variable
+pub struct EnvSecret(pub String);
+
+impl SecretProvider for EnvSecret {
+    fn secret(&self) -> Result<String, SecretError> {
+        std::env::var(&self.0).map_err(|_| SecretError::MissingEnv(self.0.clone()))
+    }
+}
+
+/// A secret read from a file, without its trailing newline
+pub struct FileSecret(pub PathBuf);
+
+impl SecretProvider for FileSecret {
+    fn secret(&self) -> Result<String, SecretError> {
+        std::fs::read_to_string(&self.0)
+            .map(|secret| secret.trim_end_matches(['\r', '\n']).to_string())
+            .map_err(|e| SecretError::Io(self.0.clone(), e))
+    }
+}
+
+#[derive(Debug)]
+pub enum SecretError {
+    MissingEnv(String),
+    Io(PathBuf, io::Error),
+    Other(String),
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::MissingEnv(var) => write!(f, "environment variable {} is not set", var),
+            SecretError::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            SecretError::Other(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// What an initiator sent on Logon, for a `CredentialCheck`
+#[derive(Default)]
+pub struct LogonCredentials<'a> {
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+    pub new_password: Option<&'a str>,
+}
+
+/// Acceptor hook deciding whether an initiator may log on
+pub trait CredentialCheck: Send + Sync {
+    ///
+    /// # Errors
+    ///
+    /// The reason for refusing the Logon, sent back as Text (58) on a Logout
+    fn check(
+        &self,
+        session_id: &SessionID,
+        credentials: &LogonCredentials<'_>,
+    ) -> Result<(), String>;
+}
+
+impl<F> CredentialCheck for F
+where
+    F: Fn(&SessionID, &LogonCredentials<'_>) -> Result<(), String> + Send + Sync,
+{
+    fn check(
+        &self,
+        session_id: &SessionID,
+        credentials: &LogonCredentials<'_>,
+    ) -> Result<(), String> {
+        self(session_id, credentials)
+    }
+}
@@ -1,15 +1,30 @@
+pub mod channel;
+pub mod credentials;
 pub mod settings;
 
 use settings::Settings;
 use std::fmt::{Display, Formatter};
+use std::time::{Duration, SystemTime};
+
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+use crate::model::timestamp::parse_utc_timestamp;
 
 // used to refer to a session
-// todo session qualifier
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
 pub struct SessionID {
+    /// Version of the session layer. FIX 5.x sessions run over `FIXT.1.1`, with
+    /// the application version agreed separately on Logon
     pub begin_string: String,
     pub sender_comp_id: String,
+    pub sender_sub_id: Option<String>,
+    pub sender_location_id: Option<String>,
     pub target_comp_id: String,
+    pub target_sub_id: Option<String>,
+    pub target_location_id: Option<String>,
+    /// Tells apart sessions that share the same CompIDs, e.g. order entry and
+    /// drop copy to the same counterparty; never sent on the wire
+    pub session_qualifier: Option<String>,
 }
 
 impl Display for SessionID {
@@ -17,29 +32,271 @@ impl Display for SessionID {
         write!(
             f,
             "{}:{}->{}",
-            self.begin_string, self.sender_comp_id, self.target_comp_id
-        )
+            self.begin_string,
+            party(
+                &self.sender_comp_id,
+                &self.sender_sub_id,
+                &self.sender_location_id,
+                "/"
+            ),
+            party(
+                &self.target_comp_id,
+                &self.target_sub_id,
+                &self.target_location_id,
+                "/"
+            )
+        )?;
+        match &self.session_qualifier {
+            Some(qualifier) => write!(f, ":{}", qualifier),
+            None => Ok(()),
+        }
     }
 }
 
+/// A CompID followed by its sub and location IDs where there are any
+fn party(
+    comp_id: &str,
+    sub_id: &Option<String>,
+    location_id: &Option<String>,
+    separator: &str,
+) -> String {
+    [Some(comp_id), sub_id.as_deref(), location_id.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Percent-encode all but ASCII letters, digits and `.`
+fn escape(id: &str) -> String {
+    let mut escaped = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'.' {
+            escaped.push(char::from(byte));
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    escaped
+}
+
 impl SessionID {
     #[must_use]
     pub fn from_settings(settings: &Settings) -> Self {
         Self {
             begin_string: settings.begin_string.into(),
             sender_comp_id: settings.sender_comp_id.clone(),
+            sender_sub_id: settings.sender_sub_id.clone(),
+            sender_location_id: settings.sender_location_id.clone(),
             target_comp_id: settings.target_comp_id.clone(),
+            target_sub_id: settings.target_sub_id.clone(),
+            target_location_id: settings.target_location_id.clone(),
+            session_qualifier: settings.session_qualifier.clone(),
+        }
+    }
+
+    /// Name the session's files in a store start with, safe for any file
+    /// system and different for every session: the BeginString, sender and
+    /// target, then the qualifier, apart by `-`. Sub IDs follow their CompID
+    /// after `_`, location IDs after `@`, and anything but letters, digits
+    /// and `.` in an ID is percent-encoded, so no ID can pass for another.
+    #[must_use]
+    pub fn file_prefix(&self) -> String {
+        let mut prefix = escape(&self.begin_string);
+        let parties = [
+            (
+                &self.sender_comp_id,
+                &self.sender_sub_id,
+                &self.sender_location_id,
+            ),
+            (
+                &self.target_comp_id,
+                &self.target_sub_id,
+                &self.target_location_id,
+            ),
+        ];
+        for (comp_id, sub_id, location_id) in parties {
+            prefix.push('-');
+            prefix.push_str(&escape(comp_id));
+            if let Some(sub_id) = sub_id {
+                prefix.push('_');
+                prefix.push_str(&escape(sub_id));
+            }
+            if let Some(location_id) = location_id {
+                prefix.push('@');
+                prefix.push_str(&escape(location_id));
+            }
+        }
+        if let Some(qualifier) = &self.session_qualifier {
+            prefix.push('-');
+            prefix.push_str(&escape(qualifier));
+        }
+        prefix
+    }
+
+    /// Header fields identifying the session on the messages it sends:
+    /// BeginString (8), SenderCompID (49), TargetCompID (56) and whichever of
+    /// SenderSubID (50), SenderLocationID (142), TargetSubID (57) and
+    /// TargetLocationID (143) it has
+    #[must_use]
+    pub fn header_fields(&self) -> Vec<Field> {
+        let mut fields = vec![
+            Field::String(8, self.begin_string.clone()),
+            Field::String(49, self.sender_comp_id.clone()),
+            Field::String(56, self.target_comp_id.clone()),
+        ];
+        let ids = [
+            (50, &self.sender_sub_id),
+            (142, &self.sender_location_id),
+            (57, &self.target_sub_id),
+            (143, &self.target_location_id),
+        ];
+        for (tag, value) in ids {
+            if let Some(value) = value {
+                fields.push(Field::String(tag, value.clone()));
+            }
+        }
+        fields
+    }
+
+    /// Check a received message is addressed as this session expects: from
+    /// the counterparty's CompID to ours, with the counterparty's sub and
+    /// location IDs as sender and ours as target. Of the sub and location
+    /// IDs, only those the session has are checked.
+    ///
+    /// # Errors
+    ///
+    /// The first header field that is missing or differs
+    pub fn check_header(&self, message: &Message) -> Result<(), HeaderMismatch> {
+        let target_comp_id = Some(self.target_comp_id.clone());
+        let sender_comp_id = Some(self.sender_comp_id.clone());
+        // the counterparty sends with the roles swapped
+        let expected = [
+            (49, &target_comp_id),
+            (56, &sender_comp_id),
+            (50, &self.target_sub_id),
+            (142, &self.target_location_id),
+            (57, &self.sender_sub_id),
+            (143, &self.sender_location_id),
+        ];
+        for (tag, expected) in expected {
+            let Some(expected) = expected else {
+                continue;
+            };
+            let received = message.get_field_safe(tag).ok().map(Field::value_string);
+            if received.as_ref() != Some(expected) {
+                return Err(HeaderMismatch {
+                    tag,
+                    expected: expected.clone(),
+                    received,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check the SendingTime (52) of a received message is within `max_latency`
+/// of `now`, either way, as clocks drift.
+///
+/// # Errors
+///
+/// If SendingTime is missing, malformed or out of the window
+pub fn check_sending_time(
+    message: &Message,
+    max_latency: Duration,
+    now: SystemTime,
+) -> Result<(), SendingTimeProblem> {
+    let Ok(field) = message.get_field_safe(52) else {
+        return Err(SendingTimeProblem::Missing);
+    };
+    let sending_time = field.value_string();
+    let Some(sent) = parse_utc_timestamp(&sending_time) else {
+        return Err(SendingTimeProblem::Malformed { sending_time });
+    };
+    let latency = now.duration_since(sent).unwrap_or_else(|e| e.duration());
+    if latency <= max_latency {
+        Ok(())
+    } else {
+        Err(SendingTimeProblem::Stale {
+            sending_time,
+            max_latency,
+        })
+    }
+}
+
+/// SendingTime (52) of a received message is missing, malformed or too far
+/// from now
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SendingTimeProblem {
+    Missing,
+    Malformed {
+        sending_time: String,
+    },
+    Stale {
+        sending_time: String,
+        max_latency: Duration,
+    },
+}
+
+impl Display for SendingTimeProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendingTimeProblem::Missing => write!(f, "SendingTime (52) is missing"),
+            SendingTimeProblem::Malformed { sending_time } => write!(
+                f,
+                "SendingTime (52) {} is not a UTC timestamp",
+                sending_time
+            ),
+            SendingTimeProblem::Stale {
+                sending_time,
+                max_latency,
+            } => write!(
+                f,
+                "SendingTime (52) {} is more than {}s from now",
+                sending_time,
+                max_latency.as_secs()
+            ),
+        }
+    }
+}
+
+/// A header field of a received message that doesn't match its session
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HeaderMismatch {
+    pub tag: u32,
+    pub expected: String,
+    /// `None` when the field is missing
+    pub received: Option<String>,
+}
+
+impl Display for HeaderMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.received {
+            Some(received) => write!(
+                f,
+                "tag {} was {}, expected {}",
+                self.tag, received, self.expected
+            ),
+            None => write!(f, "tag {} is missing, expected {}", self.tag, self.expected),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
     use crate::engine::filestore::FileStore;
     use crate::engine::Engine;
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+    use crate::model::timestamp::format_utc_timestamp;
     use crate::model::BeginString;
     use crate::session::settings::{SessionType, Settings, SettingsBuilder};
-    use crate::session::State;
+    use crate::session::{
+        check_sending_time, HeaderMismatch, SendingTimeProblem, SessionID, State,
+    };
 
     #[test]
     fn it_works() {
@@ -53,6 +310,7 @@ mod tests {
             "Exchange".to_string(),
             SessionType::Initiator,
         )
+        .with_endpoint("localhost:9876")
         .build();
         let settings2 = SettingsBuilder::new(
             BeginString::Fix40,
@@ -60,7 +318,8 @@ mod tests {
             "Venue".to_string(),
             SessionType::Initiator,
         )
-        .with_hb_interval(10_000)
+        .with_endpoint("localhost:9876")
+        .with_hb_interval(Duration::from_secs(10))
         .build();
 
         let session_id = engine.create_session(settings).unwrap().clone();
@@ -74,6 +333,7 @@ mod tests {
             "Exchange".to_string(),
             SessionType::Initiator,
         )
+        .with_endpoint("localhost:9876")
         .build();
         assert!(engine.create_session(settings).is_err());
         assert_eq!(2, engine.sessions().len());
@@ -88,6 +348,172 @@ mod tests {
 
         engine.logon_session(&session_id2);
     }
+
+    #[test]
+    fn qualified_session_ids() {
+        let order_entry = SessionID {
+            begin_string: "FIX.4.4".to_string(),
+            sender_comp_id: "Rocks".to_string(),
+            sender_sub_id: Some("Desk1".to_string()),
+            target_comp_id: "Exchange".to_string(),
+            target_location_id: Some("LDN".to_string()),
+            ..SessionID::default()
+        };
+        let drop_copy = SessionID {
+            session_qualifier: Some("DropCopy".to_string()),
+            ..order_entry.clone()
+        };
+        assert_ne!(order_entry, drop_copy);
+        assert_eq!("FIX.4.4:Rocks/Desk1->Exchange/LDN", order_entry.to_string());
+        assert_eq!(
+            "FIX.4.4:Rocks/Desk1->Exchange/LDN:DropCopy",
+            drop_copy.to_string()
+        );
+        assert_eq!(
+            "FIX.4.4-Rocks_Desk1-Exchange@LDN-DropCopy",
+            drop_copy.file_prefix()
+        );
+
+        let mut message = Message::of_type("0");
+        for field in drop_copy.header_fields() {
+            message.set_field(field);
+        }
+        assert_eq!(
+            "8=FIX.4.4|35=0|49=Rocks|50=Desk1|56=Exchange|143=LDN|",
+            message.to_string()
+        );
+
+        // the exchange replies from LDN to Desk1
+        let mut reply = Message::of_type("0");
+        reply.set_field(Field::String(49, "Exchange".to_string()));
+        reply.set_field(Field::String(56, "Rocks".to_string()));
+        reply.set_field(Field::String(142, "LDN".to_string()));
+        assert_eq!(
+            Err(HeaderMismatch {
+                tag: 57,
+                expected: "Desk1".to_string(),
+                received: None
+            }),
+            drop_copy.check_header(&reply)
+        );
+        reply.set_field(Field::String(57, "Desk1".to_string()));
+        assert_eq!(Ok(()), drop_copy.check_header(&reply));
+
+        // sent to someone else
+        reply.set_field(Field::String(56, "Paper".to_string()));
+        assert_eq!(
+            Err(HeaderMismatch {
+                tag: 56,
+                expected: "Rocks".to_string(),
+                received: Some("Paper".to_string())
+            }),
+            drop_copy.check_header(&reply)
+        );
+    }
+
+    #[test]
+    fn file_prefixes_do_not_collide() {
+        let session = |sender: &str, target: &str| SessionID {
+            begin_string: "FIX.4.4".to_string(),
+            sender_comp_id: sender.to_string(),
+            target_comp_id: target.to_string(),
+            ..SessionID::default()
+        };
+        let with_sub_id = SessionID {
+            sender_sub_id: Some("B".to_string()),
+            ..session("A", "Z")
+        };
+        let with_location_id = SessionID {
+            sender_location_id: Some("B".to_string()),
+            ..session("A", "Z")
+        };
+        let qualified = SessionID {
+            session_qualifier: Some("Z".to_string()),
+            ..session("X", "Y")
+        };
+        let prefixes = [
+            session("X-Y", "Z").file_prefix(),
+            session("X", "Y-Z").file_prefix(),
+            qualified.file_prefix(),
+            session("A_B", "Z").file_prefix(),
+            with_sub_id.file_prefix(),
+            session("A@B", "Z").file_prefix(),
+            with_location_id.file_prefix(),
+        ];
+        assert_eq!(
+            [
+                "FIX.4.4-X%2DY-Z",
+                "FIX.4.4-X-Y%2DZ",
+                "FIX.4.4-X-Y-Z",
+                "FIX.4.4-A%5FB-Z",
+                "FIX.4.4-A_B-Z",
+                "FIX.4.4-A%40B-Z",
+                "FIX.4.4-A@B-Z",
+            ],
+            prefixes
+        );
+        assert_eq!(
+            "FIX.4.4-Rocks%2FNY-C%3A%5C%25",
+            session("Rocks/NY", "C:\\%").file_prefix()
+        );
+    }
+
+    #[test]
+    fn sending_time_within_max_latency() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let max_latency = Duration::from_secs(120);
+        let sent_at = |time| {
+            let mut message = Message::of_type("0");
+            message.set_field(Field::String(52, format_utc_timestamp(time)));
+            message
+        };
+
+        for time in [now, now - max_latency, now + Duration::from_secs(30)] {
+            assert_eq!(Ok(()), check_sending_time(&sent_at(time), max_latency, now));
+        }
+        assert_eq!(
+            Err(SendingTimeProblem::Stale {
+                sending_time: "20231114-22:10:00.000".to_string(),
+                max_latency
+            }),
+            check_sending_time(&sent_at(now - Duration::from_secs(200)), max_latency, now)
+        );
+        assert_eq!(
+            Err(SendingTimeProblem::Missing),
+            check_sending_time(&Message::of_type("0"), max_latency, now)
+        );
+        let mut malformed = Message::of_type("0");
+        malformed.set_field(Field::String(52, "yesterday".to_string()));
+        assert_eq!(
+            Err(SendingTimeProblem::Malformed {
+                sending_time: "yesterday".to_string()
+            }),
+            check_sending_time(&malformed, max_latency, now)
+        );
+    }
+
+    #[test]
+    fn sending_time_problems_say_what_is_wrong() {
+        assert_eq!(
+            "SendingTime (52) is missing",
+            SendingTimeProblem::Missing.to_string()
+        );
+        assert_eq!(
+            "SendingTime (52) yesterday is not a UTC timestamp",
+            SendingTimeProblem::Malformed {
+                sending_time: "yesterday".to_string()
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "SendingTime (52) 20231114-22:10:00.000 is more than 120s from now",
+            SendingTimeProblem::Stale {
+                sending_time: "20231114-22:10:00.000".to_string(),
+                max_latency: Duration::from_secs(120)
+            }
+            .to_string()
+        );
+    }
 }
 
 // state machine; state transitions here based on events.
@@ -101,5 +527,7 @@ pub enum State {
     // session has been initialised, but scheduled downtime is in effect
     LoggedIn,
     // session has been initialised, and is logged in
+    LogoutSent,
+    // logout msg was sent, waiting for ack...
     LoggedOut, // session has been initialised, but is not logged in (failed? disconnected? etc)
 }
@@ -0,0 +1,730 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Formatter;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::model::BeginString;
+use crate::session::settings::{
+    Framing, InvalidSetting, ReconnectSettings, ScheduleSettings, SessionType, Settings,
+    SettingsBuilder, TimeOfDay, Weekday,
+};
+
+/// Settings of one section, by key
+type Section = BTreeMap<String, String>;
+
+/// Keys understood besides the numbered `SocketConnectHost<n>` and
+/// `SocketConnectPort<n>` of backup endpoints
+const KEYS: [&str; 30] = [
+    "BeginString",
+    "SenderCompID",
+    "SenderSubID",
+    "SenderLocationID",
+    "TargetCompID",
+    "TargetSubID",
+    "TargetLocationID",
+    "SessionQualifier",
+    "ConnectionType",
+    "DefaultApplVerID",
+    "HeartBtInt",
+    "SocketConnectHost",
+    "SocketConnectPort",
+    "SocketAcceptAddress",
+    "SocketAcceptPort",
+    "ReconnectInterval",
+    "StartTime",
+    "EndTime",
+    "StartDay",
+    "EndDay",
+    "TimeZone",
+    "FileStorePath",
+    "ResetOnLogon",
+    "ResetOnLogout",
+    "ResetOnDisconnect",
+    "RefreshOnLogon",
+    "CheckLatency",
+    "MaxLatency",
+    "LogoutTimeout",
+    "SocketFraming",
+];
+
+/// Load the sessions of a configuration file, read as TOML when its name
+/// ends in `.toml` and as a QuickFIX style `.cfg` file otherwise.
+///
+/// # Errors
+///
+/// If the file can't be read or any of its sessions is invalid.
+pub fn load(path: &Path) -> Result<Vec<Settings>, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        parse_toml(&text)
+    } else {
+        parse_cfg(&text)
+    }
+}
+
+/// Parse a QuickFIX style configuration: `key=value` lines under a
+/// `[DEFAULT]` section, whose settings every session starts from, and a
+/// `[SESSION]` section per session. Lines starting with `#` or `;` are
+/// comments.
+///
+/// # Errors
+///
+/// If a line isn't a section or `key=value`, or any session is invalid.
+pub fn parse_cfg(text: &str) -> Result<Vec<Settings>, ConfigError> {
+    let mut default = Section::new();
+    let mut sessions: Vec<Section> = vec![];
+    let mut current: Option<&mut Section> = None;
+    for (number, line) in (1..).zip(text.lines()) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = match name.trim().to_uppercase().as_str() {
+                "DEFAULT" => Some(&mut default),
+                "SESSION" => {
+                    sessions.push(Section::new());
+                    sessions.last_mut()
+                }
+                _ => {
+                    return Err(ConfigError::Syntax {
+                        line: number,
+                        message: format!("unknown section [{}]", name),
+                    })
+                }
+            };
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(ConfigError::Syntax {
+                line: number,
+                message: format!("expected key=value, found {}", line),
+            });
+        };
+        let Some(section) = current.as_mut() else {
+            return Err(ConfigError::Syntax {
+                line: number,
+                message: "setting outside of [DEFAULT] or [SESSION]".to_string(),
+            });
+        };
+        section.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    build_sessions(&default, &sessions)
+}
+
+/// Parse a TOML configuration with the same keys as a `.cfg` file, under a
+/// `[DEFAULT]` table and a `[[SESSION]]` table per session.
+///
+/// # Errors
+///
+/// If the text isn't TOML of that shape, or any session is invalid.
+pub fn parse_toml(text: &str) -> Result<Vec<Settings>, ConfigError> {
+    let table: toml::Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| ConfigError::Toml(e.to_string()))?;
+    let mut default = Section::new();
+    let mut sessions = vec![];
+    for (name, value) in table {
+        match (name.as_str(), value) {
+            ("DEFAULT", toml::Value::Table(table)) => default = toml_section(table)?,
+            ("SESSION", toml::Value::Array(tables)) => {
+                for table in tables {
+                    let toml::Value::Table(table) = table else {
+                        return Err(ConfigError::Toml(
+                            "SESSION must be an array of tables".to_string(),
+                        ));
+                    };
+                    sessions.push(toml_section(table)?);
+                }
+            }
+            ("DEFAULT", _) => return Err(ConfigError::Toml("DEFAULT must be a table".to_string())),
+            ("SESSION", _) => {
+                return Err(ConfigError::Toml(
+                    "SESSION must be an array of tables, [[SESSION]]".to_string(),
+                ))
+            }
+            (name, _) => return Err(ConfigError::Toml(format!("unknown section {}", name))),
+        }
+    }
+    build_sessions(&default, &sessions)
+}
+
+fn toml_section(table: toml::Table) -> Result<Section, ConfigError> {
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Boolean(value) => if value { "Y" } else { "N" }.to_string(),
+                toml::Value::Datetime(value) => value.to_string(),
+                other => {
+                    return Err(ConfigError::Toml(format!(
+                        "{} has unsupported value {}",
+                        key, other
+                    )))
+                }
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+fn build_sessions(default: &Section, sessions: &[Section]) -> Result<Vec<Settings>, ConfigError> {
+    if sessions.is_empty() {
+        return Err(ConfigError::NoSessions);
+    }
+    sessions
+        .iter()
+        .zip(1..)
+        .map(|(section, session)| {
+            let mut merged = default.clone();
+            merged.extend(
+                section
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            );
+            SessionConfig {
+                session,
+                settings: merged,
+            }
+            .build()
+        })
+        .collect()
+}
+
+/// The merged settings of one session, numbered from 1 in the file
+struct SessionConfig {
+    session: usize,
+    settings: Section,
+}
+
+impl SessionConfig {
+    fn build(&self) -> Result<Settings, ConfigError> {
+        for key in self.settings.keys() {
+            let numbered = ["SocketConnectHost", "SocketConnectPort"]
+                .iter()
+                .any(|prefix| {
+                    key.strip_prefix(prefix)
+                        .is_some_and(|n| n.parse::<u8>().is_ok())
+                });
+            if !KEYS.contains(&key.as_str()) && !numbered {
+                warn!(session = self.session, key, "ignoring unknown setting");
+            }
+        }
+
+        let begin_string = self.parse("BeginString", "a FIX version such as FIX.4.4", |v| {
+            BeginString::try_from(v).ok()
+        })?;
+        let session_type = self.parse("ConnectionType", "initiator or acceptor", |v| {
+            match v.to_lowercase().as_str() {
+                "initiator" => Some(SessionType::Initiator),
+                "acceptor" => Some(SessionType::Acceptor),
+                _ => None,
+            }
+        })?;
+        let mut builder = SettingsBuilder::new(
+            begin_string,
+            self.required("SenderCompID")?.to_string(),
+            self.required("TargetCompID")?.to_string(),
+            session_type,
+        );
+
+        if let Some(sub_id) = self.get("SenderSubID") {
+            builder.with_sender_sub_id(sub_id);
+        }
+        if let Some(location_id) = self.get("SenderLocationID") {
+            builder.with_sender_location_id(location_id);
+        }
+        if let Some(sub_id) = self.get("TargetSubID") {
+            builder.with_target_sub_id(sub_id);
+        }
+        if let Some(location_id) = self.get("TargetLocationID") {
+            builder.with_target_location_id(location_id);
+        }
+        if let Some(qualifier) = self.get("SessionQualifier") {
+            builder.with_session_qualifier(qualifier);
+        }
+        if let Some(appl_ver_id) = self.parse_optional(
+            "DefaultApplVerID",
+            "a FIX version such as FIX.5.0SP2 or its ApplVerID",
+            |v| BeginString::from_appl_ver_id(v).or_else(|| BeginString::try_from(v).ok()),
+        )? {
+            builder.with_default_appl_ver_id(appl_ver_id);
+        }
+        if let Some(seconds) =
+            self.parse_optional("HeartBtInt", "seconds", |v| v.parse::<u32>().ok())?
+        {
+            builder.with_hb_interval(Duration::from_secs(u64::from(seconds)));
+        }
+        if let Some(framing) =
+            self.parse_optional("SocketFraming", "TagValue or SOFH", |v| {
+                match v.to_lowercase().as_str() {
+                    "tagvalue" => Some(Framing::TagValue),
+                    "sofh" => Some(Framing::Sofh),
+                    _ => None,
+                }
+            })?
+        {
+            builder.with_framing(framing);
+        }
+
+        match session_type {
+            SessionType::Initiator => {
+                for endpoint in self.endpoints()? {
+                    builder.with_endpoint(&endpoint);
+                }
+            }
+            SessionType::Acceptor => {
+                let port = self.port("SocketAcceptPort")?;
+                let address = self.get("SocketAcceptAddress").unwrap_or("0.0.0.0");
+                builder.with_accept_address(&format!("{}:{}", address, port));
+            }
+        }
+        if let Some(seconds) =
+            self.parse_optional("ReconnectInterval", "seconds", |v| v.parse::<u64>().ok())?
+        {
+            let defaults = ReconnectSettings::default();
+            builder.with_reconnect(ReconnectSettings {
+                initial_backoff: Duration::from_secs(seconds),
+                max_backoff: defaults.max_backoff.max(Duration::from_secs(seconds)),
+                ..defaults
+            });
+        }
+
+        builder.with_schedule(self.schedule()?);
+        if let Some(store_path) = self.get("FileStorePath") {
+            builder.with_store_path(Path::new(store_path));
+        }
+        if let Some(reset) = self.flag("ResetOnLogon")? {
+            builder.with_reset_on_logon(reset);
+        }
+        if let Some(reset) = self.flag("ResetOnLogout")? {
+            builder.with_reset_on_logout(reset);
+        }
+        if let Some(reset) = self.flag("ResetOnDisconnect")? {
+            builder.with_reset_on_disconnect(reset);
+        }
+        if let Some(refresh) = self.flag("RefreshOnLogon")? {
+            builder.with_refresh_on_logon(refresh);
+        }
+        let max_latency =
+            self.parse_optional("MaxLatency", "seconds", |v| v.parse::<u64>().ok())?;
+        if self.flag("CheckLatency")?.unwrap_or(false) {
+            builder.with_max_latency(Duration::from_secs(max_latency.unwrap_or(120)));
+        }
+        if let Some(seconds) =
+            self.parse_optional("LogoutTimeout", "seconds", |v| v.parse::<u64>().ok())?
+        {
+            builder.with_logout_timeout(Duration::from_secs(seconds));
+        }
+        let settings = builder.build();
+        settings
+            .validate()
+            .map_err(|error| ConfigError::InvalidSettings {
+                session: self.session,
+                error,
+            })?;
+        Ok(settings)
+    }
+
+    /// The primary `SocketConnectHost`/`Port`, then backups numbered from 1
+    fn endpoints(&self) -> Result<Vec<String>, ConfigError> {
+        let mut endpoints = vec![format!(
+            "{}:{}",
+            self.required("SocketConnectHost")?,
+            self.port("SocketConnectPort")?
+        )];
+        for n in 1.. {
+            let host = format!("SocketConnectHost{}", n);
+            let Some(host) = self.get(&host) else {
+                break;
+            };
+            endpoints.push(format!(
+                "{}:{}",
+                host,
+                self.port(&format!("SocketConnectPort{}", n))?
+            ));
+        }
+        Ok(endpoints)
+    }
+
+    fn schedule(&self) -> Result<ScheduleSettings, ConfigError> {
+        Ok(ScheduleSettings {
+            start_time: self.parse_optional("StartTime", "a time such as 08:00:00", parse_time)?,
+            end_time: self.parse_optional("EndTime", "a time such as 17:30:00", parse_time)?,
+            start_day: self.parse_optional("StartDay", "a day such as Monday or mon", parse_day)?,
+            end_day: self.parse_optional("EndDay", "a day such as Friday or fri", parse_day)?,
+            time_zone: self.get("TimeZone").map(str::to_string),
+        })
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.settings.get(key).map(String::as_str)
+    }
+
+    fn required(&self, key: &str) -> Result<&str, ConfigError> {
+        self.get(key).ok_or_else(|| self.missing(key))
+    }
+
+    fn missing(&self, key: &str) -> ConfigError {
+        ConfigError::MissingSetting {
+            session: self.session,
+            key: key.to_string(),
+        }
+    }
+
+    fn parse<T>(
+        &self,
+        key: &str,
+        expected: &'static str,
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> Result<T, ConfigError> {
+        self.parse_optional(key, expected, parse)?
+            .ok_or_else(|| self.missing(key))
+    }
+
+    fn parse_optional<T>(
+        &self,
+        key: &str,
+        expected: &'static str,
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> Result<Option<T>, ConfigError> {
+        let Some(value) = self.get(key) else {
+            return Ok(None);
+        };
+        parse(value)
+            .map(Some)
+            .ok_or_else(|| ConfigError::InvalidValue {
+                session: self.session,
+                key: key.to_string(),
+                value: value.to_string(),
+                expected,
+            })
+    }
+
+    fn port(&self, key: &str) -> Result<u16, ConfigError> {
+        self.parse(key, "a port number", |v| {
+            v.parse::<u16>().ok().filter(|port| *port > 0)
+        })
+    }
+
+    fn flag(&self, key: &str) -> Result<Option<bool>, ConfigError> {
+        self.parse_optional(key, "Y or N", |v| match v {
+            "Y" | "y" => Some(true),
+            "N" | "n" => Some(false),
+            _ => None,
+        })
+    }
+}
+
+fn parse_time(value: &str) -> Option<TimeOfDay> {
+    let mut parts = value.split(':').map(|part| part.parse::<u8>().ok());
+    let time = TimeOfDay {
+        hour: parts.next()??,
+        minute: parts.next()??,
+        second: parts.next()??,
+    };
+    let valid = parts.next().is_none() && time.hour < 24 && time.minute < 60 && time.second < 60;
+    valid.then_some(time)
+}
+
+fn parse_day(value: &str) -> Option<Weekday> {
+    let day = value.to_lowercase();
+    let days = [
+        ("monday", Weekday::Monday),
+        ("tuesday", Weekday::Tuesday),
+        ("wednesday", Weekday::Wednesday),
+        ("thursday", Weekday::Thursday),
+        ("friday", Weekday::Friday),
+        ("saturday", Weekday::Saturday),
+        ("sunday", Weekday::Sunday),
+    ];
+    days.iter()
+        .find(|(name, _)| day.len() >= 2 && name.starts_with(day.as_str()))
+        .map(|(_, weekday)| *weekday)
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    Toml(String),
+    NoSessions,
+    /// `session` counts the file's sessions from 1
+    MissingSetting {
+        session: usize,
+        key: String,
+    },
+    InvalidValue {
+        session: usize,
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+    InvalidSettings {
+        session: usize,
+        error: InvalidSetting,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            ConfigError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            ConfigError::Toml(message) => write!(f, "invalid TOML: {}", message),
+            ConfigError::NoSessions => write!(f, "no [SESSION] sections"),
+            ConfigError::MissingSetting { session, key } => {
+                write!(f, "session {}: {} is required", session, key)
+            }
+            ConfigError::InvalidValue {
+                session,
+                key,
+                value,
+                expected,
+            } => write!(
+                f,
+                "session {}: {} is \"{}\", expected {}",
+                session, key, value, expected
+            ),
+            ConfigError::InvalidSettings { session, error } => {
+                write!(f, "session {}: {}", session, error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use crate::model::BeginString;
+    use crate::session::settings::config::{parse_cfg, parse_toml, ConfigError};
+    use crate::session::settings::{SessionType, Settings, TimeOfDay, Weekday};
+
+    const CFG: &str = "
+# shared by every session
+[DEFAULT]
+BeginString=FIX.4.4
+SenderCompID=Rocks
+HeartBtInt=30
+StartTime=08:00:00
+EndTime=17:30:00
+TimeZone=Europe/London
+FileStorePath=store
+ResetOnLogon=Y
+
+[SESSION]
+TargetCompID=Exchange
+SenderSubID=Desk1
+SessionQualifier=OrderEntry
+ConnectionType=initiator
+SocketConnectHost=primary.exchange.com
+SocketConnectPort=9876
+SocketConnectHost1=backup.exchange.com
+SocketConnectPort1=9877
+ReconnectInterval=5
+LogoutTimeout=5
+CheckLatency=Y
+MaxLatency=30
+
+[SESSION]
+; an acceptor for the venue, weekly
+BeginString=FIXT.1.1
+DefaultApplVerID=FIX.5.0SP2
+TargetCompID=Venue
+ConnectionType=acceptor
+SocketAcceptPort=5001
+StartDay=sun
+EndDay=Friday
+ResetOnLogon=N
+";
+
+    const TOML: &str = r#"
+[DEFAULT]
+BeginString = "FIX.4.4"
+SenderCompID = "Rocks"
+HeartBtInt = 30
+StartTime = 08:00:00
+EndTime = "17:30:00"
+TimeZone = "Europe/London"
+FileStorePath = "store"
+ResetOnLogon = true
+
+[[SESSION]]
+TargetCompID = "Exchange"
+SenderSubID = "Desk1"
+SessionQualifier = "OrderEntry"
+ConnectionType = "initiator"
+SocketConnectHost = "primary.exchange.com"
+SocketConnectPort = 9876
+SocketConnectHost1 = "backup.exchange.com"
+SocketConnectPort1 = 9877
+ReconnectInterval = 5
+LogoutTimeout = 5
+CheckLatency = true
+MaxLatency = 30
+
+[[SESSION]]
+BeginString = "FIXT.1.1"
+DefaultApplVerID = "9"
+TargetCompID = "Venue"
+ConnectionType = "acceptor"
+SocketAcceptPort = 5001
+StartDay = "sun"
+EndDay = "Friday"
+ResetOnLogon = false
+"#;
+
+    fn check_sessions(sessions: &[Settings]) {
+        assert_eq!(2, sessions.len());
+
+        let exchange = &sessions[0];
+        assert_eq!(BeginString::Fix44, exchange.begin_string);
+        assert_eq!("Rocks", exchange.sender_comp_id);
+        assert_eq!("Exchange", exchange.target_comp_id);
+        assert_eq!(Some("Desk1"), exchange.sender_sub_id.as_deref());
+        assert_eq!(Some("OrderEntry"), exchange.session_qualifier.as_deref());
+        assert!(matches!(exchange.session_type, SessionType::Initiator));
+        let connection = &exchange.connection_settings;
+        assert_eq!(Duration::from_secs(30), connection.heartbeat_interval());
+        assert_eq!(
+            ["primary.exchange.com:9876", "backup.exchange.com:9877"],
+            connection.endpoints()
+        );
+        assert_eq!(
+            Duration::from_secs(5),
+            connection.reconnect().initial_backoff
+        );
+        let schedule = &exchange.schedule_settings;
+        assert_eq!(
+            Some(TimeOfDay {
+                hour: 17,
+                minute: 30,
+                second: 0
+            }),
+            schedule.end_time
+        );
+        assert_eq!(Some("Europe/London"), schedule.time_zone.as_deref());
+        assert_eq!(None, schedule.start_day);
+        assert_eq!(
+            Some(Path::new("store")),
+            exchange.session_settings.store_path()
+        );
+        assert!(exchange.session_settings.reset_on_logon());
+        assert_eq!(
+            Some(Duration::from_secs(30)),
+            exchange.session_settings.max_latency()
+        );
+        assert_eq!(
+            Duration::from_secs(5),
+            exchange.session_settings.logout_timeout()
+        );
+
+        let venue = &sessions[1];
+        assert_eq!(BeginString::Fixt11, venue.begin_string);
+        assert_eq!(Some(BeginString::Fix50Sp2), venue.default_appl_ver_id);
+        assert!(matches!(venue.session_type, SessionType::Acceptor));
+        assert_eq!(
+            Some("0.0.0.0:5001"),
+            venue.connection_settings.accept_address()
+        );
+        assert_eq!(Some(Weekday::Sunday), venue.schedule_settings.start_day);
+        assert_eq!(Some(Weekday::Friday), venue.schedule_settings.end_day);
+        assert!(!venue.session_settings.reset_on_logon());
+        assert_eq!(None, venue.session_settings.max_latency());
+        assert_eq!(
+            Duration::from_secs(2),
+            venue.session_settings.logout_timeout()
+        );
+    }
+
+    #[test]
+    fn loads_cfg_and_toml_alike() {
+        check_sessions(&parse_cfg(CFG).unwrap());
+        check_sessions(&parse_toml(TOML).unwrap());
+    }
+
+    #[test]
+    fn names_what_is_wrong() {
+        let error = |text: &str| parse_cfg(text).unwrap_err().to_string();
+        let session = "[SESSION]\nBeginString=FIX.4.2\nSenderCompID=Rocks\nTargetCompID=Exchange\n\
+                       ConnectionType=initiator\nSocketConnectHost=localhost\n";
+
+        assert_eq!("session 1: SocketConnectPort is required", error(session));
+        assert_eq!(
+            "session 2: HeartBtInt is \"1.5\", expected seconds",
+            error(&format!(
+                "[DEFAULT]\nSocketConnectPort=9876\n{}{}HeartBtInt=1.5\n",
+                session, session
+            ))
+        );
+        assert_eq!(
+            "session 1: HeartBtInt must be greater than zero",
+            error(&format!("{}SocketConnectPort=1\nHeartBtInt=0\n", session))
+        );
+        let venue =
+            &parse_cfg(&format!("{}SocketConnectPort=1\nHeartBtInt=120\n", session)).unwrap()[0];
+        assert_eq!(
+            Duration::from_secs(120),
+            venue.connection_settings.heartbeat_interval()
+        );
+        assert_eq!(
+            "session 1: EndTime is needed with StartTime",
+            error(&format!(
+                "{}SocketConnectPort=1\nStartTime=08:00:00\n",
+                session
+            ))
+        );
+        assert_eq!(
+            "session 1: ConnectionType is \"both\", expected initiator or acceptor",
+            error(&session.replace("=initiator", "=both"))
+        );
+        assert_eq!(
+            "session 1: TargetCompID must differ from SenderCompID",
+            error(&format!(
+                "{}SocketConnectPort=1\nTargetCompID=Rocks\n",
+                session
+            ))
+        );
+        assert_eq!(
+            "session 1: ReconnectInterval must be greater than zero",
+            error(&format!(
+                "{}SocketConnectPort=1\nReconnectInterval=0\n",
+                session
+            ))
+        );
+        assert_eq!(
+            "session 1: MaxLatency must be greater than zero",
+            error(&format!(
+                "{}SocketConnectPort=1\nCheckLatency=Y\nMaxLatency=0\n",
+                session
+            ))
+        );
+        assert_eq!(
+            "line 2: expected key=value, found BeginString",
+            error("[SESSION]\nBeginString\n")
+        );
+        assert_eq!("line 1: unknown section [SESSIONS]", error("[SESSIONS]\n"));
+        assert!(matches!(
+            parse_cfg("[DEFAULT]\nBeginString=FIX.4.4\n"),
+            Err(ConfigError::NoSessions)
+        ));
+        assert!(matches!(
+            parse_toml("[SESSION]\nBeginString = \"FIX.4.4\"\n"),
+            Err(ConfigError::Toml(_))
+        ));
+    }
+}
@@ -1,52 +1,446 @@
+pub mod config;
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use crate::model::BeginString;
+use crate::session::credentials::Credentials;
 
 #[derive(Clone, Debug)]
 pub struct ConnectionSettings {
-    heartbeat_interval: u16,
-    //tcp4,6
+    heartbeat_interval: Duration,
+    /// host:port of the counterparty, backups after the primary
+    endpoints: Vec<String>,
+    reconnect: ReconnectSettings,
+    /// host:port an acceptor listens on
+    accept_address: Option<String>,
+    tls: Option<TlsSettings>,
 }
 
 impl ConnectionSettings {
-    const fn default_heartbeat_interval() -> u16 {
-        5_000
+    const fn default_heartbeat_interval() -> Duration {
+        Duration::from_secs(5)
+    }
+
+    #[must_use]
+    pub const fn heartbeat_interval(&self) -> Duration {
+        self.heartbeat_interval
+    }
+
+    /// Endpoints an initiator connects to, tried in turn
+    #[must_use]
+    pub fn endpoints(&self) -> &[String] {
+        self.endpoints.as_slice()
+    }
+
+    #[must_use]
+    pub const fn reconnect(&self) -> &ReconnectSettings {
+        &self.reconnect
+    }
+
+    #[must_use]
+    pub fn accept_address(&self) -> Option<&str> {
+        self.accept_address.as_deref()
+    }
+
+    /// TLS options of the connection, `None` for plain TCP
+    #[must_use]
+    pub const fn tls(&self) -> Option<&TlsSettings> {
+        self.tls.as_ref()
+    }
+}
+
+/// How an initiator retries failed connection attempts.
+///
+/// The delay before attempt `n + 1` is `initial_backoff * multiplier^(n - 1)`,
+/// capped at `max_backoff`, then moved randomly by up to `jitter` of itself
+/// so sessions dropped together don't reconnect in lockstep.
+#[derive(Clone, Debug)]
+pub struct ReconnectSettings {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Fraction of the delay to randomise, between 0 and 1
+    pub jitter: f64,
+    /// Attempts before giving up, `None` to keep trying
+    pub max_attempts: Option<u32>,
+}
+
+impl ReconnectSettings {
+    /// Delay after failed attempt `attempt`, counting from 1, with `random`
+    /// drawn uniformly from `[0, 1)`
+    #[must_use]
+    pub fn backoff(&self, attempt: u32, random: f64) -> Duration {
+        let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let delay = (self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        // settings that slipped past validation must not panic here
+        Duration::try_from_secs_f64((delay * (1.0 - jitter + 2.0 * jitter * random)).max(0.0))
+            .unwrap_or(self.max_backoff)
+    }
+
+    fn validate(&self) -> Result<(), InvalidSetting> {
+        // or a dead endpoint is retried in a busy loop
+        if self.initial_backoff.is_zero() {
+            return Err(InvalidSetting::new(
+                "ReconnectInterval",
+                "must be greater than zero",
+            ));
+        }
+        if self.initial_backoff > self.max_backoff {
+            return Err(InvalidSetting::new(
+                "ReconnectInterval",
+                "must not be longer than the maximum backoff",
+            ));
+        }
+        if !(self.multiplier >= 1.0 && self.multiplier.is_finite()) {
+            return Err(InvalidSetting::new(
+                "ReconnectInterval",
+                "can't back off by a multiplier below 1",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(InvalidSetting::new(
+                "ReconnectInterval",
+                "can't vary by a jitter outside 0 to 1",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for ReconnectSettings {
+    fn default() -> Self {
+        ReconnectSettings {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+/// TLS options of a connection; all files are PEM encoded.
+#[derive(Clone, Debug)]
+pub struct TlsSettings {
+    /// Certificate authorities to trust. Initiators fall back to the webpki
+    /// roots without a bundle; acceptors with one require client certificates.
+    pub ca_file: Option<PathBuf>,
+    /// Certificate chain presented to the counterparty, required on acceptors
+    pub cert_file: Option<PathBuf>,
+    /// Private key of `cert_file`
+    pub key_file: Option<PathBuf>,
+    /// Name sent as SNI and checked against the acceptor's certificate,
+    /// defaults to the host connected to
+    pub server_name: Option<String>,
+    /// Verify the counterparty's certificate; only turn off for testing
+    pub verify: bool,
+    /// How long a connection may take to complete the handshake
+    pub handshake_timeout: Duration,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        TlsSettings {
+            ca_file: None,
+            cert_file: None,
+            key_file: None,
+            server_name: None,
+            verify: true,
+            handshake_timeout: Duration::from_secs(10),
+        }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct SessionSettings {
     reset_on_logon: bool,
+    reset_on_logout: bool,
+    reset_on_disconnect: bool,
+    refresh_on_logon: bool,
+    /// Directory of the session's message store
+    store_path: Option<PathBuf>,
+    max_latency: Option<Duration>,
+    logout_timeout: Duration,
 }
 
 impl SessionSettings {
     const fn default_reset_on_logon() -> bool {
         false
     }
+
+    /// Reset sequence numbers when logging on
+    #[must_use]
+    pub const fn reset_on_logon(&self) -> bool {
+        self.reset_on_logon
+    }
+
+    /// Reset sequence numbers after a Logout
+    #[must_use]
+    pub const fn reset_on_logout(&self) -> bool {
+        self.reset_on_logout
+    }
+
+    /// Reset sequence numbers when the connection drops
+    #[must_use]
+    pub const fn reset_on_disconnect(&self) -> bool {
+        self.reset_on_disconnect
+    }
+
+    /// Reload sequence numbers from the store when logging on
+    #[must_use]
+    pub const fn refresh_on_logon(&self) -> bool {
+        self.refresh_on_logon
+    }
+
+    #[must_use]
+    pub fn store_path(&self) -> Option<&Path> {
+        self.store_path.as_deref()
+    }
+
+    /// How far the SendingTime (52) of received messages may be from now,
+    /// `None` to not check it
+    #[must_use]
+    pub const fn max_latency(&self) -> Option<Duration> {
+        self.max_latency
+    }
+
+    /// How long to wait for the counterparty's Logout after sending ours
+    /// before closing the connection anyway
+    #[must_use]
+    pub const fn logout_timeout(&self) -> Duration {
+        self.logout_timeout
+    }
 }
 
-#[derive(Clone, Debug)]
+/// When a session runs. Without times it runs all the time; with times only,
+/// daily between them, a full day when they are equal; with days as well,
+/// weekly from `start_day` at `start_time` to `end_day` at `end_time`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct ScheduleSettings {
-    // daily, weekly, specified days
-// start time, stop time, timezone
+    pub start_time: Option<TimeOfDay>,
+    pub end_time: Option<TimeOfDay>,
+    pub start_day: Option<Weekday>,
+    pub end_day: Option<Weekday>,
+    /// Zone the times are in, e.g. `America/New_York`; UTC when `None`
+    pub time_zone: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
 }
 
 #[derive(Clone, Debug)]
 pub struct Settings {
     pub begin_string: BeginString,
     pub sender_comp_id: String,
+    pub sender_sub_id: Option<String>,
+    pub sender_location_id: Option<String>,
     pub target_comp_id: String,
+    pub target_sub_id: Option<String>,
+    pub target_location_id: Option<String>,
+    /// Tells apart sessions with the same CompIDs
+    pub session_qualifier: Option<String>,
     pub session_type: SessionType,
+    /// Application version of a FIXT.1.1 session, sent as DefaultApplVerID (1137) on Logon
+    pub default_appl_ver_id: Option<BeginString>,
+    pub framing: Framing,
+    /// Sent on Logon by initiators
+    pub credentials: Option<Credentials>,
     pub session_settings: SessionSettings,
     pub connection_settings: ConnectionSettings,
     pub schedule_settings: ScheduleSettings,
 }
 
+impl Settings {
+    /// Check the settings are complete and consistent, naming the first
+    /// setting that isn't by its configuration file key.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` for the first invalid setting found
+    pub fn validate(&self) -> Result<(), InvalidSetting> {
+        if self.sender_comp_id.trim().is_empty() {
+            return Err(InvalidSetting::new("SenderCompID", "must not be empty"));
+        }
+        if self.target_comp_id.trim().is_empty() {
+            return Err(InvalidSetting::new("TargetCompID", "must not be empty"));
+        }
+        if self.sender_comp_id == self.target_comp_id {
+            return Err(InvalidSetting::new(
+                "TargetCompID",
+                "must differ from SenderCompID",
+            ));
+        }
+        if self.connection_settings.heartbeat_interval.is_zero() {
+            return Err(InvalidSetting::new(
+                "HeartBtInt",
+                "must be greater than zero",
+            ));
+        }
+        // sent on Logon as HeartBtInt (108), in seconds
+        if self.connection_settings.heartbeat_interval.subsec_nanos() != 0 {
+            return Err(InvalidSetting::new(
+                "HeartBtInt",
+                "must be a whole number of seconds",
+            ));
+        }
+        match self.session_type {
+            SessionType::Initiator => {
+                if self.connection_settings.endpoints.is_empty() {
+                    return Err(InvalidSetting::new(
+                        "SocketConnectHost",
+                        "is needed for an initiator to connect to",
+                    ));
+                }
+                if let Some(endpoint) = self
+                    .connection_settings
+                    .endpoints
+                    .iter()
+                    .find(|endpoint| port(endpoint).is_none())
+                {
+                    return Err(InvalidSetting::new(
+                        "SocketConnectPort",
+                        &format!("is missing from {}", endpoint),
+                    ));
+                }
+                self.connection_settings.reconnect.validate()?;
+            }
+            SessionType::Acceptor => {
+                if self
+                    .connection_settings
+                    .accept_address
+                    .as_deref()
+                    .and_then(port)
+                    .is_none()
+                {
+                    return Err(InvalidSetting::new(
+                        "SocketAcceptPort",
+                        "is needed for an acceptor to listen on",
+                    ));
+                }
+            }
+        }
+        self.schedule_settings.validate()?;
+        if self.session_settings.max_latency == Some(Duration::ZERO) {
+            return Err(InvalidSetting::new(
+                "MaxLatency",
+                "must be greater than zero",
+            ));
+        }
+        if self.session_settings.reset_on_logon && self.session_settings.refresh_on_logon {
+            return Err(InvalidSetting::new(
+                "RefreshOnLogon",
+                "conflicts with ResetOnLogon, which discards the sequence numbers it reloads",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The port of a host:port address, if it has a valid one
+fn port(address: &str) -> Option<u16> {
+    let (_host, port) = address.rsplit_once(':')?;
+    port.parse::<u16>().ok().filter(|port| *port > 0)
+}
+
+impl ScheduleSettings {
+    fn validate(&self) -> Result<(), InvalidSetting> {
+        for (setting, time) in [("StartTime", self.start_time), ("EndTime", self.end_time)] {
+            if time.is_some_and(|time| time.hour > 23 || time.minute > 59 || time.second > 59) {
+                return Err(InvalidSetting::new(setting, "is not a time of day"));
+            }
+        }
+        match (self.start_time, self.end_time) {
+            (Some(_), None) => {
+                return Err(InvalidSetting::new("EndTime", "is needed with StartTime"))
+            }
+            (None, Some(_)) => {
+                return Err(InvalidSetting::new("StartTime", "is needed with EndTime"))
+            }
+            _ => {}
+        }
+        match (self.start_day, self.end_day) {
+            (Some(_), None) => Err(InvalidSetting::new("EndDay", "is needed with StartDay")),
+            (None, Some(_)) => Err(InvalidSetting::new("StartDay", "is needed with EndDay")),
+            (Some(_), Some(_)) if self.start_time.is_none() => Err(InvalidSetting::new(
+                "StartTime",
+                "is needed for a weekly schedule",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A setting that is missing, out of range or inconsistent with another
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidSetting {
+    /// Configuration file key of the setting
+    pub setting: &'static str,
+    /// Reads on from the setting, as in "HeartBtInt must be greater than
+    /// zero"
+    pub reason: String,
+}
+
+impl InvalidSetting {
+    fn new(setting: &'static str, reason: &str) -> Self {
+        InvalidSetting {
+            setting,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidSetting {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.setting, self.reason)
+    }
+}
+
 pub struct SettingsBuilder {
     begin_string: BeginString,
     sender_comp_id: String,
+    sender_sub_id: Option<String>,
+    sender_location_id: Option<String>,
     target_comp_id: String,
+    target_sub_id: Option<String>,
+    target_location_id: Option<String>,
+    session_qualifier: Option<String>,
     session_type: SessionType,
-    heartbeat_interval_ms: u16,
+    default_appl_ver_id: Option<BeginString>,
+    framing: Framing,
+    credentials: Option<Credentials>,
+    heartbeat_interval: Duration,
+    endpoints: Vec<String>,
+    reconnect: ReconnectSettings,
+    accept_address: Option<String>,
+    tls: Option<TlsSettings>,
     reset_on_logon: bool,
+    reset_on_logout: bool,
+    reset_on_disconnect: bool,
+    refresh_on_logon: bool,
+    store_path: Option<PathBuf>,
+    max_latency: Option<Duration>,
+    logout_timeout: Duration,
+    schedule: ScheduleSettings,
 }
 
 impl SettingsBuilder {
@@ -59,15 +453,97 @@ impl SettingsBuilder {
         SettingsBuilder {
             begin_string,
             sender_comp_id,
+            sender_sub_id: None,
+            sender_location_id: None,
             target_comp_id,
+            target_sub_id: None,
+            target_location_id: None,
+            session_qualifier: None,
             session_type,
-            heartbeat_interval_ms: 5_000,
+            default_appl_ver_id: None,
+            framing: Framing::default(),
+            credentials: None,
+            heartbeat_interval: Duration::from_secs(5),
+            endpoints: vec![],
+            reconnect: ReconnectSettings::default(),
+            accept_address: None,
+            tls: None,
             reset_on_logon: false,
+            reset_on_logout: false,
+            reset_on_disconnect: false,
+            refresh_on_logon: false,
+            store_path: None,
+            max_latency: None,
+            logout_timeout: Duration::from_secs(2),
+            schedule: ScheduleSettings::default(),
         }
     }
 
-    pub fn with_hb_interval(&mut self, interval: u16) -> &mut Self {
-        self.heartbeat_interval_ms = interval;
+    pub fn with_sender_sub_id(&mut self, sender_sub_id: &str) -> &mut Self {
+        self.sender_sub_id = Some(sender_sub_id.to_string());
+        self
+    }
+
+    pub fn with_sender_location_id(&mut self, sender_location_id: &str) -> &mut Self {
+        self.sender_location_id = Some(sender_location_id.to_string());
+        self
+    }
+
+    pub fn with_target_sub_id(&mut self, target_sub_id: &str) -> &mut Self {
+        self.target_sub_id = Some(target_sub_id.to_string());
+        self
+    }
+
+    pub fn with_target_location_id(&mut self, target_location_id: &str) -> &mut Self {
+        self.target_location_id = Some(target_location_id.to_string());
+        self
+    }
+
+    pub fn with_session_qualifier(&mut self, session_qualifier: &str) -> &mut Self {
+        self.session_qualifier = Some(session_qualifier.to_string());
+        self
+    }
+
+    pub fn with_hb_interval(&mut self, interval: Duration) -> &mut Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    pub fn with_default_appl_ver_id(&mut self, appl_ver_id: BeginString) -> &mut Self {
+        self.default_appl_ver_id = Some(appl_ver_id);
+        self
+    }
+
+    pub fn with_framing(&mut self, framing: Framing) -> &mut Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn with_credentials(&mut self, credentials: Credentials) -> &mut Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Add an endpoint (host:port) to connect to; the first is the primary
+    /// and later ones are tried in turn when it fails
+    pub fn with_endpoint(&mut self, endpoint: &str) -> &mut Self {
+        self.endpoints.push(endpoint.to_string());
+        self
+    }
+
+    pub fn with_reconnect(&mut self, reconnect: ReconnectSettings) -> &mut Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Address (host:port) an acceptor listens on
+    pub fn with_accept_address(&mut self, address: &str) -> &mut Self {
+        self.accept_address = Some(address.to_string());
+        self
+    }
+
+    pub fn with_tls(&mut self, tls: TlsSettings) -> &mut Self {
+        self.tls = Some(tls);
         self
     }
 
@@ -76,26 +552,172 @@ impl SettingsBuilder {
         self
     }
 
+    pub fn with_reset_on_logout(&mut self, reset_on_logout: bool) -> &mut Self {
+        self.reset_on_logout = reset_on_logout;
+        self
+    }
+
+    pub fn with_reset_on_disconnect(&mut self, reset_on_disconnect: bool) -> &mut Self {
+        self.reset_on_disconnect = reset_on_disconnect;
+        self
+    }
+
+    pub fn with_refresh_on_logon(&mut self, refresh_on_logon: bool) -> &mut Self {
+        self.refresh_on_logon = refresh_on_logon;
+        self
+    }
+
+    pub fn with_store_path(&mut self, store_path: &Path) -> &mut Self {
+        self.store_path = Some(store_path.to_path_buf());
+        self
+    }
+
+    /// Refuse received messages whose SendingTime (52) is further than
+    /// `max_latency` from now
+    pub fn with_max_latency(&mut self, max_latency: Duration) -> &mut Self {
+        self.max_latency = Some(max_latency);
+        self
+    }
+
+    pub fn with_logout_timeout(&mut self, logout_timeout: Duration) -> &mut Self {
+        self.logout_timeout = logout_timeout;
+        self
+    }
+
+    pub fn with_schedule(&mut self, schedule: ScheduleSettings) -> &mut Self {
+        self.schedule = schedule;
+        self
+    }
+
     #[must_use]
     pub fn build(&self) -> Settings {
         Settings {
             begin_string: self.begin_string,
             sender_comp_id: self.sender_comp_id.clone(),
+            sender_sub_id: self.sender_sub_id.clone(),
+            sender_location_id: self.sender_location_id.clone(),
             target_comp_id: self.target_comp_id.clone(),
+            target_sub_id: self.target_sub_id.clone(),
+            target_location_id: self.target_location_id.clone(),
+            session_qualifier: self.session_qualifier.clone(),
             session_type: self.session_type,
+            default_appl_ver_id: self.default_appl_ver_id,
+            framing: self.framing,
+            credentials: self.credentials.clone(),
             session_settings: SessionSettings {
                 reset_on_logon: self.reset_on_logon,
+                reset_on_logout: self.reset_on_logout,
+                reset_on_disconnect: self.reset_on_disconnect,
+                refresh_on_logon: self.refresh_on_logon,
+                store_path: self.store_path.clone(),
+                max_latency: self.max_latency,
+                logout_timeout: self.logout_timeout,
             },
             connection_settings: ConnectionSettings {
-                heartbeat_interval: self.heartbeat_interval_ms,
+                heartbeat_interval: self.heartbeat_interval,
+                endpoints: self.endpoints.clone(),
+                reconnect: self.reconnect.clone(),
+                accept_address: self.accept_address.clone(),
+                tls: self.tls.clone(),
             },
-            schedule_settings: ScheduleSettings {},
+            schedule_settings: self.schedule.clone(),
         }
     }
 }
 
+/// How messages are delimited on the wire
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Framing {
+    /// Plain tag=value, delimited by BodyLength (9) and CheckSum (10)
+    #[default]
+    TagValue,
+    /// Every message behind a FIX Simple Open Framing Header
+    Sofh,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum SessionType {
     Initiator,
     Acceptor,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::session::settings::ReconnectSettings;
+
+    #[test]
+    fn backoff_grows_to_its_cap_with_jitter() {
+        let reconnect = ReconnectSettings {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1_000),
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_attempts: None,
+        };
+        let delays: Vec<u128> = (1..=6)
+            .map(|attempt| reconnect.backoff(attempt, 0.5).as_millis())
+            .collect();
+        assert_eq!(vec![100, 200, 400, 800, 1_000, 1_000], delays);
+
+        // up to half of the delay either way
+        assert_eq!(50, reconnect.backoff(1, 0.0).as_millis());
+        assert_eq!(1_499, reconnect.backoff(6, 0.999).as_millis());
+    }
+
+    #[test]
+    fn backoff_settings_are_validated() {
+        let invalid = |reconnect: ReconnectSettings| reconnect.validate().unwrap_err().reason;
+        assert_eq!(
+            "must be greater than zero",
+            invalid(ReconnectSettings {
+                initial_backoff: Duration::ZERO,
+                ..ReconnectSettings::default()
+            })
+        );
+        assert_eq!(
+            "must not be longer than the maximum backoff",
+            invalid(ReconnectSettings {
+                initial_backoff: Duration::from_secs(60),
+                ..ReconnectSettings::default()
+            })
+        );
+        assert_eq!(
+            "can't back off by a multiplier below 1",
+            invalid(ReconnectSettings {
+                multiplier: 0.5,
+                ..ReconnectSettings::default()
+            })
+        );
+        assert_eq!(
+            "can't back off by a multiplier below 1",
+            invalid(ReconnectSettings {
+                multiplier: f64::NAN,
+                ..ReconnectSettings::default()
+            })
+        );
+        assert_eq!(
+            "can't vary by a jitter outside 0 to 1",
+            invalid(ReconnectSettings {
+                jitter: 1.5,
+                ..ReconnectSettings::default()
+            })
+        );
+        assert!(ReconnectSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn backoff_does_not_panic_on_unvalidated_settings() {
+        let reconnect = ReconnectSettings {
+            multiplier: -2.0,
+            ..ReconnectSettings::default()
+        };
+        assert_eq!(Duration::ZERO, reconnect.backoff(2, 0.5));
+        let reconnect = ReconnectSettings {
+            multiplier: f64::NAN,
+            ..ReconnectSettings::default()
+        };
+        assert_eq!(reconnect.max_backoff, reconnect.backoff(2, 0.5));
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::cracker;
+use crate::model::field::ConversionError;
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+use crate::model::BeginString;
+
+/// FIXT.1.1 header fields which only exist to carry the application version
+const APPL_VER_ID_TAGS: [u32; 3] = [1128, 1129, 1137];
+
+/// Converts messages from the version they were received in to another, e.g.
+/// orders from an OMS speaking FIX.4.4 to a broker speaking FIX.4.2.
+///
+/// Fields are matched by tag, so fields which were only renamed between
+/// versions carry over unchanged. Fields whose type changed are converted
+/// with `Field::to_version`, and fields the target version doesn't define are
+/// dropped and reported, unless mapped to another tag with `map_tag`. Tags
+/// the source version doesn't define either, such as venue-specific fields,
+/// are kept as they are.
+///
+/// Translating to FIX 5.x produces a FIXT.1.1 message with the application
+/// version in `ApplVerID` (1128); translating from one reads the application
+/// version back out of it.
+#[derive(Debug, Clone)]
+pub struct Translator {
+    to: BeginString,
+    default_appl_ver_id: Option<BeginString>,
+    tag_map: HashMap<u32, u32>,
+}
+
+/// A translated message and what had to be left out of it
+#[derive(Debug)]
+pub struct Translation {
+    pub message: Message,
+    pub dropped: Vec<DroppedField>,
+}
+
+/// A field which has no place in the translated message
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DroppedField {
+    pub field: Field,
+    pub reason: ConversionError,
+}
+
+impl Translator {
+    /// Translate to `to`; FIX 5.x versions are sent over FIXT.1.1
+    #[must_use]
+    pub fn new(to: BeginString) -> Self {
+        Self {
+            to,
+            default_appl_ver_id: None,
+            tag_map: HashMap::default(),
+        }
+    }
+
+    /// Application version of received FIXT.1.1 messages without an
+    /// `ApplVerID` (1128), as agreed on the session's Logon
+    pub fn with_default_appl_ver_id(&mut self, appl_ver_id: BeginString) -> &mut Self {
+        self.default_appl_ver_id = Some(appl_ver_id);
+        self
+    }
+
+    /// Carry `from` over as `to`, e.g. FIX.4.2's Rule80A (47) as FIX.4.4's
+    /// OrderCapacity (528)
+    pub fn map_tag(&mut self, from: u32, to: u32) -> &mut Self {
+        self.tag_map.insert(from, to);
+        self
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the version `message` is in can't be determined
+    pub fn translate(&self, message: &Message) -> Result<Translation, TranslationError> {
+        let source = Versions::of_message(message, self.default_appl_ver_id)?;
+        let target = Versions::of(self.to, message);
+
+        let mut translated = Message::default();
+        let mut dropped = vec![];
+
+        let fields = message
+            .header()
+            .iter()
+            .chain(message.body().iter())
+            .chain(message.trailer().iter());
+        for field in fields {
+            let tag = field.tag();
+            if tag == 8 || APPL_VER_ID_TAGS.contains(&tag) {
+                continue;
+            }
+
+            let field = match self.tag_map.get(&tag) {
+                Some(mapped) => retag(field, *mapped),
+                None => field.clone(),
+            };
+            let source_version = source.for_tag(tag);
+            let target_version = target.for_tag(field.tag());
+            match field.to_version(target_version) {
+                Ok(converted) => translated.set_field(converted),
+                Err(ConversionError::NotDefined { .. })
+                    if Field::field_type(source_version, tag).is_none() =>
+                {
+                    translated.set_field(field);
+                }
+                Err(reason) => dropped.push(DroppedField { field, reason }),
+            }
+        }
+
+        translated.set_field(Field::String(8, target.transport.into()));
+        if let (BeginString::Fixt11, Some(appl_ver_id)) = (target.transport, self.to.appl_ver_id())
+        {
+            if is_logon(message) {
+                translated.set_field(Field::String(1137, appl_ver_id.to_string()));
+            } else if !is_session_level(message) {
+                translated.set_field(Field::String(1128, appl_ver_id.to_string()));
+            }
+        }
+
+        Ok(Translation {
+            message: translated,
+            dropped,
+        })
+    }
+}
+
+/// Versions the header and body of a message are in
+struct Versions {
+    transport: BeginString,
+    application: BeginString,
+}
+
+impl Versions {
+    fn of(begin_string: BeginString, message: &Message) -> Self {
+        match begin_string {
+            BeginString::Fix50 | BeginString::Fix50Sp1 | BeginString::Fix50Sp2 => Self {
+                transport: BeginString::Fixt11,
+                application: if is_session_level(message) {
+                    BeginString::Fixt11
+                } else {
+                    begin_string
+                },
+            },
+            _ => Self {
+                transport: begin_string,
+                application: begin_string,
+            },
+        }
+    }
+
+    fn of_message(
+        message: &Message,
+        default_appl_ver_id: Option<BeginString>,
+    ) -> Result<Self, TranslationError> {
+        let begin_string = message
+            .get_field_safe(8)
+            .map_err(|_| TranslationError::MissingBeginString)?
+            .value_string();
+        let begin_string = BeginString::try_from(begin_string.as_str())
+            .map_err(|_| TranslationError::UnknownBeginString(begin_string))?;
+        if begin_string != BeginString::Fixt11 || is_session_level(message) {
+            return Ok(Self {
+                transport: begin_string,
+                application: begin_string,
+            });
+        }
+
+        let application = match message.get_field_safe(1128) {
+            Ok(appl_ver_id) => {
+                let appl_ver_id = appl_ver_id.value_string();
+                BeginString::from_appl_ver_id(&appl_ver_id)
+                    .ok_or(TranslationError::UnknownApplVerID(appl_ver_id))?
+            }
+            Err(_) => default_appl_ver_id.ok_or(TranslationError::MissingApplVerID)?,
+        };
+        Ok(Self {
+            transport: begin_string,
+            application,
+        })
+    }
+
+    const fn for_tag(&self, tag: u32) -> BeginString {
+        if Field::is_header_tag(tag) || Field::is_trailer_tag(tag) {
+            self.transport
+        } else {
+            self.application
+        }
+    }
+}
+
+fn is_session_level(message: &Message) -> bool {
+    message
+        .get_field_safe(35)
+        .is_ok_and(|msg_type| cracker::is_session_level(&msg_type.value_string()))
+}
+
+fn is_logon(message: &Message) -> bool {
+    message
+        .get_field_safe(35)
+        .is_ok_and(|msg_type| msg_type.value_string() == "A")
+}
+
+fn retag(field: &Field, tag: u32) -> Field {
+    match field {
+        Field::String(_, v) => Field::String(tag, v.clone()),
+        Field::Int(_, v) => Field::Int(tag, *v),
+        Field::Char(_, v) => Field::Char(tag, *v),
+        Field::Decimal(_, v) => Field::Decimal(tag, *v),
+    }
+}
+
+/// The version a message is in could not be determined
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TranslationError {
+    MissingBeginString,
+    UnknownBeginString(String),
+    MissingApplVerID,
+    UnknownApplVerID(String),
+}
+
+impl fmt::Display for TranslationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TranslationError::MissingBeginString => write!(f, "BeginString (8) is missing"),
+            TranslationError::UnknownBeginString(v) => {
+                write!(f, "BeginString (8) {:?} is not a known version", v)
+            }
+            TranslationError::MissingApplVerID => write!(
+                f,
+                "FIXT.1.1 message has no ApplVerID (1128) and there's no default"
+            ),
+            TranslationError::UnknownApplVerID(v) => {
+                write!(f, "ApplVerID (1128) {:?} is not a known version", v)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use crate::model::field::{ConversionError, FieldKind};
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+    use crate::model::BeginString;
+    use crate::translator::{DroppedField, TranslationError, Translator};
+
+    fn fix44_order() -> Message {
+        let mut order = Message::of_type("D");
+        order.set_field(Field::String(8, "FIX.4.4".to_string()));
+        order.set_field(Field::String(49, "OMS".to_string()));
+        order.set_field(Field::String(34, "7".to_string()));
+        order.set_field(Field::String(11, "order-1".to_string()));
+        order.set_field(Field::Char(54, '1'));
+        order.set_field(Field::Decimal(44, Decimal::new(1025, 2)));
+        order.set_field(Field::Int(38, 100));
+        order.set_field(Field::Char(528, 'A'));
+        order.set_field(Field::String(20_001, "venue".to_string()));
+        order
+    }
+
+    #[test]
+    fn fix44_to_fix42() {
+        let translation = Translator::new(BeginString::Fix42)
+            .translate(&fix44_order())
+            .unwrap();
+
+        // MsgSeqNum (34) is a SEQNUM in FIX.4.4 but an INT in FIX.4.2
+        assert_eq!(
+            "8=FIX.4.2|34=7|35=D|49=OMS|11=order-1|38=100|44=10.25|54=1|20001=venue|",
+            translation.message.to_string()
+        );
+        assert_eq!(Field::Int(34, 7), *translation.message.get_field(34));
+
+        // OrderCapacity (528) is new in FIX.4.3
+        assert_eq!(
+            vec![DroppedField {
+                field: Field::Char(528, 'A'),
+                reason: ConversionError::NotDefined {
+                    tag: 528,
+                    begin_string: BeginString::Fix42
+                }
+            }],
+            translation.dropped
+        );
+    }
+
+    #[test]
+    fn mapped_tags() {
+        let mut from_fix42 = Message::of_type("D");
+        from_fix42.set_field(Field::String(8, "FIX.4.2".to_string()));
+        from_fix42.set_field(Field::Char(47, 'A'));
+        from_fix42.set_field(Field::String(54, "12".to_string()));
+
+        let translation = Translator::new(BeginString::Fix44)
+            .map_tag(47, 528)
+            .translate(&from_fix42)
+            .unwrap();
+        assert_eq!(Field::Char(528, 'A'), *translation.message.get_field(528));
+        assert!(translation.message.get_field_safe(47).is_err());
+
+        // a value the target's type can't hold
+        assert_eq!(
+            vec![DroppedField {
+                field: Field::String(54, "12".to_string()),
+                reason: ConversionError::Incompatible {
+                    tag: 54,
+                    value: "12".to_string(),
+                    kind: FieldKind::Char
+                }
+            }],
+            translation.dropped
+        );
+    }
+
+    #[test]
+    fn to_and_from_fixt() {
+        let translation = Translator::new(BeginString::Fix50Sp2)
+            .translate(&fix44_order())
+            .unwrap();
+        let fix50 = translation.message;
+        assert_eq!("FIXT.1.1", fix50.get_field(8).as_str());
+        assert_eq!("9", fix50.get_field(1128).as_str());
+        assert_eq!(&Decimal::new(1025, 2), fix50.get_field(44).as_decimal());
+        assert!(translation.dropped.is_empty());
+
+        // and back, using the ApplVerID it now carries
+        let translation = Translator::new(BeginString::Fix44)
+            .translate(&fix50)
+            .unwrap();
+        assert!(translation.dropped.is_empty());
+        assert_eq!(fix44_order().to_string(), translation.message.to_string());
+
+        // session level messages are FIXT.1.1 throughout, Logon agrees the version
+        let mut logon = Message::of_type("A");
+        logon.set_field(Field::String(8, "FIX.4.4".to_string()));
+        logon.set_field(Field::Int(108, 30));
+        let logon = Translator::new(BeginString::Fix50Sp2)
+            .translate(&logon)
+            .unwrap()
+            .message;
+        assert_eq!("8=FIXT.1.1|35=A|108=30|1137=9|", logon.to_string());
+
+        let mut xml_non_fix = Message::of_type("n");
+        xml_non_fix.set_field(Field::String(8, "FIX.4.4".to_string()));
+        let xml_non_fix = Translator::new(BeginString::Fix50Sp2)
+            .translate(&xml_non_fix)
+            .unwrap()
+            .message;
+        assert_eq!("8=FIXT.1.1|35=n|", xml_non_fix.to_string());
+    }
+
+    #[test]
+    fn fixt_without_appl_ver_id() {
+        let mut order = Message::of_type("D");
+        order.set_field(Field::String(8, "FIXT.1.1".to_string()));
+        order.set_field(Field::Char(54, '1'));
+
+        assert_eq!(
+            TranslationError::MissingApplVerID,
+            Translator::new(BeginString::Fix40)
+                .translate(&order)
+                .unwrap_err()
+        );
+
+        let translation = Translator::new(BeginString::Fix40)
+            .with_default_appl_ver_id(BeginString::Fix50)
+            .translate(&order)
+            .unwrap();
+        assert_eq!("8=FIX.4.0|35=D|54=1|", translation.message.to_string());
+        assert_eq!(
+            Field::String(54, "1".to_string()),
+            *translation.message.get_field(54)
+        );
+    }
+}
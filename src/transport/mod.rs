@@ -0,0 +1,1344 @@
+pub mod tls;
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fmt::Formatter;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, trace, warn};
+
+use bytes::Bytes;
+
+use crate::cracker;
+use crate::cracker::{EncodingType, FrameError, SofhFrame};
+use crate::encoder;
+use crate::model::generated::fields::Field;
+use crate::model::message::Message;
+use crate::model::timestamp::format_utc_timestamp;
+use crate::model::BeginString;
+use crate::session::channel::{Outbound, SessionError, SessionEvent, SessionSender};
+use crate::session::settings::{ConnectionSettings, Framing, Settings};
+use crate::session::{check_sending_time, SendingTimeProblem, SessionID, State};
+use crate::transport::tls::TlsError;
+
+/// A connection to a counterparty, over plain TCP or TLS
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Stream for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// Connect to the acceptor at `addr`, completing the TLS handshake first
+/// when `settings` has TLS options.
+///
+/// # Errors
+///
+/// If the connection fails, the TLS options are invalid or the handshake fails.
+pub async fn connect(
+    addr: &str,
+    settings: &ConnectionSettings,
+) -> Result<Box<dyn Stream>, ConnectError> {
+    let stream = TcpStream::connect(addr).await.map_err(ConnectError::Io)?;
+    let Some(tls_settings) = settings.tls() else {
+        return Ok(Box::new(stream));
+    };
+    let connector = tls::connector(tls_settings)?;
+    let server_name = tls::server_name(tls_settings, addr)?;
+    let handshake = connector.connect(server_name, stream);
+    let stream = handshake_within(tls_settings.handshake_timeout, handshake).await?;
+    Ok(Box::new(stream))
+}
+
+/// Accepts initiators' connections, over TLS when the settings it was bound
+/// with have TLS options
+pub struct Listener {
+    listener: TcpListener,
+    acceptor: Option<TlsAcceptor>,
+    handshake_timeout: Duration,
+}
+
+impl Listener {
+    ///
+    /// # Errors
+    ///
+    /// If `addr` can't be bound or the TLS options are invalid.
+    pub async fn bind(addr: &str, settings: &ConnectionSettings) -> Result<Self, ConnectError> {
+        let acceptor = settings.tls().map(tls::acceptor).transpose()?;
+        let handshake_timeout = settings
+            .tls()
+            .map_or(Duration::ZERO, |tls| tls.handshake_timeout);
+        let listener = TcpListener::bind(addr).await.map_err(ConnectError::Io)?;
+        Ok(Listener {
+            listener,
+            acceptor,
+            handshake_timeout,
+        })
+    }
+
+    ///
+    /// # Errors
+    ///
+    /// If the address can't be read from the socket.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Wait for the next initiator and complete its TLS handshake.
+    ///
+    /// # Errors
+    ///
+    /// If accepting fails or the initiator fails the handshake, or doesn't
+    /// complete it within the handshake timeout; the listener can keep
+    /// accepting after any of these.
+    pub async fn accept(&self) -> Result<(Box<dyn Stream>, SocketAddr), ConnectError> {
+        let (stream, peer) = self.listener.accept().await.map_err(ConnectError::Io)?;
+        let Some(acceptor) = &self.acceptor else {
+            return Ok((Box::new(stream), peer));
+        };
+        let stream = handshake_within(self.handshake_timeout, acceptor.accept(stream)).await?;
+        Ok((Box::new(stream), peer))
+    }
+}
+
+async fn handshake_within<T>(
+    timeout: Duration,
+    handshake: impl Future<Output = io::Result<T>>,
+) -> Result<T, TlsError> {
+    match tokio::time::timeout(timeout, handshake).await {
+        Ok(stream) => stream.map_err(TlsError::Handshake),
+        Err(_) => Err(TlsError::Handshake(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "handshake timed out",
+        ))),
+    }
+}
+
+#[derive(Debug)]
+pub enum ConnectError {
+    Io(io::Error),
+    Tls(TlsError),
+    NoEndpoints,
+    /// Gave up after this many attempts
+    AttemptsExhausted(u32),
+}
+
+impl From<TlsError> for ConnectError {
+    fn from(e: TlsError) -> Self {
+        ConnectError::Tls(e)
+    }
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectError::Io(e) => write!(f, "connection failed: {}", e),
+            ConnectError::Tls(e) => write!(f, "{}", e),
+            ConnectError::NoEndpoints => write!(f, "no endpoints to connect to"),
+            ConnectError::AttemptsExhausted(attempts) => {
+                write!(f, "gave up connecting after {} attempts", attempts)
+            }
+        }
+    }
+}
+
+/// How a running session treats the messages on its connection
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransportSettings {
+    pub framing: Framing,
+    /// Refuse received messages whose SendingTime (52) is further than this
+    /// from now
+    pub max_latency: Option<Duration>,
+    /// Application version of FIXT.1.1 messages without an ApplVerID until
+    /// the counterparty's Logon says otherwise
+    pub default_appl_ver_id: Option<BeginString>,
+    /// Start every Logon from MsgSeqNum 1 with ResetSeqNumFlag (141)
+    pub reset_on_logon: bool,
+    /// Start from MsgSeqNum 1 again after a connection ends. Otherwise the
+    /// numbers carry on from one connection to the next.
+    pub reset_on_disconnect: bool,
+}
+
+impl TransportSettings {
+    #[must_use]
+    pub const fn from_settings(settings: &Settings) -> Self {
+        TransportSettings {
+            framing: settings.framing,
+            max_latency: settings.session_settings.max_latency(),
+            default_appl_ver_id: settings.default_appl_ver_id,
+            reset_on_logon: settings.session_settings.reset_on_logon(),
+            reset_on_disconnect: settings.session_settings.reset_on_disconnect(),
+        }
+    }
+}
+
+/// Run a session over an established connection.
+///
+/// Received messages are framed, cracked and delivered to `events`, so one
+/// receiver can serve every session. Messages queued on the returned
+/// `SessionSender` are stamped with the session's header and written out.
+/// Both sides delimit messages as `settings` say.
+///
+/// A received message that isn't addressed to the session, or whose
+/// SendingTime is out of the `max_latency` window, is answered with a Reject
+/// and a Logout, after which the connection is closed. It is also closed when
+/// the sender is dropped.
+pub fn spawn_session<R, W>(
+    session_id: SessionID,
+    settings: TransportSettings,
+    reader: R,
+    writer: W,
+    events: mpsc::Sender<SessionEvent>,
+) -> SessionSender
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let sender = SessionSender::new(session_id.clone(), tx);
+    tokio::spawn(async move {
+        let mut seq_num = OutboundSeqNum::new();
+        run_connection(
+            session_id,
+            settings,
+            reader,
+            writer,
+            &mut rx,
+            &mut seq_num,
+            events,
+        )
+        .await;
+    });
+    sender
+}
+
+/// Run an initiator session that connects itself, through
+/// [`connect_with_failover`], and reconnects whenever the connection drops.
+///
+/// Reconnecting waits out the reconnect backoff, which keeps growing across
+/// connections until one of them gets a Logon back.
+///
+/// Messages queued on the returned `SessionSender` while disconnected go out
+/// once a connection is up again. The session stops when the sender is
+/// dropped, `events` is closed or reconnecting gives up.
+pub fn spawn_initiator(
+    session_id: SessionID,
+    settings: TransportSettings,
+    connection: ConnectionSettings,
+    events: mpsc::Sender<SessionEvent>,
+) -> SessionSender {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let sender = SessionSender::new(session_id.clone(), tx);
+    tokio::spawn(run_initiator(session_id, settings, connection, rx, events));
+    sender
+}
+
+/// The connections of an initiator session, as run by [`spawn_initiator`]
+pub(crate) async fn run_initiator(
+    session_id: SessionID,
+    settings: TransportSettings,
+    connection: ConnectionSettings,
+    mut outbound: mpsc::UnboundedReceiver<Outbound>,
+    events: mpsc::Sender<SessionEvent>,
+) {
+    let reconnect = connection.reconnect();
+    // connections in a row that ended without logging on
+    let mut attempt = 0;
+    let mut seq_num = OutboundSeqNum::new();
+    while !events.is_closed() {
+        let Ok(stream) = connect_with_failover(&session_id, &connection, &events).await else {
+            break;
+        };
+        let (reader, writer) = tokio::io::split(stream);
+        let end = run_connection(
+            session_id.clone(),
+            settings,
+            reader,
+            writer,
+            &mut outbound,
+            &mut seq_num,
+            events.clone(),
+        )
+        .await;
+        seq_num.connection_ended(settings);
+        if end.outbound_closed {
+            break;
+        }
+        attempt = if end.logged_on { 1 } else { attempt + 1 };
+        let delay = reconnect.backoff(attempt, random());
+        debug!(%session_id, attempt, ?delay, "connection ended, reconnecting");
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Serve an acceptor session on `listener`, one initiator connection at a
+/// time, until `events` is closed
+pub(crate) async fn run_acceptor(
+    session_id: SessionID,
+    settings: TransportSettings,
+    listener: Listener,
+    mut outbound: mpsc::UnboundedReceiver<Outbound>,
+    events: mpsc::Sender<SessionEvent>,
+) {
+    let mut seq_num = OutboundSeqNum::new();
+    while !events.is_closed() {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(%session_id, error = %e, "could not accept connection");
+                let _ = events
+                    .send(SessionEvent::Error {
+                        session_id: session_id.clone(),
+                        error: SessionError::Accept(e),
+                    })
+                    .await;
+                continue;
+            }
+        };
+        debug!(%session_id, %peer, "accepted");
+        let _ = events
+            .send(SessionEvent::Connected {
+                session_id: session_id.clone(),
+                endpoint: peer.to_string(),
+            })
+            .await;
+        let (reader, writer) = tokio::io::split(stream);
+        let end = run_connection(
+            session_id.clone(),
+            settings,
+            reader,
+            writer,
+            &mut outbound,
+            &mut seq_num,
+            events.clone(),
+        )
+        .await;
+        seq_num.connection_ended(settings);
+        if end.outbound_closed {
+            break;
+        }
+    }
+}
+
+/// Read and write one connection until it closes, then report the session
+/// logged out
+async fn run_connection<R, W>(
+    session_id: SessionID,
+    settings: TransportSettings,
+    reader: R,
+    writer: W,
+    outbound: &mut mpsc::UnboundedReceiver<Outbound>,
+    seq_num: &mut OutboundSeqNum,
+    events: mpsc::Sender<SessionEvent>,
+) -> ConnectionEnd
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (replies_tx, mut replies) = mpsc::unbounded_channel();
+    let logged_on = AtomicBool::new(false);
+    let read = read_loop(
+        session_id.clone(),
+        settings,
+        reader,
+        replies_tx,
+        events.clone(),
+        &logged_on,
+    );
+    let write = write_loop(
+        session_id.clone(),
+        settings,
+        writer,
+        outbound,
+        &mut replies,
+        seq_num,
+        events.clone(),
+    );
+    tokio::pin!(read, write);
+    let outbound_closed = tokio::select! {
+        // let the writer send what the reader replied before closing
+        () = &mut read => write.await,
+        closed = &mut write => closed,
+    };
+
+    debug!(%session_id, "disconnected");
+    let _ = events
+        .send(SessionEvent::StateChanged {
+            session_id,
+            state: State::LoggedOut,
+        })
+        .await;
+    ConnectionEnd {
+        outbound_closed,
+        logged_on: logged_on.load(Ordering::Relaxed),
+    }
+}
+
+/// Outbound MsgSeqNum (34) of a session, kept from one connection to the
+/// next
+struct OutboundSeqNum {
+    next: i32,
+    /// Gone back to 1 since the counterparty last saw it, which the next
+    /// Logon tells it with ResetSeqNumFlag (141)
+    reset: bool,
+}
+
+impl OutboundSeqNum {
+    const fn new() -> Self {
+        OutboundSeqNum {
+            next: 1,
+            reset: false,
+        }
+    }
+
+    /// After a connection, start from 1 again if `settings` say so
+    fn connection_ended(&mut self, settings: TransportSettings) {
+        if settings.reset_on_disconnect && self.next > 1 {
+            self.next = 1;
+            self.reset = true;
+        }
+    }
+}
+
+/// How a connection of a session ended
+struct ConnectionEnd {
+    /// The session's `outbound` was closed, rather than the connection ending
+    outbound_closed: bool,
+    /// A Logon was received and passed the session-level checks
+    logged_on: bool,
+}
+
+/// Connect to the endpoints of `settings` in turn, starting over from the
+/// primary after the last, until one accepts or the attempts run out.
+///
+/// Failed attempts back off as the reconnect settings say. Every attempt is
+/// reported on `events` as `Connecting`, then `Connected` or `ConnectFailed`.
+///
+/// # Errors
+///
+/// `AttemptsExhausted` once `max_attempts` have failed, each failure being
+/// on `events`, or `NoEndpoints` if there is nothing to connect to.
+pub async fn connect_with_failover(
+    session_id: &SessionID,
+    settings: &ConnectionSettings,
+    events: &mpsc::Sender<SessionEvent>,
+) -> Result<Box<dyn Stream>, ConnectError> {
+    let endpoints = settings.endpoints();
+    if endpoints.is_empty() {
+        return Err(ConnectError::NoEndpoints);
+    }
+    let reconnect = settings.reconnect();
+    for (attempt, endpoint) in (1..).zip(endpoints.iter().cycle()) {
+        let _ = events
+            .send(SessionEvent::Connecting {
+                session_id: session_id.clone(),
+                endpoint: endpoint.clone(),
+                attempt,
+            })
+            .await;
+        let error = match connect(endpoint, settings).await {
+            Ok(stream) => {
+                debug!(%session_id, endpoint, attempt, "connected");
+                let _ = events
+                    .send(SessionEvent::Connected {
+                        session_id: session_id.clone(),
+                        endpoint: endpoint.clone(),
+                    })
+                    .await;
+                return Ok(stream);
+            }
+            Err(e) => e,
+        };
+
+        let exhausted = reconnect.max_attempts.is_some_and(|max| attempt >= max);
+        let retry_in = (!exhausted).then(|| reconnect.backoff(attempt, random()));
+        warn!(%session_id, endpoint, attempt, error = %error, ?retry_in, "could not connect");
+        let _ = events
+            .send(SessionEvent::ConnectFailed {
+                session_id: session_id.clone(),
+                endpoint: endpoint.clone(),
+                attempt,
+                error,
+                retry_in,
+            })
+            .await;
+        match retry_in {
+            Some(delay) => tokio::time::sleep(delay).await,
+            None => return Err(ConnectError::AttemptsExhausted(attempt)),
+        }
+    }
+    unreachable!("endpoints cycle forever")
+}
+
+/// Uniformly distributed in `[0, 1)`, for jitter
+fn random() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1_u64 << 53) as f64
+}
+
+/// Ends when the connection does or a received message is refused, dropping
+/// `replies` to tell the writer. Sets `logged_on` once a Logon passes the
+/// session-level checks.
+async fn read_loop<R>(
+    session_id: SessionID,
+    settings: TransportSettings,
+    mut reader: R,
+    replies: mpsc::UnboundedSender<Message>,
+    events: mpsc::Sender<SessionEvent>,
+    logged_on: &AtomicBool,
+) where
+    R: AsyncRead + Unpin,
+{
+    let mut buf = BytesMut::with_capacity(4096);
+    // application version of FIXT.1.1 messages without an ApplVerID, as
+    // configured and then agreed on Logon
+    let mut default_appl_ver_id = settings.default_appl_ver_id;
+    'connection: loop {
+        match reader.read_buf(&mut buf).await {
+            Ok(0) => break,
+            Ok(_) => loop {
+                let mut end_connection = false;
+                let event = match next_frame(settings.framing, &mut buf) {
+                    Ok(Some(frame)) => {
+                        match cracker::crack_message_with(&frame, default_appl_ver_id) {
+                            Ok(message) => {
+                                trace!(%session_id, message = %message.redacted(), "received");
+                                if let Some(version) = logon_appl_ver_id(&message) {
+                                    default_appl_ver_id = Some(version);
+                                }
+                                match check_received(&session_id, &message, settings.max_latency) {
+                                    Ok(()) => {
+                                        if is_logon(&message) {
+                                            logged_on.store(true, Ordering::Relaxed);
+                                        }
+                                        SessionEvent::Message {
+                                            session_id: session_id.clone(),
+                                            message,
+                                        }
+                                    }
+                                    Err(refusal) => {
+                                        warn!(%session_id, error = %refusal.error, "refused message, logging out");
+                                        for reply in refusal.replies(&session_id, &message) {
+                                            let _ = replies.send(reply);
+                                        }
+                                        end_connection = true;
+                                        SessionEvent::Error {
+                                            session_id: session_id.clone(),
+                                            error: refusal.error,
+                                        }
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                warn!(%session_id, error = %e, "could not parse message");
+                                SessionEvent::Error {
+                                    session_id: session_id.clone(),
+                                    error: SessionError::Parse(e),
+                                }
+                            }
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!(%session_id, error = %e, "could not frame message");
+                        // SOFH has nothing to resync on after a bad length
+                        end_connection = matches!(e, FrameError::InvalidMessageLength(_));
+                        SessionEvent::Error {
+                            session_id: session_id.clone(),
+                            error: SessionError::Framing(e),
+                        }
+                    }
+                };
+                if events.send(event).await.is_err() {
+                    // nobody is listening any more
+                    return;
+                }
+                if end_connection {
+                    break 'connection;
+                }
+            },
+            Err(e) => {
+                warn!(%session_id, error = %e, "read failed");
+                let _ = events
+                    .send(SessionEvent::Error {
+                        session_id: session_id.clone(),
+                        error: SessionError::Io(e),
+                    })
+                    .await;
+                break;
+            }
+        }
+    }
+}
+
+/// A received message failing the session-level checks
+struct Refusal {
+    error: SessionError,
+    /// SessionRejectReason (373)
+    reason: i32,
+    /// RefTagID (371)
+    ref_tag: u32,
+}
+
+impl Refusal {
+    /// The Reject of `received`, then the Logout ending the session
+    fn replies(&self, session_id: &SessionID, received: &Message) -> [Message; 2] {
+        let text = self.error.to_string();
+        let mut reject = Message::of_type("3");
+        if let Ok(seq_num) = received.get_field_safe(34) {
+            reject.set_field(Field::String(45, seq_num.value_string()));
+        }
+        // FIX.4.0 and FIX.4.1 Rejects only carry RefSeqNum and Text
+        if !matches!(session_id.begin_string.as_str(), "FIX.4.0" | "FIX.4.1") {
+            reject.set_field(Field::Int(
+                371,
+                i32::try_from(self.ref_tag).unwrap_or(i32::MAX),
+            ));
+            if let Ok(msg_type) = received.get_field_safe(35) {
+                reject.set_field(Field::String(372, msg_type.value_string()));
+            }
+            reject.set_field(Field::Int(373, self.reason));
+        }
+        reject.set_field(Field::String(58, text.clone()));
+
+        let mut logout = Message::of_type("5");
+        logout.set_field(Field::String(58, text));
+        [reject, logout]
+    }
+}
+
+/// Check a received message is addressed to the session and, with a
+/// `max_latency`, was sent recently enough
+fn check_received(
+    session_id: &SessionID,
+    message: &Message,
+    max_latency: Option<Duration>,
+) -> Result<(), Refusal> {
+    session_id.check_header(message).map_err(|e| Refusal {
+        ref_tag: e.tag,
+        error: SessionError::Header(e),
+        // CompID problem
+        reason: 9,
+    })?;
+    if let Some(max_latency) = max_latency {
+        check_sending_time(message, max_latency, SystemTime::now()).map_err(|e| Refusal {
+            reason: match e {
+                // Required tag missing
+                SendingTimeProblem::Missing => 1,
+                // Incorrect data format for value
+                SendingTimeProblem::Malformed { .. } => 6,
+                // SendingTime accuracy problem
+                SendingTimeProblem::Stale { .. } => 10,
+            },
+            error: SessionError::SendingTime(e),
+            ref_tag: 52,
+        })?;
+    }
+    Ok(())
+}
+
+/// Writes `replies` of the reader first, then `outbound`, until told to
+/// disconnect. Returns whether `outbound` was closed, rather than the
+/// connection ending.
+async fn write_loop<W>(
+    session_id: SessionID,
+    settings: TransportSettings,
+    mut writer: W,
+    outbound: &mut mpsc::UnboundedReceiver<Outbound>,
+    replies: &mut mpsc::UnboundedReceiver<Message>,
+    seq_num: &mut OutboundSeqNum,
+    events: mpsc::Sender<SessionEvent>,
+) -> bool
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = BytesMut::with_capacity(4096);
+    let outbound_closed = loop {
+        let mut message = tokio::select! {
+            biased;
+            reply = replies.recv() => match reply {
+                Some(reply) => reply,
+                // the reader is done with the connection
+                None => break false,
+            },
+            message = outbound.recv() => match message {
+                Some(Outbound::Message(message)) => message,
+                Some(Outbound::Disconnect) => {
+                    debug!(%session_id, "disconnecting");
+                    break false;
+                }
+                None => break true,
+            },
+        };
+        if is_logon(&message) && (settings.reset_on_logon || seq_num.reset) {
+            seq_num.next = 1;
+            seq_num.reset = false;
+            message.set_field(Field::Char(141, 'Y'));
+        }
+        stamp_header(&session_id, &mut message, seq_num.next);
+        seq_num.next += 1;
+
+        trace!(%session_id, message = %message.redacted(), "sending");
+        buf.clear();
+        match settings.framing {
+            Framing::TagValue => encoder::encode(&message, &mut buf),
+            Framing::Sofh => encoder::encode_sofh(&message, &mut buf),
+        }
+        if let Err(e) = writer.write_all(&buf).await {
+            warn!(%session_id, error = %e, "write failed");
+            let _ = events
+                .send(SessionEvent::Error {
+                    session_id,
+                    error: SessionError::Io(e),
+                })
+                .await;
+            return false;
+        }
+    };
+    let _ = writer.shutdown().await;
+    outbound_closed
+}
+
+fn next_frame(framing: Framing, buf: &mut BytesMut) -> Result<Option<Bytes>, FrameError> {
+    match framing {
+        Framing::TagValue => cracker::next_frame(buf),
+        Framing::Sofh => match cracker::next_sofh_frame(buf)? {
+            Some(SofhFrame {
+                encoding_type: EncodingType::TagValue,
+                payload,
+            }) => Ok(Some(payload)),
+            Some(frame) => Err(FrameError::UnsupportedEncoding(frame.encoding_type)),
+            None => Ok(None),
+        },
+    }
+}
+
+fn logon_appl_ver_id(message: &Message) -> Option<BeginString> {
+    if !is_logon(message) {
+        return None;
+    }
+    BeginString::from_appl_ver_id(&message.get_field_safe(1137).ok()?.value_string())
+}
+
+fn is_logon(message: &Message) -> bool {
+    message
+        .get_field_safe(35)
+        .is_ok_and(|msg_type| msg_type.as_str_safe().is_ok_and(|t| t == "A"))
+}
+
+fn stamp_header(session_id: &SessionID, message: &mut Message, seq_num: i32) {
+    for field in session_id.header_fields() {
+        message.set_field(field);
+    }
+    message.set_field(Field::Int(34, seq_num));
+    message.set_field(Field::String(52, format_utc_timestamp(SystemTime::now())));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use bytes::BytesMut;
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    use crate::cracker;
+    use crate::cracker::{EncodingType, FrameError};
+    use crate::encoder;
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+    use crate::model::timestamp::{format_utc_timestamp, parse_utc_timestamp};
+    use crate::model::BeginString;
+    use crate::session::channel::{SessionError, SessionEvent};
+    use crate::session::settings::{
+        ConnectionSettings, Framing, ReconnectSettings, SessionType, SettingsBuilder,
+    };
+    use crate::session::{SendingTimeProblem, SessionID, State};
+    use crate::transport::{
+        connect_with_failover, spawn_initiator, spawn_session, ConnectError, TransportSettings,
+    };
+
+    fn id(target: &str) -> SessionID {
+        SessionID {
+            begin_string: "FIX.4.2".to_string(),
+            sender_comp_id: "Rocks".to_string(),
+            target_comp_id: target.to_string(),
+            ..SessionID::default()
+        }
+    }
+
+    /// A message the exchange sends the `id("Exchange")` session
+    fn from_exchange(msg_type: &str, begin_string: &str) -> Message {
+        let mut message = Message::of_type(msg_type);
+        message.set_field(Field::String(8, begin_string.to_string()));
+        message.set_field(Field::String(49, "Exchange".to_string()));
+        message.set_field(Field::String(56, "Rocks".to_string()));
+        message
+    }
+
+    fn tag_value() -> TransportSettings {
+        TransportSettings::default()
+    }
+
+    #[tokio::test]
+    async fn sessions_fan_in_to_one_channel() {
+        let (events_tx, mut events) = mpsc::channel(16);
+
+        let (ours, mut exchange) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let exchange_sender = spawn_session(
+            id("Exchange"),
+            tag_value(),
+            reader,
+            writer,
+            events_tx.clone(),
+        );
+
+        let (ours, venue) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let _venue_sender = spawn_session(id("Venue"), tag_value(), reader, writer, events_tx);
+
+        // outbound: send from another task via a cloned handle
+        let sender = exchange_sender.clone();
+        tokio::spawn(async move {
+            sender.send(Message::of_type("0")).unwrap();
+        })
+        .await
+        .unwrap();
+
+        let mut buf = BytesMut::with_capacity(1024);
+        let frame = loop {
+            if let Some(frame) = cracker::next_frame(&mut buf).unwrap() {
+                break frame;
+            }
+            exchange.read_buf(&mut buf).await.unwrap();
+        };
+        let heartbeat = cracker::crack_message(&frame).unwrap();
+        assert_eq!("0", heartbeat.get_field(35).as_str());
+        assert_eq!(1, heartbeat.get_field(34).as_i32());
+        assert_eq!("Rocks", heartbeat.get_field(49).as_str());
+        assert_eq!("Exchange", heartbeat.get_field(56).as_str());
+
+        // inbound: the exchange replies
+        let mut reply = from_exchange("0", "FIX.4.2");
+        reply.set_field(Field::String(58, "pong".to_string()));
+        let mut wire = BytesMut::new();
+        encoder::encode(&reply, &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+
+        match events.recv().await.unwrap() {
+            SessionEvent::Message {
+                session_id,
+                message,
+            } => {
+                assert_eq!(id("Exchange"), session_id);
+                assert_eq!("pong", message.get_field(58).as_str());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // the venue disconnects
+        drop(venue);
+        match events.recv().await.unwrap() {
+            SessionEvent::StateChanged { session_id, state } => {
+                assert_eq!(id("Venue"), session_id);
+                assert_eq!(State::LoggedOut, state);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn fixt_session_uses_default_appl_ver_id_from_logon() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let (ours, mut exchange) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let mut session_id = id("Exchange");
+        session_id.begin_string = "FIXT.1.1".to_string();
+        let _sender = spawn_session(session_id, tag_value(), reader, writer, events_tx);
+
+        let mut logon = from_exchange("A", "FIXT.1.1");
+        logon.set_field(Field::String(1137, "6".to_string()));
+        let mut order = from_exchange("D", "FIXT.1.1");
+        order.set_field(Field::String(44, "10.5".to_string()));
+
+        let mut wire = BytesMut::new();
+        encoder::encode(&logon, &mut wire);
+        encoder::encode(&order, &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+
+        for expected in ["A", "D"] {
+            match events.recv().await.unwrap() {
+                SessionEvent::Message { message, .. } => {
+                    assert_eq!(expected, message.get_field(35).as_str());
+                    if expected == "D" {
+                        // typed as FIX.4.4's Price
+                        assert_eq!("10.5", message.get_field(44).as_decimal().to_string());
+                    }
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn fixt_session_uses_configured_default_appl_ver_id() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let (ours, mut exchange) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let mut session_id = id("Exchange");
+        session_id.begin_string = "FIXT.1.1".to_string();
+        let settings = TransportSettings {
+            default_appl_ver_id: Some(BeginString::Fix50Sp2),
+            ..TransportSettings::default()
+        };
+        let _sender = spawn_session(session_id, settings, reader, writer, events_tx);
+
+        // no Logon has said which version the order is in
+        let mut order = from_exchange("D", "FIXT.1.1");
+        order.set_field(Field::String(44, "10.5".to_string()));
+        let mut wire = BytesMut::new();
+        encoder::encode(&order, &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+
+        match events.recv().await.unwrap() {
+            SessionEvent::Message { message, .. } => {
+                assert_eq!("10.5", message.get_field(44).as_decimal().to_string());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn sofh_framed_session() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let (ours, mut exchange) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let sender = spawn_session(
+            id("Exchange"),
+            TransportSettings {
+                framing: Framing::Sofh,
+                ..TransportSettings::default()
+            },
+            reader,
+            writer,
+            events_tx,
+        );
+
+        sender.send(Message::of_type("0")).unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let frame = loop {
+            if let Some(frame) = cracker::next_sofh_frame(&mut buf).unwrap() {
+                break frame;
+            }
+            exchange.read_buf(&mut buf).await.unwrap();
+        };
+        assert_eq!(EncodingType::TagValue, frame.encoding_type);
+        let heartbeat = cracker::crack_message(&frame.payload).unwrap();
+        assert_eq!("Exchange", heartbeat.get_field(56).as_str());
+
+        // a binary message the session can't decode, then a tag=value one
+        let mut reply = from_exchange("0", "FIX.4.2");
+        reply.set_field(Field::String(58, "pong".to_string()));
+        let mut wire = BytesMut::new();
+        encoder::put_sofh(&mut wire, EncodingType::SbeLittleEndian, &[1, 2, 3]);
+        encoder::encode_sofh(&reply, &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+
+        match events.recv().await.unwrap() {
+            SessionEvent::Error {
+                error: SessionError::Framing(e),
+                ..
+            } => assert_eq!(
+                FrameError::UnsupportedEncoding(EncodingType::SbeLittleEndian),
+                e
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+        match events.recv().await.unwrap() {
+            SessionEvent::Message { message, .. } => {
+                assert_eq!("pong", message.get_field(58).as_str());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn bad_sofh_length_ends_the_connection() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let (ours, mut exchange) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let settings = TransportSettings {
+            framing: Framing::Sofh,
+            ..TransportSettings::default()
+        };
+        let _sender = spawn_session(id("Exchange"), settings, reader, writer, events_tx);
+
+        // a length short of the header, then what would be misread after it
+        let mut wire = BytesMut::from(&[0, 0, 0, 2, 0xF0, 0x00][..]);
+        encoder::encode_sofh(&from_exchange("0", "FIX.4.2"), &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+
+        match events.recv().await.unwrap() {
+            SessionEvent::Error {
+                error: SessionError::Framing(e),
+                ..
+            } => assert_eq!(FrameError::InvalidMessageLength(2), e),
+            other => panic!("unexpected {:?}", other),
+        }
+        match events.recv().await.unwrap() {
+            SessionEvent::StateChanged { state, .. } => assert_eq!(State::LoggedOut, state),
+            other => panic!("unexpected {:?}", other),
+        }
+        let mut rest = vec![];
+        assert_eq!(0, exchange.read_to_end(&mut rest).await.unwrap());
+    }
+
+    /// An address nothing listens on
+    fn dead_endpoint() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    fn connection(endpoints: &[&str], max_attempts: Option<u32>) -> ConnectionSettings {
+        let mut builder = SettingsBuilder::new(
+            BeginString::Fix42,
+            "Rocks".to_string(),
+            "Exchange".to_string(),
+            SessionType::Initiator,
+        );
+        for endpoint in endpoints {
+            builder.with_endpoint(endpoint);
+        }
+        builder
+            .with_reconnect(ReconnectSettings {
+                initial_backoff: Duration::from_millis(10),
+                jitter: 0.0,
+                max_attempts,
+                ..ReconnectSettings::default()
+            })
+            .build()
+            .connection_settings
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_backup_endpoint() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let primary = dead_endpoint();
+        let backup = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let backup_addr = backup.local_addr().unwrap().to_string();
+
+        let settings = connection(&[&primary, &backup_addr], None);
+        assert!(
+            connect_with_failover(&id("Exchange"), &settings, &events_tx)
+                .await
+                .is_ok()
+        );
+
+        match events.recv().await.unwrap() {
+            SessionEvent::Connecting {
+                endpoint, attempt, ..
+            } => assert_eq!((primary.as_str(), 1), (endpoint.as_str(), attempt)),
+            other => panic!("unexpected {:?}", other),
+        }
+        match events.recv().await.unwrap() {
+            SessionEvent::ConnectFailed {
+                attempt,
+                error: ConnectError::Io(_),
+                retry_in,
+                ..
+            } => {
+                assert_eq!(1, attempt);
+                assert_eq!(Some(Duration::from_millis(10)), retry_in);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match events.recv().await.unwrap() {
+            SessionEvent::Connecting {
+                endpoint, attempt, ..
+            } => assert_eq!((backup_addr.as_str(), 2), (endpoint.as_str(), attempt)),
+            other => panic!("unexpected {:?}", other),
+        }
+        match events.recv().await.unwrap() {
+            SessionEvent::Connected { endpoint, .. } => assert_eq!(backup_addr, endpoint),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let settings = connection(&[&dead_endpoint()], Some(2));
+        match connect_with_failover(&id("Exchange"), &settings, &events_tx).await {
+            Err(ConnectError::AttemptsExhausted(2)) => {}
+            Err(e) => panic!("unexpected {}", e),
+            Ok(_) => panic!("connected to a dead endpoint"),
+        }
+
+        let mut retries = vec![];
+        while let Ok(event) = events.try_recv() {
+            if let SessionEvent::ConnectFailed { retry_in, .. } = event {
+                retries.push(retry_in);
+            }
+        }
+        assert_eq!(vec![Some(Duration::from_millis(10)), None], retries);
+    }
+
+    #[tokio::test]
+    async fn initiator_reconnects_after_disconnect() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let exchange = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = exchange.local_addr().unwrap().to_string();
+        let sender = spawn_initiator(
+            id("Exchange"),
+            tag_value(),
+            connection(&[&addr], None),
+            events_tx,
+        );
+
+        // the exchange hangs up on the first connection
+        drop(exchange.accept().await.unwrap());
+        let (mut connection, _) = exchange.accept().await.unwrap();
+
+        let mut connects = 0;
+        while connects < 2 {
+            match events.recv().await.unwrap() {
+                SessionEvent::Connected { .. } => connects += 1,
+                SessionEvent::StateChanged { state, .. } => assert_eq!(State::LoggedOut, state),
+                _ => {}
+            }
+        }
+
+        sender.send(Message::of_type("0")).unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let frame = loop {
+            if let Some(frame) = cracker::next_frame(&mut buf).unwrap() {
+                break frame;
+            }
+            connection.read_buf(&mut buf).await.unwrap();
+        };
+        let heartbeat = cracker::crack_message(&frame).unwrap();
+        assert_eq!("0", heartbeat.get_field(35).as_str());
+    }
+
+    #[tokio::test]
+    async fn seq_nums_carry_on_across_reconnects() {
+        async fn logon_seq_nums(settings: TransportSettings) -> (String, Option<String>) {
+            let (events_tx, _events) = mpsc::channel(64);
+            let exchange = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = exchange.local_addr().unwrap().to_string();
+            let sender = spawn_initiator(
+                id("Exchange"),
+                settings,
+                connection(&[&addr], None),
+                events_tx,
+            );
+
+            // a Logon and a Heartbeat, then the exchange hangs up
+            let (mut first, _) = exchange.accept().await.unwrap();
+            sender.send(Message::of_type("A")).unwrap();
+            sender.send(Message::of_type("0")).unwrap();
+            let mut buf = BytesMut::with_capacity(1024);
+            assert_eq!(
+                1,
+                next_sent(&mut first, &mut buf).await.get_field(34).as_i32()
+            );
+            assert_eq!(
+                2,
+                next_sent(&mut first, &mut buf).await.get_field(34).as_i32()
+            );
+            drop(first);
+
+            let (mut second, _) = exchange.accept().await.unwrap();
+            sender.send(Message::of_type("A")).unwrap();
+            let mut buf = BytesMut::with_capacity(1024);
+            let logon = next_sent(&mut second, &mut buf).await;
+            (
+                logon.get_field(34).value_string(),
+                logon.get_field_safe(141).ok().map(Field::value_string),
+            )
+        }
+
+        assert_eq!(("3".to_string(), None), logon_seq_nums(tag_value()).await);
+        let reset = TransportSettings {
+            reset_on_disconnect: true,
+            ..TransportSettings::default()
+        };
+        assert_eq!(
+            ("1".to_string(), Some("Y".to_string())),
+            logon_seq_nums(reset).await
+        );
+    }
+
+    #[tokio::test]
+    async fn initiator_backs_off_until_logged_on() {
+        let (events_tx, _events) = mpsc::channel(64);
+        let exchange = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = exchange.local_addr().unwrap().to_string();
+        let _sender = spawn_initiator(
+            id("Exchange"),
+            tag_value(),
+            connection(&[&addr], None),
+            events_tx,
+        );
+
+        // the exchange hangs up on every connection before a Logon, so the
+        // waits of 10ms then 20ms grow rather than start over
+        drop(exchange.accept().await.unwrap());
+        let first = tokio::time::Instant::now();
+        drop(exchange.accept().await.unwrap());
+        let second = tokio::time::Instant::now();
+        drop(exchange.accept().await.unwrap());
+        assert!(second - first >= Duration::from_millis(10));
+        assert!(second.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn sub_ids_are_stamped_and_checked() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let (ours, mut exchange) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let session_id = SessionID {
+            sender_sub_id: Some("Desk1".to_string()),
+            ..id("Exchange")
+        };
+        let sender = spawn_session(session_id, tag_value(), reader, writer, events_tx);
+
+        sender.send(Message::of_type("0")).unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let frame = loop {
+            if let Some(frame) = cracker::next_frame(&mut buf).unwrap() {
+                break frame;
+            }
+            exchange.read_buf(&mut buf).await.unwrap();
+        };
+        let heartbeat = cracker::crack_message(&frame).unwrap();
+        assert_eq!("Desk1", heartbeat.get_field(50).as_str());
+
+        // a reply to another desk
+        let mut reply = from_exchange("0", "FIX.4.2");
+        reply.set_field(Field::String(57, "Desk2".to_string()));
+        let mut wire = BytesMut::new();
+        encoder::encode(&reply, &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+
+        match events.recv().await.unwrap() {
+            SessionEvent::Error {
+                error: SessionError::Header(e),
+                ..
+            } => assert_eq!("tag 57 was Desk2, expected Desk1", e.to_string()),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    /// Read the next tag=value message the session sends
+    async fn next_sent<R: AsyncRead + Unpin>(exchange: &mut R, buf: &mut BytesMut) -> Message {
+        loop {
+            if let Some(frame) = cracker::next_frame(buf).unwrap() {
+                return cracker::crack_message(&frame).unwrap();
+            }
+            exchange.read_buf(buf).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn wrong_comp_id_is_rejected_and_logged_out() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let (ours, mut exchange) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let _sender = spawn_session(id("Exchange"), tag_value(), reader, writer, events_tx);
+
+        let mut order = from_exchange("D", "FIX.4.2");
+        order.set_field(Field::Int(34, 7));
+        order.set_field(Field::String(49, "Imposter".to_string()));
+        let mut wire = BytesMut::new();
+        encoder::encode(&order, &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+
+        match events.recv().await.unwrap() {
+            SessionEvent::Error {
+                error: SessionError::Header(e),
+                ..
+            } => assert_eq!(49, e.tag),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let mut buf = BytesMut::with_capacity(1024);
+        let reject = next_sent(&mut exchange, &mut buf).await;
+        assert_eq!("3", reject.get_field(35).as_str());
+        assert_eq!(1, reject.get_field(34).as_i32());
+        assert_eq!("7", reject.get_field(45).value_string());
+        assert_eq!(49, reject.get_field(371).as_i32());
+        assert_eq!("D", reject.get_field(372).as_str());
+        assert_eq!(9, reject.get_field(373).as_i32());
+        let logout = next_sent(&mut exchange, &mut buf).await;
+        assert_eq!("5", logout.get_field(35).as_str());
+        assert_eq!(
+            "unexpected header: tag 49 was Imposter, expected Exchange",
+            logout.get_field(58).as_str()
+        );
+
+        // then the session hangs up
+        assert_eq!(0, exchange.read_buf(&mut buf).await.unwrap());
+        match events.recv().await.unwrap() {
+            SessionEvent::StateChanged { state, .. } => assert_eq!(State::LoggedOut, state),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_sending_time_is_rejected() {
+        let (events_tx, mut events) = mpsc::channel(16);
+        let (ours, mut exchange) = tokio::io::duplex(1024);
+        let (reader, writer) = tokio::io::split(ours);
+        let settings = TransportSettings {
+            max_latency: Some(Duration::from_secs(120)),
+            ..TransportSettings::default()
+        };
+        let sender = spawn_session(id("Exchange"), settings, reader, writer, events_tx);
+
+        // what the session sends carries a current SendingTime
+        sender.send(Message::of_type("0")).unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let heartbeat = next_sent(&mut exchange, &mut buf).await;
+        let sent_at = parse_utc_timestamp(heartbeat.get_field(52).as_str()).unwrap();
+        assert!(sent_at.elapsed().unwrap_or_default() < Duration::from_secs(60));
+
+        let mut fresh = from_exchange("0", "FIX.4.2");
+        fresh.set_field(Field::String(52, format_utc_timestamp(SystemTime::now())));
+        let mut stale = from_exchange("0", "FIX.4.2");
+        stale.set_field(Field::String(
+            52,
+            format_utc_timestamp(SystemTime::now() - Duration::from_secs(600)),
+        ));
+        let mut wire = BytesMut::new();
+        encoder::encode(&fresh, &mut wire);
+        encoder::encode(&stale, &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            SessionEvent::Message { .. }
+        ));
+        match events.recv().await.unwrap() {
+            SessionEvent::Error {
+                error: SessionError::SendingTime(SendingTimeProblem::Stale { max_latency, .. }),
+                ..
+            } => assert_eq!(Duration::from_secs(120), max_latency),
+            other => panic!("unexpected {:?}", other),
+        }
+        let reject = next_sent(&mut exchange, &mut buf).await;
+        assert_eq!(52, reject.get_field(371).as_i32());
+        assert_eq!(10, reject.get_field(373).as_i32());
+        let logout = next_sent(&mut exchange, &mut buf).await;
+        assert_eq!("5", logout.get_field(35).as_str());
+    }
+}
@@ -0,0 +1,464 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::server::{VerifierBuilderError, WebPkiClientVerifier};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, ServerConfig, SignatureScheme};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+use crate::session::settings::TlsSettings;
+
+/// Build the connector an initiator wraps its TCP stream with.
+///
+/// The acceptor's certificate is checked against `ca_file`, or the webpki
+/// roots without one, unless `verify` is off. A client certificate is
+/// presented when both `cert_file` and `key_file` are set.
+///
+/// # Errors
+///
+/// If a file can't be read or holds no certificate or key, or rustls
+/// refuses the configuration.
+pub fn connector(settings: &TlsSettings) -> Result<TlsConnector, TlsError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(TlsError::Rustls)?;
+    let builder = if settings.verify {
+        let roots = match &settings.ca_file {
+            Some(ca_file) => load_roots(ca_file)?,
+            None => RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        };
+        builder.with_root_certificates(roots)
+    } else {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+    };
+    let config = match (&settings.cert_file, &settings.key_file) {
+        (Some(cert_file), Some(key_file)) => builder
+            .with_client_auth_cert(load_certs(cert_file)?, load_key(key_file)?)
+            .map_err(TlsError::Rustls)?,
+        (None, None) => builder.with_no_client_auth(),
+        _ => return Err(TlsError::MissingIdentity),
+    };
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// Build the acceptor a listener wraps incoming TCP streams with.
+///
+/// `cert_file` and `key_file` are the acceptor's identity. With a `ca_file`
+/// and `verify` on, initiators must present a certificate issued by it.
+///
+/// # Errors
+///
+/// If the identity is missing, a file can't be read or holds no certificate
+/// or key, or rustls refuses the configuration.
+pub fn acceptor(settings: &TlsSettings) -> Result<TlsAcceptor, TlsError> {
+    let (Some(cert_file), Some(key_file)) = (&settings.cert_file, &settings.key_file) else {
+        return Err(TlsError::MissingIdentity);
+    };
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(TlsError::Rustls)?;
+    let builder = match &settings.ca_file {
+        Some(ca_file) if settings.verify => {
+            let verifier = WebPkiClientVerifier::builder_with_provider(
+                Arc::new(load_roots(ca_file)?),
+                provider,
+            )
+            .build()
+            .map_err(TlsError::ClientVerifier)?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        _ => builder.with_no_client_auth(),
+    };
+    let config = builder
+        .with_single_cert(load_certs(cert_file)?, load_key(key_file)?)
+        .map_err(TlsError::Rustls)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// The name an initiator sends as SNI when connecting to `addr`
+///
+/// # Errors
+///
+/// If neither `server_name` nor the host of `addr` is a DNS name or IP address.
+pub fn server_name(settings: &TlsSettings, addr: &str) -> Result<ServerName<'static>, TlsError> {
+    let name = match &settings.server_name {
+        Some(name) => name.as_str(),
+        None => addr
+            .rsplit_once(':')
+            .map_or(addr, |(host, _port)| host)
+            .trim_start_matches('[')
+            .trim_end_matches(']'),
+    };
+    ServerName::try_from(name.to_string())
+        .map_err(|_| TlsError::InvalidServerName(name.to_string()))
+}
+
+fn load_roots(path: &Path) -> Result<RootCertStore, TlsError> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(cert).map_err(TlsError::Rustls)?;
+    }
+    Ok(roots)
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TlsError::Io(path.to_path_buf(), e))?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(path.to_path_buf()));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>, TlsError> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .map_err(|e| TlsError::Io(path.to_path_buf(), e))?
+        .ok_or_else(|| TlsError::NoPrivateKey(path.to_path_buf()))
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, TlsError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| TlsError::Io(path.to_path_buf(), e))
+}
+
+/// Accepts any certificate the acceptor presents, still checking that the
+/// handshake is signed by it
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[derive(Debug)]
+pub enum TlsError {
+    Io(PathBuf, io::Error),
+    NoCertificates(PathBuf),
+    NoPrivateKey(PathBuf),
+    /// Only one of a certificate and its key was given, or an acceptor has neither
+    MissingIdentity,
+    InvalidServerName(String),
+    ClientVerifier(VerifierBuilderError),
+    Rustls(rustls::Error),
+    Handshake(io::Error),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsError::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            TlsError::NoCertificates(path) => {
+                write!(f, "no certificates found in {}", path.display())
+            }
+            TlsError::NoPrivateKey(path) => write!(f, "no private key found in {}", path.display()),
+            TlsError::MissingIdentity => {
+                write!(f, "both a certificate and its private key are needed")
+            }
+            TlsError::InvalidServerName(name) => write!(f, "{} is not a valid server name", name),
+            TlsError::ClientVerifier(e) => write!(f, "could not verify client certificates: {}", e),
+            TlsError::Rustls(e) => write!(f, "{}", e),
+            TlsError::Handshake(e) => write!(f, "TLS handshake failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use bytes::BytesMut;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::mpsc;
+
+    use crate::cracker;
+    use crate::encoder;
+    use crate::model::generated::fields::Field;
+    use crate::model::message::Message;
+    use crate::session::channel::SessionEvent;
+    use crate::session::settings::{ConnectionSettings, SessionType, SettingsBuilder, TlsSettings};
+    use crate::session::SessionID;
+    use crate::transport::tls::TlsError;
+    use crate::transport::{connect, spawn_session, ConnectError, Listener, TransportSettings};
+
+    struct Identity {
+        cert_file: PathBuf,
+        key_file: PathBuf,
+    }
+
+    /// A self-signed certificate for localhost, written to the temp directory
+    fn identity(name: &str) -> Identity {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let dir = std::env::temp_dir().join(format!("fix-oxide-tls-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cert_file = dir.join(format!("{}.crt", name));
+        let key_file = dir.join(format!("{}.key", name));
+        std::fs::write(&cert_file, certified.cert.pem()).unwrap();
+        std::fs::write(&key_file, certified.key_pair.serialize_pem()).unwrap();
+        Identity {
+            cert_file,
+            key_file,
+        }
+    }
+
+    fn connection(tls: TlsSettings) -> ConnectionSettings {
+        SettingsBuilder::new(
+            crate::model::BeginString::Fix44,
+            "Rocks".to_string(),
+            "Exchange".to_string(),
+            SessionType::Initiator,
+        )
+        .with_tls(tls)
+        .build()
+        .connection_settings
+    }
+
+    async fn listen(tls: TlsSettings) -> (Listener, String) {
+        let listener = Listener::bind("127.0.0.1:0", &connection(tls))
+            .await
+            .unwrap();
+        let addr = format!("localhost:{}", listener.local_addr().unwrap().port());
+        (listener, addr)
+    }
+
+    #[tokio::test]
+    async fn session_over_tls_to_self_signed_acceptor() {
+        let acceptor = identity("session-acceptor");
+        let (listener, addr) = listen(TlsSettings {
+            cert_file: Some(acceptor.cert_file.clone()),
+            key_file: Some(acceptor.key_file),
+            ..TlsSettings::default()
+        })
+        .await;
+        let accepted = tokio::spawn(async move { listener.accept().await.unwrap().0 });
+
+        let stream = connect(
+            &addr,
+            &connection(TlsSettings {
+                ca_file: Some(acceptor.cert_file),
+                server_name: Some("localhost".to_string()),
+                ..TlsSettings::default()
+            }),
+        )
+        .await
+        .unwrap();
+        let mut exchange = accepted.await.unwrap();
+
+        let (events_tx, mut events) = mpsc::channel(16);
+        let session_id = SessionID {
+            begin_string: "FIX.4.4".to_string(),
+            sender_comp_id: "Rocks".to_string(),
+            target_comp_id: "Exchange".to_string(),
+            ..SessionID::default()
+        };
+        let (reader, writer) = tokio::io::split(stream);
+        let sender = spawn_session(
+            session_id,
+            TransportSettings::default(),
+            reader,
+            writer,
+            events_tx,
+        );
+
+        sender.send(Message::of_type("0")).unwrap();
+        let mut buf = BytesMut::with_capacity(1024);
+        let frame = loop {
+            if let Some(frame) = cracker::next_frame(&mut buf).unwrap() {
+                break frame;
+            }
+            exchange.read_buf(&mut buf).await.unwrap();
+        };
+        let heartbeat = cracker::crack_message(&frame).unwrap();
+        assert_eq!("Exchange", heartbeat.get_field(56).as_str());
+
+        let mut reply = Message::of_type("0");
+        reply.set_field(Field::String(8, "FIX.4.4".to_string()));
+        reply.set_field(Field::String(49, "Exchange".to_string()));
+        reply.set_field(Field::String(56, "Rocks".to_string()));
+        reply.set_field(Field::String(58, "pong".to_string()));
+        let mut wire = BytesMut::new();
+        encoder::encode(&reply, &mut wire);
+        exchange.write_all(&wire).await.unwrap();
+        exchange.flush().await.unwrap();
+
+        match events.recv().await.unwrap() {
+            SessionEvent::Message { message, .. } => {
+                assert_eq!("pong", message.get_field(58).as_str());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn initiator_verifies_acceptor_unless_told_not_to() {
+        let acceptor = identity("verify-acceptor");
+        let (listener, addr) = listen(TlsSettings {
+            cert_file: Some(acceptor.cert_file),
+            key_file: Some(acceptor.key_file),
+            ..TlsSettings::default()
+        })
+        .await;
+        tokio::spawn(async move {
+            loop {
+                let _ = listener.accept().await;
+            }
+        });
+
+        // the self-signed certificate isn't among the webpki roots
+        match connect(&addr, &connection(TlsSettings::default())).await {
+            Err(ConnectError::Tls(TlsError::Handshake(_))) => {}
+            Err(e) => panic!("unexpected {}", e),
+            Ok(_) => panic!("connected to an untrusted acceptor"),
+        }
+
+        let unverified = TlsSettings {
+            verify: false,
+            ..TlsSettings::default()
+        };
+        assert!(connect(&addr, &connection(unverified)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn acceptor_gives_up_on_stalled_handshake() {
+        let acceptor = identity("stalled-acceptor");
+        let (listener, addr) = listen(TlsSettings {
+            cert_file: Some(acceptor.cert_file.clone()),
+            key_file: Some(acceptor.key_file),
+            handshake_timeout: Duration::from_millis(100),
+            ..TlsSettings::default()
+        })
+        .await;
+
+        // connects, then never says hello
+        let _stalled = tokio::net::TcpStream::connect(addr.replace("localhost", "127.0.0.1"))
+            .await
+            .unwrap();
+        match listener.accept().await {
+            Err(ConnectError::Tls(TlsError::Handshake(e))) => {
+                assert_eq!(std::io::ErrorKind::TimedOut, e.kind());
+            }
+            Err(e) => panic!("unexpected {}", e),
+            Ok(_) => panic!("accepted a connection without a handshake"),
+        }
+
+        // and the next initiator is served
+        let initiator = TlsSettings {
+            ca_file: Some(acceptor.cert_file),
+            ..TlsSettings::default()
+        };
+        let initiator = connection(initiator);
+        let (accepted, connected) = tokio::join!(listener.accept(), connect(&addr, &initiator));
+        assert!(accepted.is_ok());
+        assert!(connected.is_ok());
+    }
+
+    #[tokio::test]
+    async fn acceptor_with_ca_requires_client_certificate() {
+        let acceptor = identity("mtls-acceptor");
+        let initiator = identity("mtls-initiator");
+        let (listener, addr) = listen(TlsSettings {
+            ca_file: Some(initiator.cert_file.clone()),
+            cert_file: Some(acceptor.cert_file.clone()),
+            key_file: Some(acceptor.key_file),
+            ..TlsSettings::default()
+        })
+        .await;
+
+        let anonymous = TlsSettings {
+            ca_file: Some(acceptor.cert_file.clone()),
+            ..TlsSettings::default()
+        };
+        let (accepted, _) = tokio::join!(listener.accept(), async {
+            if let Ok(mut stream) = connect(&addr, &connection(anonymous)).await {
+                // TLS 1.3 initiators only learn of the rejection on their next read
+                let _ = stream.read_u8().await;
+            }
+        });
+        assert!(matches!(
+            accepted,
+            Err(ConnectError::Tls(TlsError::Handshake(_)))
+        ));
+
+        let authenticated = TlsSettings {
+            ca_file: Some(acceptor.cert_file),
+            cert_file: Some(initiator.cert_file),
+            key_file: Some(initiator.key_file),
+            ..TlsSettings::default()
+        };
+        let authenticated = connection(authenticated);
+        let (accepted, connected) = tokio::join!(listener.accept(), connect(&addr, &authenticated));
+        assert!(accepted.is_ok());
+        assert!(connected.is_ok());
+    }
+
+    #[test]
+    fn half_an_identity_is_rejected() {
+        let initiator = identity("half-initiator");
+        let settings = TlsSettings {
+            cert_file: Some(initiator.cert_file),
+            ..TlsSettings::default()
+        };
+        assert!(matches!(
+            super::connector(&settings),
+            Err(TlsError::MissingIdentity)
+        ));
+        assert!(matches!(
+            super::acceptor(&TlsSettings::default()),
+            Err(TlsError::MissingIdentity)
+        ));
+    }
+}